@@ -0,0 +1,28 @@
+use alloc::format;
+use alloc::vec::Vec;
+use noli::net::lookup_host;
+use noli::net::IpV4Addr;
+use saba_core::dns::Resolver;
+use saba_core::error::Error;
+
+/// Resolves host names with the DNS client of noli.
+#[derive(Debug, Clone, Default)]
+pub struct NoliResolver;
+
+impl Resolver for NoliResolver {
+    type Addr = IpV4Addr;
+
+    fn resolve(&mut self, host: &str) -> Result<Vec<IpV4Addr>, Error> {
+        let ips = match lookup_host(host) {
+            Ok(ips) => ips,
+            Err(e) => {
+                return Err(Error::Dns(format!("Failed to find IP addresses of {}: {:#?}", host, e)))
+            }
+        };
+
+        if ips.is_empty() {
+            return Err(Error::Dns(format!("Failed to find IP addresses of {}", host)));
+        }
+        Ok(ips)
+    }
+}
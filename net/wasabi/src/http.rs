@@ -1,82 +1,122 @@
-extern crate alloc;
-
+use crate::dns::NoliResolver;
 use alloc::format;
-use alloc::string::String;
+use alloc::rc::Rc;
 use alloc::string::ToString;
-use alloc::vec::Vec;
+use core::cell::RefCell;
 use noli::net::TcpStream;
+use saba_core::dns::DnsCache;
 use saba_core::error::Error;
-use saba_core::http::HttpResponse;
-use noli::net::lookup_host;
+use saba_core::http::Transport;
 use noli::net::SocketAddr;
 
-pub struct HttpClient {}
+pub struct TcpTransport {
+    stream: Option<TcpStream>,
+    dns_cache: Rc<RefCell<DnsCache<NoliResolver>>>,
+    clock: fn() -> u64,
+}
 
-impl HttpClient {
-    pub fn new() -> Self {
-        Self {}
+impl TcpTransport {
+    /// Resolves host names through `dns_cache`, which is shared between requests so that the
+    /// same host isn't resolved again. `clock` gives the current time in the ticks its TTL is
+    /// counted in.
+    pub fn new(dns_cache: Rc<RefCell<DnsCache<NoliResolver>>>, clock: fn() -> u64) -> Self {
+        Self {
+            stream: None,
+            dns_cache,
+            clock,
+        }
     }
 
-    pub fn get(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
-        let ips = match lookup_host(&host) {
-            Ok(ips) => ips,
-            Err(e) => {
-                return Err(Error::Network(format!("Failed to find IP addresses: {:#?}", e)))
-            },
-        };
-
-        if ips.len() < 1 {
-            return Err(Error::Network("Failed to find IP addresses".to_string()))
+    fn stream(&mut self) -> Result<&mut TcpStream, Error> {
+        match &mut self.stream {
+            Some(stream) => Ok(stream),
+            None => Err(Error::Network("TCP stream is not connected".to_string())),
         }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn connect(&mut self, host: &str, port: u16) -> Result<(), Error> {
+        let ips = self.dns_cache.borrow_mut().lookup(host, (self.clock)())?;
 
         let socket_addr: SocketAddr = (ips[0], port).into();
 
-        let mut stream = match TcpStream::connect(socket_addr) {
+        let stream = match TcpStream::connect(socket_addr) {
             Ok(stream) => stream,
-            Err(_) => {
-                return Err(Error::Network("Failed to connect to TCP stream".to_string()))
-            },
+            Err(e) => return Err(connect_error(e)),
         };
+        self.stream = Some(stream);
 
-        let mut request = String::from("GET /");
-        request.push_str(&path);
-        request.push_str(" HTTP/1.1\n");
+        Ok(())
+    }
 
-        request.push_str("Host: ");
-        request.push_str(&host);
-        request.push('\n');
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        match self.stream()?.write(buf) {
+            Ok(bytes) => Ok(bytes),
+            Err(_) => Err(Error::Network("Failed to send a request to TCP stream".to_string())),
+        }
+    }
 
-        request.push_str("Accept: text/html\n");
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self.stream()?.read(buf) {
+            Ok(bytes) => Ok(bytes),
+            Err(e) => Err(read_error(e)),
+        }
+    }
+}
 
-        request.push_str("Connection: close\n");
+/// noli はタイムアウトや接続拒否を専用のエラーで返さないので、エラーの内容から判断する
+fn error_message_contains(error: &noli::error::Error, patterns: &[&str]) -> bool {
+    let message = format!("{:?}", error).to_ascii_lowercase();
+    patterns.iter().any(|pattern| message.contains(pattern))
+}
 
-        request.push('\n');
+fn is_timeout(error: &noli::error::Error) -> bool {
+    error_message_contains(error, &["timeout", "timed out"])
+}
 
-        let _bytes_written = match stream.write(request.as_bytes()) {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return Err(Error::Network("Failed to send a request to TCP stream".to_string()))
-            },
-        };
+fn is_refused(error: &noli::error::Error) -> bool {
+    error_message_contains(error, &["refused"])
+}
 
-        let mut received = Vec::new();
-        loop {
-            let mut buf = [0u8; 4096];
-            let bytes_read = match stream.read(&mut buf) {
-                Ok(bytes) => bytes,
-                Err(_) => {
-                    return Err(Error::Network("Failed to receive a request from TCP stream".to_string()))
-                },
-            };
-            if bytes_read == 0 {
-                break;
-            }
-            received.extend_from_slice(&buf[..bytes_read]);
-        }
+/// Only a refused connection becomes `Error::ConnectionRefused`, which is retried. Other failures
+/// such as unknown OS errors are reported as `Error::Network`.
+fn connect_error(error: noli::error::Error) -> Error {
+    if is_timeout(&error) {
+        Error::Timeout
+    } else if is_refused(&error) {
+        Error::ConnectionRefused
+    } else {
+        Error::Network(format!("Failed to connect to TCP stream: {:?}", error))
+    }
+}
 
-        match core::str::from_utf8(&received) {
-            Ok(response) => HttpResponse::new(response.to_string()),
-            Err(e) => Err(Error::Network(format!("Invalid received response: {}", e))),
-        }
+fn read_error(error: noli::error::Error) -> Error {
+    if is_timeout(&error) {
+        Error::Timeout
+    } else {
+        Error::Network("Failed to receive a request from TCP stream".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_errors() {
+        use noli::error::Error as OsError;
+
+        assert_eq!(Error::Timeout, connect_error(OsError::Failed("connect timed out")));
+        assert_eq!(Error::ConnectionRefused, connect_error(OsError::Failed("connection refused")));
+        assert!(matches!(
+            connect_error(OsError::Failed("no route to host")),
+            Error::Network(_)
+        ));
+        assert_eq!(Error::Timeout, read_error(OsError::Failed("read timeout")));
+        assert_eq!(
+            Error::Network("Failed to receive a request from TCP stream".to_string()),
+            read_error(OsError::Failed("connection reset"))
+        );
     }
 }
\ No newline at end of file
@@ -1,3 +1,6 @@
 #![no_std]
 
-pub mod http;
\ No newline at end of file
+extern crate alloc;
+
+pub mod dns;
+pub mod http;
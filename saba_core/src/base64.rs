@@ -0,0 +1,148 @@
+use crate::error::Error;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+static ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// https://datatracker.ietf.org/doc/html/rfc4648#section-4
+pub fn encode(input: &[u8]) -> String {
+    let mut result = String::new();
+
+    for chunk in input.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        result.push(ALPHABET[(triple >> 18) as usize & 0x3f] as char);
+        result.push(ALPHABET[(triple >> 12) as usize & 0x3f] as char);
+        if chunk.len() > 1 {
+            result.push(ALPHABET[(triple >> 6) as usize & 0x3f] as char);
+        } else {
+            result.push('=');
+        }
+        if chunk.len() > 2 {
+            result.push(ALPHABET[triple as usize & 0x3f] as char);
+        } else {
+            result.push('=');
+        }
+    }
+
+    result
+}
+
+fn decode_char(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc4648#section-4
+pub fn decode(input: &str) -> Result<Vec<u8>, Error> {
+    let bytes = input.as_bytes();
+    if bytes.len() % 4 != 0 {
+        return Err(Error::UnexpectedInput(format!(
+            "base64 input length should be a multiple of 4 but got {}",
+            bytes.len()
+        )));
+    }
+
+    let mut result = Vec::new();
+    let chunk_count = bytes.len() / 4;
+
+    for (i, chunk) in bytes.chunks(4).enumerate() {
+        // パディングは最後のチャンクの末尾にのみ許される
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && i != chunk_count - 1) {
+            return Err(Error::UnexpectedInput(format!(
+                "invalid base64 padding: {}",
+                input
+            )));
+        }
+
+        let mut triple = 0u32;
+        for &c in &chunk[..4 - padding] {
+            match decode_char(c) {
+                Some(v) => triple = (triple << 6) | v,
+                None => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid base64 character: {}",
+                        c as char
+                    )))
+                }
+            }
+        }
+        triple <<= 6 * padding as u32;
+
+        result.push((triple >> 16) as u8);
+        if padding < 2 {
+            result.push((triple >> 8) as u8);
+        }
+        if padding < 1 {
+            result.push(triple as u8);
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // https://datatracker.ietf.org/doc/html/rfc4648#section-10
+    const VECTORS: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn test_encode() {
+        for (input, expected) in VECTORS {
+            assert_eq!(expected, encode(input.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        for (expected, input) in VECTORS {
+            assert_eq!(Ok(expected.as_bytes().to_vec()), decode(input));
+        }
+    }
+
+    #[test]
+    fn test_binary() {
+        let bytes = [0u8, 0xff, 0x10, 0x80];
+        assert_eq!("AP8QgA==", encode(&bytes));
+        assert_eq!(Ok(bytes.to_vec()), decode("AP8QgA=="));
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        assert!(decode("Zm9*").is_err());
+        assert!(decode("Zm 9").is_err());
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        assert!(decode("Zm9").is_err());
+    }
+
+    #[test]
+    fn test_invalid_padding() {
+        assert!(decode("Z===").is_err());
+        assert!(decode("Zg==Zm9v").is_err());
+        assert!(decode("Zm=v").is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,144 @@
+use crate::url::Url;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub static BOOKMARKS_STORAGE_KEY: &str = "bookmarks";
+static BOOKMARKS_HEADER: &str = "saba-bookmarks 1";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bookmarks {
+    entries: Vec<(String, Url)>,
+}
+
+impl Bookmarks {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a bookmark. If `url` is already bookmarked, its title is updated instead.
+    pub fn add(&mut self, title: String, url: Url) {
+        for entry in &mut self.entries {
+            if entry.1 == url {
+                entry.0 = title;
+                return;
+            }
+        }
+        self.entries.push((title, url));
+    }
+
+    /// Returns true if `url` was bookmarked.
+    pub fn remove(&mut self, url: &Url) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.1 != *url);
+        len != self.entries.len()
+    }
+
+    pub fn list(&self) -> Vec<(String, Url)> {
+        self.entries.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Serializes the bookmarks as a header line followed by one `<url>\t<title>` per line.
+    pub fn serialize(&self) -> String {
+        let mut result = BOOKMARKS_HEADER.to_string();
+        result.push('\n');
+        for (title, url) in &self.entries {
+            result.push_str(&url.to_string());
+            result.push('\t');
+            result.push_str(&title.replace(['\t', '\n'], " "));
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Restores bookmarks from the output of `serialize`. Malformed input yields no bookmarks.
+    pub fn deserialize(data: &str) -> Self {
+        let mut lines = data.lines();
+        if lines.next() != Some(BOOKMARKS_HEADER) {
+            return Self::new();
+        }
+
+        let mut bookmarks = Self::new();
+        for line in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let (url, title) = match line.split_once('\t') {
+                Some(entry) => entry,
+                None => return Self::new(),
+            };
+            let url = match Url::new(url.to_string()).parse() {
+                Ok(url) => url,
+                Err(_) => return Self::new(),
+            };
+            bookmarks.add(title.to_string(), url);
+        }
+        bookmarks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::new(s.to_string()).parse().expect("failed to parse url")
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.add("Example".to_string(), url("http://example.com"));
+        bookmarks.add("Index".to_string(), url("http://example.com/index.html"));
+        assert_eq!(2, bookmarks.len());
+
+        assert!(bookmarks.remove(&url("http://example.com")));
+        assert!(!bookmarks.remove(&url("http://example.com")));
+        assert_eq!(
+            [("Index".to_string(), url("http://example.com/index.html"))].to_vec(),
+            bookmarks.list()
+        );
+    }
+
+    #[test]
+    fn test_add_duplicate() {
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.add("Old".to_string(), url("http://example.com"));
+        bookmarks.add("New".to_string(), url("http://example.com"));
+        assert_eq!(
+            [("New".to_string(), url("http://example.com"))].to_vec(),
+            bookmarks.list()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.add("Example Domain".to_string(), url("http://example.com"));
+        bookmarks.add("".to_string(), url("http://example.com:8888/index.html?a=123"));
+
+        let data = bookmarks.serialize();
+        assert_eq!(
+            "saba-bookmarks 1\nhttp://example.com\tExample Domain\nhttp://example.com:8888/index.html?a=123\t\n",
+            data
+        );
+        assert_eq!(bookmarks, Bookmarks::deserialize(&data));
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert!(Bookmarks::deserialize("").is_empty());
+        assert!(Bookmarks::deserialize("http://example.com\tExample\n").is_empty());
+        assert!(Bookmarks::deserialize("saba-bookmarks 1\nhttp://example.com\n").is_empty());
+        assert!(Bookmarks::deserialize("saba-bookmarks 1\nexample.com\tExample\n").is_empty());
+    }
+}
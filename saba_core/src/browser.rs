@@ -1,24 +1,62 @@
+use crate::bookmarks::{Bookmarks, BOOKMARKS_STORAGE_KEY};
+use crate::cookie::CookieJar;
+use crate::display_item::DisplayItem;
+use crate::error::Error;
+use crate::event_log::EventLog;
+use crate::http::CancelFlag;
+use crate::http::ResponseCache;
+use crate::history::{History, HISTORY_STORAGE_KEY};
+use crate::renderer::dom::node::Node;
 use crate::renderer::page::Page;
+use crate::storage::Storage;
+use crate::url::Url;
 use alloc::rc::Rc;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+/// An empty page that is shown without any network access.
+/// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#about:blank
+pub static ABOUT_BLANK: &str = "about:blank";
+
+/// ページをまたいで再検証のために残しておくレスポンスの数
+const RESPONSE_CACHE_ENTRIES: usize = 32;
+
 #[derive(Debug, Clone)]
 pub struct Browser {
     active_page_index: usize,
     pages: Vec<Rc<RefCell<Page>>>,
+    history: History,
+    bookmarks: Bookmarks,
+    cookie_jar: Rc<RefCell<CookieJar>>,
+    response_cache: Rc<RefCell<ResponseCache>>,
+    cancel_flag: CancelFlag,
+    home_url: String,
+    event_log: Rc<RefCell<EventLog>>,
+    /// 履歴とブックマークを保存する場所。設定されるまでは、セッションの間だけ残る
+    storage: Option<Rc<RefCell<dyn Storage>>>,
 }
 
 impl Browser {
     pub fn new() -> Rc<RefCell<Self>> {
         let mut page = Page::new();
+        let event_log = Rc::new(RefCell::new(EventLog::default()));
 
         let browser = Rc::new(RefCell::new(Self {
             active_page_index: 0,
             pages: Vec::new(),
+            history: History::new(),
+            bookmarks: Bookmarks::new(),
+            cookie_jar: Rc::new(RefCell::new(CookieJar::new())),
+            response_cache: Rc::new(RefCell::new(ResponseCache::new(RESPONSE_CACHE_ENTRIES))),
+            cancel_flag: CancelFlag::new(),
+            home_url: ABOUT_BLANK.to_string(),
+            event_log: event_log.clone(),
+            storage: None,
         }));
 
         page.set_browser(Rc::downgrade(&browser));
+        page.set_event_log(event_log);
         browser.borrow_mut().pages.push(Rc::new(RefCell::new(page)));
 
         browser
@@ -27,4 +65,359 @@ impl Browser {
     pub fn current_page(&self) -> Rc<RefCell<Page>> {
         self.pages[self.active_page_index].clone()
     }
-}
\ No newline at end of file
+
+    /// Returns the document of the current page, or None before any page is loaded.
+    pub fn document(&self) -> Option<Rc<RefCell<Node>>> {
+        self.current_page().borrow().document()
+    }
+
+    /// Renders `html` in the current page without any network access.
+    pub fn load_from_str(&mut self, html: &str) {
+        self.current_page().borrow_mut().load_from_str(html);
+    }
+
+    /// Returns what the current page paints, in painting order.
+    pub fn display_list(&self) -> Vec<DisplayItem> {
+        self.current_page().borrow().display_items()
+    }
+
+    /// Returns the cookie jar shared by all pages, to be passed to `HttpClient::with_cookie_jar`.
+    pub fn cookie_jar(&self) -> Rc<RefCell<CookieJar>> {
+        self.cookie_jar.clone()
+    }
+
+    /// Returns the response cache shared by all pages, to be passed to `HttpClient::with_cache`.
+    pub fn response_cache(&self) -> Rc<RefCell<ResponseCache>> {
+        self.response_cache.clone()
+    }
+
+    /// Returns the flag that stops the load in progress, to be passed to
+    /// `HttpClient::with_cancel_flag`.
+    pub fn cancel_flag(&self) -> CancelFlag {
+        self.cancel_flag.clone()
+    }
+
+    /// Returns the log of navigations and page loads shared by all pages, to be dumped for
+    /// troubleshooting.
+    pub fn event_log(&self) -> Rc<RefCell<EventLog>> {
+        self.event_log.clone()
+    }
+
+    /// Returns the URL loaded at startup and by the home action. It's `about:blank` by default.
+    pub fn home_url(&self) -> String {
+        self.home_url.clone()
+    }
+
+    pub fn set_home_url(&mut self, url: String) {
+        self.home_url = url;
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Adds `url` to the history and saves the history to the storage, if any.
+    pub fn push_history(&mut self, url: String) -> Result<(), Error> {
+        self.history.push(url);
+        match &self.storage {
+            Some(storage) => self.save_history(&mut *storage.borrow_mut()),
+            None => Ok(()),
+        }
+    }
+
+    /// Restores the history and the bookmarks from `storage` and saves them there every time
+    /// they change. The platform calls this at startup with a storage backed by its filesystem.
+    pub fn set_storage(&mut self, storage: Rc<RefCell<dyn Storage>>) {
+        self.load_history(&*storage.borrow());
+        self.load_bookmarks(&*storage.borrow());
+        self.storage = Some(storage);
+    }
+
+    /// Restores the history saved by `save_history`. A missing or corrupt history yields an
+    /// empty history.
+    pub fn load_history(&mut self, storage: &dyn Storage) {
+        self.history = match storage.load(HISTORY_STORAGE_KEY) {
+            Some(data) => History::deserialize(&data),
+            None => History::new(),
+        };
+    }
+
+    pub fn save_history(&self, storage: &mut dyn Storage) -> Result<(), Error> {
+        storage.save(HISTORY_STORAGE_KEY, &self.history.serialize())
+    }
+
+    pub fn bookmarks(&self) -> &Bookmarks {
+        &self.bookmarks
+    }
+
+    /// Bookmarks `url`, or updates its title if it's already bookmarked, and saves the bookmarks
+    /// to the storage, if any.
+    pub fn add_bookmark(&mut self, title: String, url: Url) -> Result<(), Error> {
+        self.bookmarks.add(title, url);
+        match &self.storage {
+            Some(storage) => self.save_bookmarks(&mut *storage.borrow_mut()),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the bookmark of `url` and saves the bookmarks to the storage, if any. Returns
+    /// false if `url` wasn't bookmarked.
+    pub fn remove_bookmark(&mut self, url: &Url) -> Result<bool, Error> {
+        if !self.bookmarks.remove(url) {
+            return Ok(false);
+        }
+        match &self.storage {
+            Some(storage) => self.save_bookmarks(&mut *storage.borrow_mut()).map(|_| true),
+            None => Ok(true),
+        }
+    }
+
+    /// Restores the bookmarks saved by `save_bookmarks`. Missing or corrupt bookmarks yield no
+    /// bookmarks.
+    pub fn load_bookmarks(&mut self, storage: &dyn Storage) {
+        self.bookmarks = match storage.load(BOOKMARKS_STORAGE_KEY) {
+            Some(data) => Bookmarks::deserialize(&data),
+            None => Bookmarks::new(),
+        };
+    }
+
+    pub fn save_bookmarks(&self, storage: &mut dyn Storage) -> Result<(), Error> {
+        storage.save(BOOKMARKS_STORAGE_KEY, &self.bookmarks.serialize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+    use crate::http::HttpResponse;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::storage::MemoryStorage;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_document() {
+        let browser = Browser::new();
+        assert!(browser.borrow().document().is_none());
+
+        let raw = "HTTP/1.1 200 OK\nData: xx xx xx\n\n<html><head></head><body><h1 id=\"title\">H1 title</h1><p>Test text.</p></body></html>";
+        let response = HttpResponse::new(raw.to_string()).expect("failed to parse http response");
+        browser
+            .borrow()
+            .current_page()
+            .borrow_mut()
+            .receive_response(response);
+
+        let document = browser.borrow().document().expect("failed to get a document");
+        let html = document.borrow().first_child().expect("failed to get html");
+        assert_eq!(Some(ElementKind::Html), html.borrow().element_kind());
+        let head = html.borrow().first_child().expect("failed to get head");
+        assert_eq!(Some(ElementKind::Head), head.borrow().element_kind());
+        let body = head.borrow().next_sibling().expect("failed to get body");
+        assert_eq!(Some(ElementKind::Body), body.borrow().element_kind());
+    }
+
+    #[test]
+    fn test_load_from_str() {
+        let html = r#"<html>
+<head><title>Sample</title></head>
+<body>
+  <h1 id="title">H1 title</h1>
+  <p>
+    <a href="http://example.com/1">Link1</a>
+    <a href="/2">Link2</a>
+  </p>
+</body>
+</html>"#;
+        let browser = Browser::new();
+        browser.borrow_mut().load_from_str(html);
+
+        let page = browser.borrow().current_page();
+        assert_eq!("Sample", page.borrow().title());
+        assert_eq!(
+            vec!["http://example.com/1".to_string(), "/2".to_string()],
+            page.borrow()
+                .links()
+                .iter()
+                .map(|link| link.href())
+                .collect::<Vec<String>>()
+        );
+        assert!(page.borrow().content_height() > 0);
+        assert!(!page.borrow().display_items().is_empty());
+    }
+
+    #[test]
+    fn test_display_list() {
+        let html = "<html><body><h1>Title</h1><h2>Subtitle</h2><p>text</p></body></html>";
+        let browser = Browser::new();
+        browser.borrow_mut().load_from_str(html);
+
+        let texts: Vec<(String, i64, i64)> = browser
+            .borrow()
+            .display_list()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text, layout_point.x(), layout_point.y())),
+                _ => None,
+            })
+            .collect();
+
+        // 見出しの上下には 8px のマージンがあり、h1 は3倍、h2 は2倍の大きさで描画される
+        let h1_y = 8;
+        let h2_y = h1_y + CHAR_HEIGHT_WITH_PADDING * 3 + 8;
+        let p_y = h2_y + CHAR_HEIGHT_WITH_PADDING * 2 + 8;
+        assert_eq!(
+            vec![
+                ("Title".to_string(), 0, h1_y),
+                ("Subtitle".to_string(), 0, h2_y),
+                ("text".to_string(), 0, p_y),
+            ],
+            texts
+        );
+    }
+
+    #[test]
+    fn test_history_persistence() {
+        let mut storage = MemoryStorage::new();
+
+        let browser = Browser::new();
+        browser
+            .borrow_mut()
+            .push_history("http://example.com".to_string())
+            .expect("failed to push history");
+        browser
+            .borrow()
+            .save_history(&mut storage)
+            .expect("failed to save history");
+
+        let restored = Browser::new();
+        restored.borrow_mut().load_history(&storage);
+        assert_eq!(browser.borrow().history(), restored.borrow().history());
+    }
+
+    #[test]
+    fn test_bookmarks_persistence() {
+        let mut storage = MemoryStorage::new();
+
+        let browser = Browser::new();
+        let url = Url::new("http://example.com".to_string())
+            .parse()
+            .expect("failed to parse url");
+        browser
+            .borrow_mut()
+            .add_bookmark("Example".to_string(), url)
+            .expect("failed to add a bookmark");
+        browser
+            .borrow()
+            .save_bookmarks(&mut storage)
+            .expect("failed to save bookmarks");
+
+        let restored = Browser::new();
+        restored.borrow_mut().load_bookmarks(&storage);
+        assert_eq!(browser.borrow().bookmarks(), restored.borrow().bookmarks());
+        assert_eq!(1, restored.borrow().bookmarks().len());
+    }
+
+    #[test]
+    fn test_storage_lifecycle() {
+        let storage = Rc::new(RefCell::new(MemoryStorage::new()));
+
+        // 履歴は移動するたびに保存され、次のセッションの開始時に読み込まれる
+        let browser = Browser::new();
+        browser.borrow_mut().set_storage(storage.clone());
+        browser
+            .borrow_mut()
+            .push_history("http://example.com/".to_string())
+            .expect("failed to push history");
+
+        let url = Url::new("http://example.com/a".to_string())
+            .parse()
+            .expect("failed to parse url");
+        browser
+            .borrow_mut()
+            .add_bookmark("A".to_string(), url.clone())
+            .expect("failed to add a bookmark");
+
+        let next_session = Browser::new();
+        next_session.borrow_mut().set_storage(storage.clone());
+        assert_eq!(
+            vec!["http://example.com/".to_string()],
+            next_session.borrow().history().entries()
+        );
+        assert_eq!(vec![("A".to_string(), url.clone())], next_session.borrow().bookmarks().list());
+
+        // 削除も保存される
+        assert_eq!(Ok(true), next_session.borrow_mut().remove_bookmark(&url));
+        assert_eq!(Ok(false), next_session.borrow_mut().remove_bookmark(&url));
+        let third_session = Browser::new();
+        third_session.borrow_mut().set_storage(storage);
+        assert!(third_session.borrow().bookmarks().is_empty());
+    }
+
+    #[test]
+    fn test_missing_or_corrupt_history() {
+        let mut storage = MemoryStorage::new();
+        let browser = Browser::new();
+        browser.borrow_mut().load_history(&storage);
+        assert!(browser.borrow().history().is_empty());
+
+        storage
+            .save(HISTORY_STORAGE_KEY, "not a history")
+            .expect("failed to save history");
+        browser.borrow_mut().load_history(&storage);
+        assert!(browser.borrow().history().is_empty());
+    }
+
+    #[test]
+    fn test_home_url() {
+        let browser = Browser::new();
+        assert_eq!(ABOUT_BLANK, browser.borrow().home_url());
+
+        browser.borrow_mut().set_home_url("http://example.com/".to_string());
+        assert_eq!("http://example.com/", browser.borrow().home_url());
+    }
+
+    #[test]
+    fn test_event_log() {
+        use crate::event_log::LogEvent;
+        use crate::http::testing::FakeTransport;
+        use crate::http::HttpClient;
+
+        let mut transport = FakeTransport::new(Rc::new(RefCell::new(Vec::new())), "");
+        transport.next_responses = vec![
+            "HTTP/1.1 302 Found\nLocation: /next\n\n".as_bytes().to_vec(),
+            "HTTP/1.1 200 OK\n\n<html><body><p>next</p></div></body></html>".as_bytes().to_vec(),
+        ];
+        let url = Url::new("http://example.com/".to_string())
+            .parse()
+            .expect("failed to parse url");
+
+        let browser = Browser::new();
+        let page = browser.borrow().current_page();
+        page.borrow_mut().begin_navigation("http://example.com/");
+        let result = HttpClient::new(transport).navigate(&url).expect("failed to navigate");
+        page.borrow_mut().receive_navigation_result(result, &mut |_| {
+            Err(Error::Network("no subresources".to_string()))
+        });
+
+        assert_eq!(
+            vec![
+                LogEvent::NavigationStarted("http://example.com/".to_string()),
+                LogEvent::RedirectFollowed {
+                    from: "http://example.com/".to_string(),
+                    to: "http://example.com/next".to_string(),
+                },
+                LogEvent::ParseErrors(1),
+                LogEvent::LoadCompleted("http://example.com/next".to_string()),
+            ],
+            browser.borrow().event_log().borrow().events()
+        );
+        assert_eq!(
+            Some("http://example.com/next".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+    }
+}
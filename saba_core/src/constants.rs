@@ -3,6 +3,7 @@ pub static LIGHTGRAY: u32 = 0xd3d3d3;
 pub static GREY: u32 = 0x808080;
 pub static DARKGRAY: u32 = 0x5a5a5a;
 pub static BLACK: u32 = 0x000000;
+pub static RED: u32 = 0xff0000;
 
 pub static ADDRESSBAR_HEIGHT: i64 = 20;
 
@@ -17,10 +18,20 @@ pub static TITLE_BAR_HEIGHT: i64 = 24;
 
 pub static TOOLBAR_HEIGHT: i64 = 26;
 
+pub static PROGRESS_BAR_HEIGHT: i64 = 2;
+
+pub static STATUS_BAR_HEIGHT: i64 = CHAR_HEIGHT_WITH_PADDING;
+
 pub static CONTENT_AREA_WIDTH: i64 = WINDOW_WIDTH - WINDOW_PADDING * 2;
 pub static CONTENT_AREA_HEIGHT: i64 =
-    WINDOW_HEIGHT - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - WINDOW_PADDING * 2;
+    WINDOW_HEIGHT - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - STATUS_BAR_HEIGHT - WINDOW_PADDING * 2;
 
 pub static CHAR_WIDTH: i64 = 8;
 pub static CHAR_HEIGHT: i64 = 16;
-pub static CHAR_HEIGHT_WITH_PADDING: i64 = CHAR_HEIGHT + 4;
\ No newline at end of file
+pub static CHAR_HEIGHT_WITH_PADDING: i64 = CHAR_HEIGHT + 4;
+
+/// ズーム倍率（パーセント）
+pub static DEFAULT_ZOOM: i64 = 100;
+pub static MIN_ZOOM: i64 = 50;
+pub static MAX_ZOOM: i64 = 300;
+pub static ZOOM_STEP: i64 = 10;
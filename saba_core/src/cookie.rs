@@ -0,0 +1,469 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::error::Error;
+use crate::http::HttpResponse;
+use crate::url::Url;
+
+/// A cookie set by a Set-Cookie header.
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-4.1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    expires: Option<String>,
+    max_age: Option<i64>,
+    secure: bool,
+    http_only: bool,
+}
+
+impl Cookie {
+    pub fn new(name: String, value: String) -> Self {
+        Self {
+            name,
+            value,
+            path: None,
+            domain: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+        }
+    }
+
+    /// Parses the value of a Set-Cookie header. Unknown attributes are ignored.
+    /// https://datatracker.ietf.org/doc/html/rfc6265#section-5.2
+    pub fn parse(set_cookie: &str) -> Result<Self, Error> {
+        let mut parts = set_cookie.split(';');
+        let name_value = parts.next().unwrap_or("");
+        let (name, value) = match name_value.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid Set-Cookie header: {:?}",
+                    set_cookie
+                )))
+            }
+        };
+
+        let mut cookie = Cookie::new(name.to_string(), value.to_string());
+        for attribute in parts {
+            let (attribute_name, attribute_value) = match attribute.split_once('=') {
+                Some((n, v)) => (n.trim(), v.trim()),
+                None => (attribute.trim(), ""),
+            };
+
+            // 属性名は大文字と小文字を区別しない
+            match attribute_name.to_ascii_lowercase().as_str() {
+                "path" => cookie.path = Some(attribute_value.to_string()),
+                "domain" => cookie.domain = Some(attribute_value.to_string()),
+                "expires" => cookie.expires = Some(attribute_value.to_string()),
+                "max-age" => cookie.max_age = attribute_value.parse().ok(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+
+        Ok(cookie)
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    pub fn path(&self) -> Option<String> {
+        self.path.clone()
+    }
+
+    pub fn domain(&self) -> Option<String> {
+        self.domain.clone()
+    }
+
+    pub fn expires(&self) -> Option<String> {
+        self.expires.clone()
+    }
+
+    pub fn max_age(&self) -> Option<i64> {
+        self.max_age
+    }
+
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    pub fn http_only(&self) -> bool {
+        self.http_only
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StoredCookie {
+    /// Domain 属性がない場合はレスポンスを返したホスト、ある場合はその値
+    domain: String,
+    host_only: bool,
+    path: String,
+    cookie: Cookie,
+}
+
+/// Cookies received from servers, sent back on later requests to matching URLs.
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.3
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieJar {
+    cookies: Vec<StoredCookie>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self {
+            cookies: Vec::new(),
+        }
+    }
+
+    /// Stores the cookies set by `response` to a request for `url`.
+    pub fn store_response(&mut self, url: &Url, response: &HttpResponse) {
+        // 時計がないので、Expires はレスポンスの Date ヘッダーの日時と比べる
+        let date = response.header("Date").and_then(|date| parse_cookie_date(&date));
+        for cookie in response.cookies() {
+            self.store_at(url, cookie, date);
+        }
+    }
+
+    /// Stores `cookie` received from `url`, replacing one with the same name, domain and path.
+    /// Max-Age=0 removes the cookie. Expires is only interpreted by `store_response`, which
+    /// knows the date of the response.
+    pub fn store(&mut self, url: &Url, cookie: Cookie) {
+        self.store_at(url, cookie, None);
+    }
+
+    /// Stores `cookie`, removing it instead if it expired before `date`.
+    fn store_at(&mut self, url: &Url, cookie: Cookie, date: Option<CookieDate>) {
+        let host = url.host().to_ascii_lowercase();
+        let (domain, host_only) = match cookie.domain() {
+            Some(domain) => {
+                let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+                // 他のドメインの Cookie は設定させない
+                if !domain_match(&host, &domain) {
+                    return;
+                }
+                // com のようなトップレベルドメイン全体にも設定させない。公開サフィックスの一覧は
+                // 持っていないので、ドットを含まないドメインをトップレベルドメインとみなす
+                // https://datatracker.ietf.org/doc/html/rfc6265#section-5.3
+                if !domain.contains('.') {
+                    if domain != host {
+                        return;
+                    }
+                    (host, true)
+                } else {
+                    (domain, false)
+                }
+            }
+            None => (host, true),
+        };
+        let path = match cookie.path() {
+            Some(path) if path.starts_with('/') => path,
+            _ => default_path(url),
+        };
+
+        self.cookies.retain(|c| {
+            !(c.cookie.name == cookie.name && c.domain == domain && c.path == path)
+        });
+        let expired = match (cookie.max_age(), cookie.expires()) {
+            // Max-Age は Expires より優先する
+            (Some(max_age), _) => max_age <= 0,
+            (None, Some(expires)) => match (parse_cookie_date(&expires), date) {
+                (Some(expires), Some(date)) => expires <= date,
+                _ => false,
+            },
+            (None, None) => false,
+        };
+        if expired {
+            return;
+        }
+        self.cookies.push(StoredCookie {
+            domain,
+            host_only,
+            path,
+            cookie,
+        });
+    }
+
+    /// Returns the value of the Cookie request header for `url`, or None if no cookie matches.
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let host = url.host().to_ascii_lowercase();
+        let path = format!("/{}", url.path());
+        let is_secure = url.scheme() == "https";
+
+        let pairs = self
+            .cookies
+            .iter()
+            .filter(|c| {
+                if c.host_only {
+                    c.domain == host
+                } else {
+                    domain_match(&host, &c.domain)
+                }
+            })
+            .filter(|c| path_match(&path, &c.path))
+            .filter(|c| is_secure || !c.cookie.secure())
+            .map(|c| format!("{}={}", c.cookie.name, c.cookie.value))
+            .collect::<Vec<String>>();
+
+        if pairs.is_empty() {
+            None
+        } else {
+            Some(pairs.join("; "))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+}
+
+/// (年, 月, 日, 時, 分, 秒) の組。そのまま比較すると日時の前後がわかる
+type CookieDate = (u32, u32, u32, u32, u32, u32);
+
+/// Parses a date such as `Wed, 21 Oct 2015 07:28:00 GMT`, or returns None if a part is missing.
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.1
+fn parse_cookie_date(date: &str) -> Option<CookieDate> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    for token in date.split([' ', ',', '-', '\t']).filter(|t| !t.is_empty()) {
+        if time.is_none() {
+            let parts = token
+                .split(':')
+                .map(|p| p.parse::<u32>().ok())
+                .collect::<Option<Vec<u32>>>();
+            if let Some([h, m, s]) = parts.as_deref() {
+                time = Some((*h, *m, *s));
+                continue;
+            }
+        }
+        if day.is_none() && token.len() <= 2 {
+            if let Ok(d) = token.parse::<u32>() {
+                day = Some(d);
+                continue;
+            }
+        }
+        if month.is_none() {
+            let prefix = token.get(..3).unwrap_or("").to_ascii_lowercase();
+            if let Some(i) = MONTHS.iter().position(|m| *m == prefix) {
+                month = Some(i as u32 + 1);
+                continue;
+            }
+        }
+        if year.is_none() && (2..=4).contains(&token.len()) {
+            if let Ok(y) = token.parse::<u32>() {
+                // 2 桁の年は、70 以上を 1900 年代、それ以外を 2000 年代とする
+                year = Some(match y {
+                    70..=99 => y + 1900,
+                    0..=69 => y + 2000,
+                    _ => y,
+                });
+            }
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    Some((year?, month?, day?, hour, minute, second))
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.3
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.4
+fn default_path(url: &Url) -> String {
+    let path = format!("/{}", url.path());
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.4
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    if request_path == cookie_path {
+        return true;
+    }
+    match request_path.strip_prefix(cookie_path) {
+        Some(rest) => cookie_path.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::new(s.to_string()).parse().expect("failed to parse url")
+    }
+
+    fn parse(s: &str) -> Cookie {
+        Cookie::parse(s).expect("failed to parse cookie")
+    }
+
+    #[test]
+    fn test_parse() {
+        let cookie = Cookie::parse("id=1; Path=/; HttpOnly").expect("failed to parse cookie");
+        let mut expected = Cookie::new("id".to_string(), "1".to_string());
+        expected.path = Some("/".to_string());
+        expected.http_only = true;
+        assert_eq!(expected, cookie);
+    }
+
+    #[test]
+    fn test_parse_all_attributes() {
+        let cookie = Cookie::parse(
+            "sid=abc; domain=example.com; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Secure; SameSite=Lax",
+        )
+        .expect("failed to parse cookie");
+        assert_eq!("sid", cookie.name());
+        assert_eq!("abc", cookie.value());
+        assert_eq!(None, cookie.path());
+        assert_eq!(Some("example.com".to_string()), cookie.domain());
+        assert_eq!(Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()), cookie.expires());
+        assert!(cookie.secure());
+        assert!(!cookie.http_only());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Cookie::parse("").is_err());
+        assert!(Cookie::parse("novalue").is_err());
+        assert!(Cookie::parse("=1").is_err());
+    }
+
+    #[test]
+    fn test_jar_host() {
+        let mut jar = CookieJar::new();
+        jar.store(&url("http://example.com/"), parse("id=1"));
+        jar.store(&url("http://example.com/"), parse("lang=ja"));
+
+        assert_eq!(Some("id=1; lang=ja".to_string()), jar.cookie_header(&url("http://example.com/a")));
+        assert_eq!(None, jar.cookie_header(&url("http://other.com/")));
+        // Domain 属性がなければ、サブドメインには送らない
+        assert_eq!(None, jar.cookie_header(&url("http://www.example.com/")));
+    }
+
+    #[test]
+    fn test_jar_domain() {
+        let mut jar = CookieJar::new();
+        jar.store(&url("http://www.example.com/"), parse("id=1; Domain=example.com"));
+        jar.store(&url("http://www.example.com/"), parse("evil=1; Domain=other.com"));
+
+        assert_eq!(1, jar.len());
+        assert_eq!(Some("id=1".to_string()), jar.cookie_header(&url("http://example.com/")));
+        assert_eq!(Some("id=1".to_string()), jar.cookie_header(&url("http://a.example.com/")));
+        assert_eq!(None, jar.cookie_header(&url("http://notexample.com/")));
+    }
+
+    #[test]
+    fn test_jar_path() {
+        let mut jar = CookieJar::new();
+        jar.store(&url("http://example.com/docs/index.html"), parse("a=1"));
+        jar.store(&url("http://example.com/"), parse("b=2; Path=/docs/api"));
+
+        assert_eq!(Some("a=1".to_string()), jar.cookie_header(&url("http://example.com/docs")));
+        assert_eq!(
+            Some("a=1; b=2".to_string()),
+            jar.cookie_header(&url("http://example.com/docs/api/x"))
+        );
+        assert_eq!(Some("a=1".to_string()), jar.cookie_header(&url("http://example.com/docs/apis")));
+        assert_eq!(None, jar.cookie_header(&url("http://example.com/")));
+    }
+
+    #[test]
+    fn test_jar_replace_and_expire() {
+        let mut jar = CookieJar::new();
+        jar.store(&url("http://example.com/"), parse("id=1"));
+        jar.store(&url("http://example.com/"), parse("id=2"));
+        assert_eq!(Some("id=2".to_string()), jar.cookie_header(&url("http://example.com/")));
+
+        jar.store(&url("http://example.com/"), parse("id=; Max-Age=0"));
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn test_jar_top_level_domain() {
+        let mut jar = CookieJar::new();
+        jar.store(&url("http://www.example.com/"), parse("id=1; Domain=com"));
+        assert!(jar.is_empty());
+
+        // ホスト名そのものなら、そのホストだけに送る Cookie として設定できる
+        jar.store(&url("http://localhost/"), parse("id=1; Domain=localhost"));
+        assert_eq!(Some("id=1".to_string()), jar.cookie_header(&url("http://localhost/")));
+    }
+
+    #[test]
+    fn test_parse_cookie_date() {
+        assert_eq!(
+            Some((2015, 10, 21, 7, 28, 0)),
+            parse_cookie_date("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+        assert_eq!(
+            Some((1999, 1, 2, 3, 4, 5)),
+            parse_cookie_date("Saturday, 02-Jan-99 03:04:05 GMT")
+        );
+        assert_eq!(None, parse_cookie_date("Wed, 21 Oct 2015"));
+    }
+
+    #[test]
+    fn test_jar_expires() {
+        let response = |set_cookie: &str| {
+            HttpResponse::new(format!(
+                "HTTP/1.1 200 OK\nDate: Wed, 21 Oct 2015 07:28:00 GMT\nSet-Cookie: {}\n\n",
+                set_cookie
+            ))
+            .expect("failed to parse http response")
+        };
+        let mut jar = CookieJar::new();
+        jar.store_response(
+            &url("http://example.com/"),
+            &response("id=1; Expires=Thu, 22 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(1, jar.len());
+
+        // 過去の日時を Expires にすると削除される
+        jar.store_response(
+            &url("http://example.com/"),
+            &response("id=; Expires=Thu, 01 Jan 1970 00:00:00 GMT"),
+        );
+        assert!(jar.is_empty());
+
+        // Max-Age は Expires より優先する
+        jar.store_response(
+            &url("http://example.com/"),
+            &response("id=1; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=60"),
+        );
+        assert_eq!(1, jar.len());
+    }
+
+    #[test]
+    fn test_jar_secure() {
+        let mut jar = CookieJar::new();
+        jar.store(&url("http://example.com/"), parse("id=1; Secure"));
+        // Secure の Cookie は https にだけ送る
+        assert_eq!(None, jar.cookie_header(&url("http://example.com/")));
+        assert_eq!(Some("id=1".to_string()), jar.cookie_header(&url("https://example.com/")));
+    }
+}
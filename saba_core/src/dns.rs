@@ -0,0 +1,116 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::error::Error;
+
+/// Resolves a host name into addresses. Platforms implement this on top of their DNS client.
+pub trait Resolver {
+    type Addr: Clone;
+
+    fn resolve(&mut self, host: &str) -> Result<Vec<Self::Addr>, Error>;
+}
+
+#[derive(Debug, Clone)]
+struct DnsEntry<A> {
+    host: String,
+    addrs: Vec<A>,
+    expires_at: u64,
+}
+
+/// Keeps resolved addresses for `ttl` ticks so that repeated requests to the same host don't
+/// resolve it again. Failed lookups aren't cached. A tick is the unit of the clock given to
+/// `lookup`, e.g. milliseconds, or UI event loop iterations on platforms without a clock.
+#[derive(Debug, Clone)]
+pub struct DnsCache<R: Resolver> {
+    resolver: R,
+    ttl: u64,
+    entries: Vec<DnsEntry<R::Addr>>,
+}
+
+impl<R: Resolver> DnsCache<R> {
+    pub fn new(resolver: R, ttl: u64) -> Self {
+        Self {
+            resolver,
+            ttl,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the addresses of `host`. `now` is the current time in the same ticks as the TTL.
+    pub fn lookup(&mut self, host: &str, now: u64) -> Result<Vec<R::Addr>, Error> {
+        // ホスト名は大文字と小文字を区別しない
+        let host = host.to_ascii_lowercase();
+        self.entries.retain(|e| now < e.expires_at);
+        if let Some(entry) = self.entries.iter().find(|e| e.host == host) {
+            return Ok(entry.addrs.clone());
+        }
+
+        let addrs = self.resolver.resolve(&host)?;
+        self.entries.push(DnsEntry {
+            host,
+            addrs: addrs.clone(),
+            expires_at: now.saturating_add(self.ttl),
+        });
+        Ok(addrs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::rc::Rc;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use core::cell::RefCell;
+
+    struct FakeResolver {
+        lookups: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Resolver for FakeResolver {
+        type Addr = [u8; 4];
+
+        fn resolve(&mut self, host: &str) -> Result<Vec<[u8; 4]>, Error> {
+            self.lookups.borrow_mut().push(host.to_string());
+            if host == "unknown.example" {
+                return Err(Error::Dns(format!("Failed to find IP addresses of {}", host)));
+            }
+            Ok(vec![[10, 0, 0, self.lookups.borrow().len() as u8]])
+        }
+    }
+
+    fn create_cache(ttl: u64) -> (DnsCache<FakeResolver>, Rc<RefCell<Vec<String>>>) {
+        let lookups = Rc::new(RefCell::new(Vec::new()));
+        let cache = DnsCache::new(
+            FakeResolver {
+                lookups: lookups.clone(),
+            },
+            ttl,
+        );
+        (cache, lookups)
+    }
+
+    #[test]
+    fn test_within_ttl() {
+        let (mut cache, lookups) = create_cache(1000);
+        assert_eq!(Ok(vec![[10, 0, 0, 1]]), cache.lookup("example.com", 0));
+        assert_eq!(Ok(vec![[10, 0, 0, 1]]), cache.lookup("Example.com", 999));
+        assert_eq!(vec!["example.com".to_string()], *lookups.borrow());
+    }
+
+    #[test]
+    fn test_expired() {
+        let (mut cache, lookups) = create_cache(1000);
+        assert_eq!(Ok(vec![[10, 0, 0, 1]]), cache.lookup("example.com", 0));
+        assert_eq!(Ok(vec![[10, 0, 0, 2]]), cache.lookup("example.com", 1000));
+        assert_eq!(2, lookups.borrow().len());
+    }
+
+    #[test]
+    fn test_failure_not_cached() {
+        let (mut cache, lookups) = create_cache(1000);
+        assert!(cache.lookup("unknown.example", 0).is_err());
+        assert!(cache.lookup("unknown.example", 1).is_err());
+        assert_eq!(2, lookups.borrow().len());
+    }
+}
@@ -2,8 +2,54 @@ use alloc::string::String;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
+    /// A network failure that doesn't fit the more specific variants below.
     Network(String),
+    ConnectionRefused,
+    /// The server didn't answer in time while connecting or reading.
+    Timeout,
+    /// Failed to resolve the host name.
+    Dns(String),
+    /// The response doesn't start with a valid HTTP status line.
+    BadStatusLine(String),
+    /// The load was cancelled before it finished.
+    Aborted,
+    /// The response body is larger than the limit of the client.
+    TooLarge,
+    /// A script evaluated more nodes than the budget of the JS runtime.
+    ScriptTimeout,
+    /// The request needs a feature that isn't implemented, e.g. TLS for https.
+    Unsupported(String),
     UnexpectedInput(String),
     InvalidUI(String),
     Other(String),
-}
\ No newline at end of file
+}
+
+impl Error {
+    /// Returns true if the same request may succeed when it's sent again.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Error::ConnectionRefused | Error::Timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_is_transient() {
+        let errors = [
+            (Error::ConnectionRefused, true),
+            (Error::Timeout, true),
+            (Error::Network("connection reset".to_string()), false),
+            (Error::Dns("example.com".to_string()), false),
+            (Error::BadStatusLine("".to_string()), false),
+            (Error::Aborted, false),
+            (Error::TooLarge, false),
+            (Error::Unsupported("TLS not implemented".to_string()), false),
+        ];
+        for (error, expected) in errors {
+            assert_eq!(expected, error.is_transient(), "{:?}", error);
+        }
+    }
+}
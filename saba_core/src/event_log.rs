@@ -0,0 +1,192 @@
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+/// The number of entries the browser keeps by default.
+pub const DEFAULT_EVENT_LOG_SIZE: usize = 100;
+
+/// Something that happened while loading a page, recorded for troubleshooting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEvent {
+    NavigationStarted(String),
+    RedirectFollowed { from: String, to: String },
+    LoadCompleted(String),
+    /// The number of parse errors found in the HTML of the loaded page.
+    ParseErrors(usize),
+    /// A syntax error in a script, with its line and column.
+    ScriptSyntaxError(String),
+    /// A script that ran too long, with the line and column of the statement where it stopped.
+    ScriptTimeout(String),
+    ScriptExecuted,
+}
+
+impl Display for LogEvent {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            LogEvent::NavigationStarted(url) => write!(f, "navigation started: {}", url),
+            LogEvent::RedirectFollowed { from, to } => {
+                write!(f, "redirect followed: {} -> {}", from, to)
+            }
+            LogEvent::LoadCompleted(url) => write!(f, "load completed: {}", url),
+            LogEvent::ParseErrors(count) => write!(f, "parse errors: {}", count),
+            LogEvent::ScriptSyntaxError(error) => write!(f, "script syntax error: {}", error),
+            LogEvent::ScriptTimeout(position) => write!(f, "script timed out at {}", position),
+            LogEvent::ScriptExecuted => write!(f, "script executed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    timestamp: u64,
+    event: LogEvent,
+}
+
+impl LogEntry {
+    pub fn new(timestamp: u64, event: LogEvent) -> Self {
+        Self { timestamp, event }
+    }
+
+    /// Returns the time given by the clock of the log. The unit is up to the clock.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn event(&self) -> LogEvent {
+        self.event.clone()
+    }
+}
+
+/// Keeps the latest `max_entries` events. When full, the oldest one is dropped.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    max_entries: usize,
+    entries: VecDeque<LogEntry>,
+    clock: fn() -> u64,
+}
+
+impl EventLog {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: VecDeque::new(),
+            clock: no_clock,
+        }
+    }
+
+    /// Sets the function that returns the current time, e.g. in milliseconds or in ticks of an
+    /// event loop. Until it's set, every entry has the timestamp 0.
+    pub fn set_clock(&mut self, clock: fn() -> u64) {
+        self.clock = clock;
+    }
+
+    pub fn push(&mut self, event: LogEvent) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        while self.entries.len() >= self.max_entries {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry::new((self.clock)(), event));
+    }
+
+    /// Returns the entries from the oldest to the latest.
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries.iter().cloned().collect()
+    }
+
+    /// Returns the events from the oldest to the latest, without the timestamps.
+    pub fn events(&self) -> Vec<LogEvent> {
+        self.entries.iter().map(|entry| entry.event()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Formats the entries one per line, e.g. `[1200] load completed: http://example.com/`.
+    pub fn dump(&self) -> String {
+        let mut result = String::new();
+        for entry in &self.entries {
+            result.push_str(&format!("[{}] {}\n", entry.timestamp, entry.event));
+        }
+        result
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_LOG_SIZE)
+    }
+}
+
+fn no_clock() -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    static NOW: AtomicU64 = AtomicU64::new(0);
+
+    fn fake_clock() -> u64 {
+        NOW.fetch_add(10, Ordering::SeqCst) + 10
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut log = EventLog::new(2);
+        log.push(LogEvent::NavigationStarted("http://a.com/".to_string()));
+        log.push(LogEvent::ParseErrors(1));
+        log.push(LogEvent::LoadCompleted("http://a.com/".to_string()));
+        assert_eq!(
+            vec![
+                LogEvent::ParseErrors(1),
+                LogEvent::LoadCompleted("http://a.com/".to_string()),
+            ],
+            log.events()
+        );
+
+        let mut log = EventLog::new(0);
+        log.push(LogEvent::ScriptExecuted);
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn test_dump() {
+        let mut log = EventLog::default();
+        assert_eq!("", log.dump());
+
+        log.set_clock(fake_clock);
+        log.push(LogEvent::RedirectFollowed {
+            from: "http://a.com/".to_string(),
+            to: "http://a.com/next".to_string(),
+        });
+        log.push(LogEvent::ScriptExecuted);
+        let entries = log.entries();
+        assert!(entries[0].timestamp() < entries[1].timestamp());
+        assert_eq!(
+            format!(
+                "[{}] redirect followed: http://a.com/ -> http://a.com/next\n[{}] script executed\n",
+                entries[0].timestamp(),
+                entries[1].timestamp()
+            ),
+            log.dump()
+        );
+    }
+}
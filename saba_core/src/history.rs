@@ -0,0 +1,221 @@
+use crate::url::Url;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub static HISTORY_STORAGE_KEY: &str = "history";
+static HISTORY_HEADER: &str = "saba-history 1";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<String>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, url: String) {
+        self.entries.push(url);
+    }
+
+    pub fn entries(&self) -> Vec<String> {
+        self.entries.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns up to `limit` distinct history entries matching `query`, most recent first.
+    /// Entries whose normalized form starts with the query come before entries that only
+    /// contain it.
+    pub fn suggestions(&self, query: &str, limit: usize) -> Vec<String> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut prefix_matches = Vec::new();
+        let mut substring_matches = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if prefix_matches.contains(entry) || substring_matches.contains(entry) {
+                continue;
+            }
+
+            let key = normalize(entry);
+            if key.starts_with(&query) {
+                prefix_matches.push(entry.clone());
+            } else if key.contains(&query) {
+                substring_matches.push(entry.clone());
+            }
+        }
+
+        prefix_matches.extend(substring_matches);
+        prefix_matches.truncate(limit);
+        prefix_matches
+    }
+
+    /// Serializes the history as a header line followed by one URL per line.
+    pub fn serialize(&self) -> String {
+        let mut result = HISTORY_HEADER.to_string();
+        result.push('\n');
+        for entry in &self.entries {
+            result.push_str(entry);
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Restores a history from the output of `serialize`. Malformed input yields an empty
+    /// history.
+    pub fn deserialize(data: &str) -> Self {
+        let mut lines = data.lines();
+        if lines.next() != Some(HISTORY_HEADER) {
+            return Self::new();
+        }
+
+        let mut history = Self::new();
+        for line in lines {
+            if line.is_empty() {
+                continue;
+            }
+            if Url::new(line.to_string()).parse().is_err() {
+                return Self::new();
+            }
+            history.push(line.to_string());
+        }
+        history
+    }
+}
+
+/// Normalizes a URL or user input for matching: case-insensitive, ignoring the scheme and a
+/// trailing slash.
+fn normalize(s: &str) -> String {
+    let s = s.trim().to_ascii_lowercase();
+    // http:// と https:// のどちらで訪れたページも、同じホスト名で見つけられるようにする
+    let without_scheme = match s.split_once("://") {
+        Some((scheme, rest))
+            if !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) =>
+        {
+            rest
+        }
+        _ => &s,
+    };
+    without_scheme.trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_round_trip() {
+        let mut history = History::new();
+        history.push("http://example.com".to_string());
+        history.push("http://example.com:8888/index.html?a=123".to_string());
+
+        let data = history.serialize();
+        assert_eq!(
+            "saba-history 1\nhttp://example.com\nhttp://example.com:8888/index.html?a=123\n",
+            data
+        );
+        assert_eq!(history, History::deserialize(&data));
+    }
+
+    #[test]
+    fn test_empty() {
+        let history = History::new();
+        assert_eq!(history, History::deserialize(&history.serialize()));
+        assert!(History::deserialize("").is_empty());
+    }
+
+    fn sample_history() -> History {
+        let mut history = History::new();
+        history.push("http://example.com".to_string());
+        history.push("http://www.example.com/".to_string());
+        history.push("http://example.org/index.html".to_string());
+        history.push("http://test.example.com".to_string());
+        history.push("http://example.com".to_string());
+        history
+    }
+
+    #[test]
+    fn test_suggestions_ranking() {
+        let history = sample_history();
+        assert_eq!(
+            vec![
+                "http://example.com".to_string(),
+                "http://example.org/index.html".to_string(),
+                "http://test.example.com".to_string(),
+                "http://www.example.com/".to_string(),
+            ],
+            history.suggestions("exam", 10)
+        );
+    }
+
+    #[test]
+    fn test_suggestions_normalized() {
+        let history = sample_history();
+        assert_eq!(
+            vec!["http://www.example.com/".to_string()],
+            history.suggestions("HTTP://WWW.", 10)
+        );
+        assert_eq!(
+            vec!["http://example.org/index.html".to_string()],
+            history.suggestions("index", 10)
+        );
+    }
+
+    #[test]
+    fn test_suggestions_https() {
+        let mut history = sample_history();
+        history.push("https://secure.example.net/".to_string());
+        let expected = vec!["https://secure.example.net/".to_string()];
+        assert_eq!(expected, history.suggestions("secure.example.net", 10));
+        assert_eq!(expected, history.suggestions("http://secure.", 10));
+        assert_eq!(expected, history.suggestions("HTTPS://SECURE.", 10));
+        // https で入力しても、http で訪れたページが見つかる
+        assert_eq!(
+            vec!["http://test.example.com".to_string()],
+            history.suggestions("https://test.", 10)
+        );
+    }
+
+    #[test]
+    fn test_suggestions_limit_and_empty() {
+        let history = sample_history();
+        assert_eq!(
+            vec![
+                "http://example.com".to_string(),
+                "http://example.org/index.html".to_string(),
+            ],
+            history.suggestions("example", 2)
+        );
+        assert!(history.suggestions("", 10).is_empty());
+        assert!(history.suggestions("nothing", 10).is_empty());
+    }
+
+    #[test]
+    fn test_malformed() {
+        // ヘッダーがない
+        assert!(History::deserialize("http://example.com\n").is_empty());
+        // URL として解釈できない行がある
+        assert!(History::deserialize("saba-history 1\nhttp://example.com\n\u{0}garbage\n").is_empty());
+        // 空行は無視する
+        assert_eq!(
+            vec!["http://example.com".to_string()],
+            History::deserialize("saba-history 1\n\nhttp://example.com\n").entries()
+        );
+    }
+}
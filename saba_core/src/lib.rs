@@ -1,11 +1,22 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+pub mod base64;
+pub mod bookmarks;
 pub mod browser;
+pub mod cookie;
+pub mod dns;
 pub mod error;
+pub mod event_log;
+pub mod history;
 pub mod http;
 pub mod renderer;
+pub mod storage;
+#[cfg(test)]
+pub(crate) mod testing;
 pub mod url;
 pub mod utils;
 pub mod constants;
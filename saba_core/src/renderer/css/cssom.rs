@@ -1,19 +1,19 @@
 use crate::alloc::string::ToString;
 use crate::renderer::css::token::CssTokenizer;
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
-use core::iter::Peekable;
 
 use super::token::CssToken;
 
 #[derive(Debug, Clone)]
 pub struct CssParser {
-    t: Peekable<CssTokenizer>,
+    t: CssTokenizer,
 }
 
 impl CssParser {
     pub fn new(t: CssTokenizer) -> Self {
-        Self { t: t.peekable() }
+        Self { t }
     }
 
     pub fn parse_stylesheet(&mut self) -> StyleSheet {
@@ -33,22 +33,64 @@ impl CssParser {
             match token {
                 // AtKeyword トークンが出てきた場合、他の CSS をインポートする @import、メディアクエリを表す @media などのルールが始まることを表す
                 CssToken::AtKeywork(_keyword) => {
-                    let _rule = self.consume_qualified_rule();
                     // @ から始まるルールはサポートしないので、無視する
+                    self.consume_at_rule();
+                }
+                CssToken::CloseCurly => {
+                    // 対応する { がない } は無視する
+                    self.t.next();
                 }
                 _ => {
-                    let rule = self.consume_qualified_rule();
-                    match rule {
-                        Some(r) => rules.push(r),
-                        None => return rules,
+                    // 壊れたルールは読み飛ばして、次のルールから解析を続ける
+                    if let Some(rule) = self.consume_qualified_rule() {
+                        rules.push(rule);
+                    }
+                }
+            }
+        }
+    }
+
+    /// https://www.w3.org/TR/css-syntax-3/#consume-at-rule
+    fn consume_at_rule(&mut self) {
+        while let Some(token) = self.t.next() {
+            match token {
+                CssToken::SemiColon => return,
+                CssToken::OpenCurly => {
+                    self.skip_block();
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Skips tokens up to the `}` that closes the block whose `{` was already consumed.
+    fn skip_block(&mut self) {
+        let mut depth = 1;
+        for token in self.t.by_ref() {
+            match token {
+                CssToken::OpenCurly => depth += 1,
+                CssToken::CloseCurly => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
                     }
                 }
+                _ => {}
             }
         }
     }
 
+    /// Returns None at the end of the input, if the rule has no selector, or if any selector in
+    /// the list contains a part this parser doesn't support. An invalid selector invalidates the
+    /// whole rule, so the rule never matches more elements than its author wrote.
+    /// https://www.w3.org/TR/selectors-4/#invalid
     fn consume_qualified_rule(&mut self) -> Option<QualifiedRule> {
         let mut rule = QualifiedRule::new();
+        let mut selectors = Vec::new();
+        // カンマで区切られたセレクタリストのうち、解析中のセレクタを構成する単純セレクタ
+        let mut current = Vec::new();
+        let mut valid = true;
 
         loop {
             let token = match self.t.peek() {
@@ -59,51 +101,93 @@ impl CssParser {
             match token {
                 CssToken::OpenCurly => {
                     assert_eq!(self.t.next(), Some(CssToken::OpenCurly));
+                    if let Some(selector) = Selector::from_compounds(current) {
+                        selectors.push(selector);
+                    }
+                    rule.set_selectors(selectors);
                     rule.set_declarations(self.consume_list_of_declarations());
+                    if !valid || rule.selectors.is_empty() {
+                        return None;
+                    }
                     return Some(rule);
                 }
+                // https://www.w3.org/TR/selectors-4/#grouping
+                CssToken::Delim(',') => {
+                    assert_eq!(self.t.next(), Some(CssToken::Delim(',')));
+                    if let Some(selector) = Selector::from_compounds(current) {
+                        selectors.push(selector);
+                    }
+                    current = Vec::new();
+                }
                 _ => {
-                    rule.set_selector(self.consume_selector());
+                    let selector = self.consume_compound_selector();
+                    if selector == Selector::UnknownSelector {
+                        // サポートしないセレクタや結合子が含まれるルールは、宣言ブロックごと読み飛ばす
+                        valid = false;
+                        self.skip_until_open_curly();
+                    } else {
+                        current.push(selector);
+                    }
                 }
             }
         }
     }
 
+    /// 空白を挟まずに続く単純セレクタを、1つの複合セレクタとしてまとめて読む。
+    /// サポートしない単純セレクタが含まれる場合は UnknownSelector を返す
+    /// https://www.w3.org/TR/selectors-4/#compound
+    fn consume_compound_selector(&mut self) -> Selector {
+        let mut selectors = Vec::new();
+
+        loop {
+            let selector = self.consume_selector();
+            if selector == Selector::UnknownSelector {
+                return Selector::UnknownSelector;
+            }
+            selectors.push(selector);
+
+            if self.t.has_whitespace_before_next() {
+                break;
+            }
+            match self.t.peek() {
+                Some(CssToken::HashToken(_)) | Some(CssToken::Delim('.')) => {}
+                _ => break,
+            }
+        }
+
+        match selectors.len() {
+            1 => selectors.pop().expect("selectors should have one element"),
+            _ => Selector::CompoundSelector(selectors),
+        }
+    }
+
     fn consume_selector(&mut self) -> Selector {
         let token = match self.t.next() {
             Some(t) => t,
-            None => panic!("should have a token but got None"),
+            None => return Selector::UnknownSelector,
         };
 
         match token {
             CssToken::HashToken(value) => Selector::IdSelector(value[1..].to_string()),
-            CssToken::Delim(delim) => {
-                if delim == '.' {
+            CssToken::Delim('.') => {
+                if let Some(CssToken::Ident(_)) = self.t.peek() {
                     return Selector::ClassSelector(self.consume_ident());
                 }
-                panic!("Parse error: {:?} is an unexpected token.", token);
-            }
-            CssToken::Ident(ident) => {
-                // a:hover のようなセレクタはタイプセレクタとして扱うため、もしコロン (:) が出てきた場合は
-                // 宣言ブロックの開始直前までトークンを進める
-                if self.t.peek() == Some(&CssToken::Colon) {
-                    while self.t.peek() != Some(&CssToken::OpenCurly) {
-                        self.t.next();
-                    }
-                }
-                Selector::TypeSelector(ident.to_string())
+                Selector::UnknownSelector
             }
+            CssToken::Ident(ident) => Selector::TypeSelector(ident.to_string()),
             CssToken::AtKeywork(_keyword) => {
                 // @ から始まるルールを無視するために、宣言ブロックの開始直前までトークンを進める
-                while self.t.peek() != Some(&CssToken::OpenCurly) {
-                    self.t.next();
-                }
-                Selector::UnknownSelector
-            }
-            _ => {
-                self.t.next();
+                self.skip_until_open_curly();
                 Selector::UnknownSelector
             }
+            _ => Selector::UnknownSelector,
+        }
+    }
+
+    fn skip_until_open_curly(&mut self) {
+        while self.t.peek().is_some() && self.t.peek() != Some(&CssToken::OpenCurly) {
+            self.t.next();
         }
     }
 
@@ -125,11 +209,18 @@ impl CssParser {
                     assert_eq!(self.t.next(), Some(CssToken::SemiColon));
                     // 一つの宣言が終了。何もしない
                 }
+                CssToken::Ident(ref ident) if ident == "margin" => {
+                    declarations.extend(self.consume_margin_shorthand());
+                }
                 CssToken::Ident(ref _ident) => {
                     if let Some(declaration) = self.consume_declaration() {
                         declarations.push(declaration);
                     }
                 }
+                CssToken::OpenCurly => {
+                    assert_eq!(self.t.next(), Some(CssToken::OpenCurly));
+                    self.skip_block();
+                }
                 _ => {
                     self.t.next();
                 }
@@ -137,6 +228,7 @@ impl CssParser {
         }
     }
 
+    /// https://www.w3.org/TR/css-syntax-3/#consume-declaration
     fn consume_declaration(&mut self) -> Option<Declaration> {
         if self.t.peek().is_none() {
             return None;
@@ -145,18 +237,84 @@ impl CssParser {
         let mut declaration = Declaration::new();
         declaration.set_property(self.consume_ident());
 
-        match self.t.next() {
-            Some(token) => match token {
-                CssToken::Colon => {}
-                _ => return None,
-            },
-            None => return None,
+        if self.t.peek() != Some(&CssToken::Colon) {
+            self.skip_declaration();
+            return None;
+        }
+        assert_eq!(self.t.next(), Some(CssToken::Colon));
+
+        match self.t.peek() {
+            None | Some(CssToken::SemiColon) | Some(CssToken::CloseCurly) => return None,
+            _ => declaration.set_value(self.consume_component_value()),
         }
 
-        declaration.set_value(self.consume_component_value());
+        // 10px の px など、最初の値以降は使わないので読み飛ばす
+        self.skip_declaration();
         Some(declaration)
     }
 
+    /// margin の一括指定を、上下左右それぞれのプロパティの宣言に展開する
+    /// https://www.w3.org/TR/css-box-4/#margin-shorthand
+    fn consume_margin_shorthand(&mut self) -> Vec<Declaration> {
+        self.consume_ident();
+        if self.t.peek() != Some(&CssToken::Colon) {
+            self.skip_declaration();
+            return Vec::new();
+        }
+        assert_eq!(self.t.next(), Some(CssToken::Colon));
+
+        let mut values = Vec::new();
+        loop {
+            match self.t.peek() {
+                None | Some(CssToken::SemiColon) | Some(CssToken::CloseCurly) => break,
+                Some(CssToken::OpenCurly) => {
+                    assert_eq!(self.t.next(), Some(CssToken::OpenCurly));
+                    self.skip_block();
+                }
+                // 10px の px は使わないので読み飛ばす
+                Some(CssToken::Ident(unit)) if unit == "px" => {
+                    self.t.next();
+                }
+                _ => values.push(self.consume_component_value()),
+            }
+        }
+
+        // 値の数に応じて、上、右、下、左のそれぞれに使う値を決める
+        let indices = match values.len() {
+            1 => [0, 0, 0, 0],
+            2 => [0, 1, 0, 1],
+            3 => [0, 1, 2, 1],
+            4 => [0, 1, 2, 3],
+            _ => return Vec::new(),
+        };
+        ["margin-top", "margin-right", "margin-bottom", "margin-left"]
+            .iter()
+            .zip(indices)
+            .map(|(property, i)| {
+                let mut declaration = Declaration::new();
+                declaration.set_property(property.to_string());
+                declaration.set_value(values[i].clone());
+                declaration
+            })
+            .collect()
+    }
+
+    /// Skips the rest of the current declaration, leaving `;` or `}` as the next token.
+    fn skip_declaration(&mut self) {
+        loop {
+            match self.t.peek() {
+                None | Some(CssToken::SemiColon) | Some(CssToken::CloseCurly) => return,
+                Some(CssToken::OpenCurly) => {
+                    assert_eq!(self.t.next(), Some(CssToken::OpenCurly));
+                    self.skip_block();
+                }
+                _ => {
+                    self.t.next();
+                }
+            }
+        }
+    }
+
     fn consume_ident(&mut self) -> String {
         let token = match self.t.next() {
             Some(t) => t,
@@ -178,6 +336,41 @@ impl CssParser {
     }
 }
 
+/// Parses `css` into a style sheet. Unsupported at-rules and malformed rules are skipped.
+/// https://www.w3.org/TR/css-syntax-3/#parse-stylesheet
+pub fn parse_stylesheet(css: &str) -> StyleSheet {
+    CssParser::new(CssTokenizer::new(css.to_string())).parse_stylesheet()
+}
+
+/// ユーザーエージェントスタイルシート。作者のスタイルよりも優先度が低い
+/// https://html.spec.whatwg.org/multipage/rendering.html#rendering
+const USER_AGENT_STYLE_SHEET: &str = "
+    h1 { font-size: xx-large; margin: 8px 0; }
+    h2 { font-size: x-large; margin: 8px 0; }
+    h3 { font-size: large; margin: 8px 0; }
+    h4 { font-size: medium; margin: 8px 0; }
+    h5 { font-size: small; margin: 8px 0; }
+    h6 { font-size: x-small; margin: 8px 0; }
+    h1, h2, h3, h4, h5, h6 { font-weight: bold; }
+    p { margin: 8px 0; }
+    a { color: blue; }
+    b, strong { font-weight: bold; }
+    i, em { font-style: italic; }
+    code, kbd, samp { font-family: monospace; white-space: pre; background-color: lightgray; }
+";
+
+pub fn user_agent_style_sheet() -> StyleSheet {
+    parse_stylesheet(USER_AGENT_STYLE_SHEET)
+}
+
+/// スクリプトが有効なときだけユーザーエージェントスタイルシートに加えるルール
+/// https://html.spec.whatwg.org/multipage/rendering.html#hidden-elements
+const SCRIPTING_STYLE_SHEET: &str = "noscript { display: none; }";
+
+pub fn scripting_style_sheet() -> StyleSheet {
+    parse_stylesheet(SCRIPTING_STYLE_SHEET)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct StyleSheet {
     pub rules: Vec<QualifiedRule>,
@@ -195,20 +388,25 @@ impl StyleSheet {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct QualifiedRule {
-    pub selector: Selector,
+    /// A comma-separated selector list. The rule applies to nodes matching any of them.
+    pub selectors: Vec<Selector>,
     pub declarations: Vec<Declaration>,
 }
 
 impl QualifiedRule {
     pub fn new() -> Self {
         Self {
-            selector: Selector::TypeSelector("".to_string()),
+            selectors: Vec::new(),
             declarations: Vec::new(),
         }
     }
 
     pub fn set_selector(&mut self, selector: Selector) {
-        self.selector = selector;
+        self.selectors = vec![selector];
+    }
+
+    pub fn set_selectors(&mut self, selectors: Vec<Selector>) {
+        self.selectors = selectors;
     }
 
     pub fn set_declarations(&mut self, declarations: Vec<Declaration>) {
@@ -221,9 +419,27 @@ pub enum Selector {
     TypeSelector(String),
     ClassSelector(String),
     IdSelector(String),
+    /// `div p` のように空白で区切られたセレクタ。先頭から順に祖先、最後の要素が対象の要素に対応する
+    /// https://www.w3.org/TR/selectors-4/#descendant-combinators
+    DescendantSelector(Vec<Selector>),
+    /// `p.note` のように空白を挟まずに続くセレクタ。すべてが同じ要素に一致する必要がある
+    /// https://www.w3.org/TR/selectors-4/#compound
+    CompoundSelector(Vec<Selector>),
     UnknownSelector,
 }
 
+impl Selector {
+    /// 空白で区切られたセレクタの並びから1つのセレクタを作る。
+    /// 2つ以上ある場合は、子孫結合子でつながっているものとして扱う
+    fn from_compounds(mut selectors: Vec<Selector>) -> Option<Selector> {
+        match selectors.len() {
+            0 => None,
+            1 => selectors.pop(),
+            _ => Some(Selector::DescendantSelector(selectors)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration {
     pub property: String,
@@ -252,7 +468,6 @@ pub type ComponentValue = CssToken;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloc::vec;
 
     #[test]
     fn test_empty() {
@@ -364,4 +579,156 @@ mod tests {
             i += 1;
         }
     }
-}
+
+    #[test]
+    fn test_parse_stylesheet() {
+        let cssom = parse_stylesheet("/* header */ p { color: red; } .x { font-size: 10px }");
+
+        let mut rule1 = QualifiedRule::new();
+        rule1.set_selector(Selector::TypeSelector("p".to_string()));
+        let mut declaration1 = Declaration::new();
+        declaration1.set_property("color".to_string());
+        declaration1.set_value(CssToken::Ident("red".to_string()));
+        rule1.set_declarations(vec![declaration1]);
+
+        let mut rule2 = QualifiedRule::new();
+        rule2.set_selector(Selector::ClassSelector("x".to_string()));
+        let mut declaration2 = Declaration::new();
+        declaration2.set_property("font-size".to_string());
+        declaration2.set_value(CssToken::Number(10.0));
+        rule2.set_declarations(vec![declaration2]);
+
+        assert_eq!(vec![rule1, rule2], cssom.rules);
+    }
+
+    #[test]
+    fn test_skip_malformed_rules() {
+        let style = "@media screen { p { color: red; } } @import \"a.css\"; \
+                     * { color: red; } } p { color blue; font-size: ; { x } color: green; } \
+                     #id { color: red";
+        let cssom = parse_stylesheet(style);
+
+        let mut rule1 = QualifiedRule::new();
+        rule1.set_selector(Selector::TypeSelector("p".to_string()));
+        let mut declaration1 = Declaration::new();
+        declaration1.set_property("color".to_string());
+        declaration1.set_value(CssToken::Ident("green".to_string()));
+        rule1.set_declarations(vec![declaration1]);
+
+        let mut rule2 = QualifiedRule::new();
+        rule2.set_selector(Selector::IdSelector("id".to_string()));
+        let mut declaration2 = Declaration::new();
+        declaration2.set_property("color".to_string());
+        declaration2.set_value(CssToken::Ident("red".to_string()));
+        rule2.set_declarations(vec![declaration2]);
+
+        assert_eq!(vec![rule1, rule2], cssom.rules);
+    }
+
+    #[test]
+    fn test_selector_list() {
+        let cssom = parse_stylesheet("h1, h2, .title { color: black }");
+
+        let mut rule = QualifiedRule::new();
+        rule.set_selectors(vec![
+            Selector::TypeSelector("h1".to_string()),
+            Selector::TypeSelector("h2".to_string()),
+            Selector::ClassSelector("title".to_string()),
+        ]);
+        let mut declaration = Declaration::new();
+        declaration.set_property("color".to_string());
+        declaration.set_value(CssToken::Ident("black".to_string()));
+        rule.set_declarations(vec![declaration]);
+
+        assert_eq!(vec![rule], cssom.rules);
+    }
+
+    #[test]
+    fn test_compound_selector() {
+        let cssom = parse_stylesheet("p.note, div #main.title .note { color: black }");
+
+        let mut rule = QualifiedRule::new();
+        rule.set_selectors(vec![
+            Selector::CompoundSelector(vec![
+                Selector::TypeSelector("p".to_string()),
+                Selector::ClassSelector("note".to_string()),
+            ]),
+            Selector::DescendantSelector(vec![
+                Selector::TypeSelector("div".to_string()),
+                Selector::CompoundSelector(vec![
+                    Selector::IdSelector("main".to_string()),
+                    Selector::ClassSelector("title".to_string()),
+                ]),
+                Selector::ClassSelector("note".to_string()),
+            ]),
+        ]);
+        let mut declaration = Declaration::new();
+        declaration.set_property("color".to_string());
+        declaration.set_value(CssToken::Ident("black".to_string()));
+        rule.set_declarations(vec![declaration]);
+
+        assert_eq!(vec![rule], cssom.rules);
+    }
+
+    #[test]
+    fn test_descendant_selector() {
+        let cssom = parse_stylesheet("div p, #main .note { color: black }");
+
+        let mut rule = QualifiedRule::new();
+        rule.set_selectors(vec![
+            Selector::DescendantSelector(vec![
+                Selector::TypeSelector("div".to_string()),
+                Selector::TypeSelector("p".to_string()),
+            ]),
+            Selector::DescendantSelector(vec![
+                Selector::IdSelector("main".to_string()),
+                Selector::ClassSelector("note".to_string()),
+            ]),
+        ]);
+        let mut declaration = Declaration::new();
+        declaration.set_property("color".to_string());
+        declaration.set_value(CssToken::Ident("black".to_string()));
+        rule.set_declarations(vec![declaration]);
+
+        assert_eq!(vec![rule], cssom.rules);
+    }
+
+    #[test]
+    fn test_unsupported_selector_invalidates_rule() {
+        let cssom = parse_stylesheet(
+            "div > * { display: none; } * { color: red; } p[title] { color: red; } \
+             h1, a:hover { color: red; } div + p { color: red; } p { color: blue; }",
+        );
+
+        let mut rule = QualifiedRule::new();
+        rule.set_selector(Selector::TypeSelector("p".to_string()));
+        let mut declaration = Declaration::new();
+        declaration.set_property("color".to_string());
+        declaration.set_value(CssToken::Ident("blue".to_string()));
+        rule.set_declarations(vec![declaration]);
+
+        assert_eq!(vec![rule], cssom.rules);
+    }
+
+    #[test]
+    fn test_margin_shorthand() {
+        let cssom = parse_stylesheet("p { margin: 10px 0 5px; } h1 { margin: 3px 1px 2px 4px 5px; }");
+
+        let declarations: Vec<(String, ComponentValue)> = cssom.rules[0]
+            .declarations
+            .iter()
+            .map(|d| (d.property.clone(), d.value.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("margin-top".to_string(), CssToken::Number(10.0)),
+                ("margin-right".to_string(), CssToken::Number(0.0)),
+                ("margin-bottom".to_string(), CssToken::Number(5.0)),
+                ("margin-left".to_string(), CssToken::Number(0.0)),
+            ],
+            declarations
+        );
+        // 値が多すぎる宣言は無視する
+        assert!(cssom.rules[1].declarations.is_empty());
+    }
+}
\ No newline at end of file
@@ -21,6 +21,10 @@ pub enum CssToken {
 pub struct CssTokenizer {
     pos: usize,
     input: Vec<char>,
+    /// peek で先読みしたトークン
+    peeked: Option<Option<CssToken>>,
+    /// 最後に読み出したトークンの直前に空白があったかどうか
+    whitespace_before: bool,
 }
 
 impl CssTokenizer {
@@ -28,28 +32,60 @@ impl CssTokenizer {
         Self {
             pos: 0,
             input: css.chars().collect(),
+            peeked: None,
+            whitespace_before: false,
         }
     }
 
+    /// Returns the next token without consuming it.
+    pub fn peek(&mut self) -> Option<&CssToken> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.consume_token());
+        }
+        self.peeked.as_ref().and_then(|t| t.as_ref())
+    }
+
+    /// Returns whether whitespace or a comment precedes the next token.
+    /// `p.note` と `p .note` のように、空白の有無で意味が変わるセレクタを区別するために使う
+    pub fn has_whitespace_before_next(&mut self) -> bool {
+        self.peek();
+        self.whitespace_before
+    }
+
     fn consume_string_token(&mut self) -> String {
         let mut s = String::new();
+        let quote = self.input[self.pos];
 
         loop {
+            self.pos += 1;
             if self.pos >= self.input.len() {
                 return s;
             }
 
-            self.pos += 1;
             let c = self.input[self.pos];
-            match c {
-                '"' | '\'' => break,
-                _ => s.push(c),
+            if c == quote {
+                break;
             }
+            s.push(c);
         }
 
         s
     }
 
+    /// https://www.w3.org/TR/css-syntax-3/#consume-comments
+    fn consume_comment(&mut self) {
+        // skip '/*'
+        self.pos += 2;
+
+        while self.pos < self.input.len() {
+            if self.input[self.pos] == '*' && self.input.get(self.pos + 1) == Some(&'/') {
+                self.pos += 2;
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
     fn consume_numeric_token(&mut self) -> f64 {
         let mut num = 0f64;
         let mut floating = false;
@@ -88,6 +124,9 @@ impl CssTokenizer {
 
         loop {
             self.pos += 1;
+            if self.pos >= self.input.len() {
+                break;
+            }
             let c = self.input[self.pos];
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => {
@@ -99,12 +138,9 @@ impl CssTokenizer {
 
         s
     }
-}
-
-impl Iterator for CssTokenizer {
-    type Item = CssToken;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn consume_token(&mut self) -> Option<CssToken> {
+        self.whitespace_before = false;
         loop {
             if self.pos >= self.input.len() {
                 return None;
@@ -121,10 +157,16 @@ impl Iterator for CssTokenizer {
                 ';' => CssToken::SemiColon,
                 '{' => CssToken::OpenCurly,
                 '}' => CssToken::CloseCurly,
-                ' ' | '\n' => {
+                ' ' | '\n' | '\r' | '\t' => {
+                    self.whitespace_before = true;
                     self.pos += 1;
                     continue;
                 }
+                '/' if self.input.get(self.pos + 1) == Some(&'*') => {
+                    self.whitespace_before = true;
+                    self.consume_comment();
+                    continue;
+                }
                 '"' | '\'' => {
                     let value = self.consume_string_token();
                     CssToken::StringToken(value)
@@ -149,10 +191,9 @@ impl Iterator for CssTokenizer {
                 '@' => {
                     // 次の3文字が識別子として有効な文字の場合、<at-keywork-token> トークンを作成して返す。
                     // それ以外の場合、<delim-token> を返す。
-                    if self.input[self.pos + 1].is_ascii_alphabetic()
-                        && self.input[self.pos + 2].is_ascii_alphabetic()
-                        && self.input[self.pos + 3].is_ascii_alphabetic()
-                    {
+                    let is_alphabetic =
+                        |i: usize| self.input.get(self.pos + i).is_some_and(|c| c.is_ascii_alphabetic());
+                    if is_alphabetic(1) && is_alphabetic(2) && is_alphabetic(3) {
                         // skip '@'
                         self.pos += 1;
                         let t = CssToken::AtKeywork(self.consume_ident_token());
@@ -167,9 +208,8 @@ impl Iterator for CssTokenizer {
                     self.pos -= 1;
                     t
                 }
-                _ => {
-                    unimplemented!("char {} is not supported yet", c);
-                }
+                // サポートしていない文字は、そのまま <delim-token> として返す
+                _ => CssToken::Delim(c),
             };
 
             self.pos += 1;
@@ -178,10 +218,22 @@ impl Iterator for CssTokenizer {
     }
 }
 
+impl Iterator for CssTokenizer {
+    type Item = CssToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(t) => t,
+            None => self.consume_token(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use alloc::string::ToString;
+    use alloc::vec;
 
     #[test]
     fn test_empty() {
@@ -277,4 +329,36 @@ mod tests {
         }
         assert!(t.next().is_none());
     }
-}
+
+    #[test]
+    fn test_comment_and_unknown_chars() {
+        let style = "/* comment */ p > a{color:'red'}/* unterminated".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = vec![
+            CssToken::Ident("p".to_string()),
+            CssToken::Delim('>'),
+            CssToken::Ident("a".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("color".to_string()),
+            CssToken::Colon,
+            CssToken::StringToken("red".to_string()),
+            CssToken::CloseCurly,
+        ];
+        assert_eq!(expected, t.collect::<Vec<CssToken>>());
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let t = CssTokenizer::new("p".to_string());
+        assert_eq!(vec![CssToken::Ident("p".to_string())], t.collect::<Vec<CssToken>>());
+
+        let t = CssTokenizer::new("\"abc".to_string());
+        assert_eq!(vec![CssToken::StringToken("abc".to_string())], t.collect::<Vec<CssToken>>());
+
+        let t = CssTokenizer::new("@a".to_string());
+        assert_eq!(
+            vec![CssToken::Delim('@'), CssToken::Ident("a".to_string())],
+            t.collect::<Vec<CssToken>>()
+        );
+    }
+}
\ No newline at end of file
@@ -1,36 +1,68 @@
-use alloc::rc::Rc;
+use alloc::rc::{Rc, Weak};
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
-use crate::renderer::dom::node::Element;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeIterator;
 use crate::renderer::dom::node::NodeKind;
 
+/// https://dom.spec.whatwg.org/#concept-node-append
+pub fn append_child(parent: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>) {
+    let last_child = parent.borrow().last_child().upgrade();
+    match last_child {
+        Some(last_child) => {
+            last_child.borrow_mut().set_next_sibling(Some(node.clone()));
+            node.borrow_mut().set_previous_sibling(Rc::downgrade(&last_child));
+        }
+        None => parent.borrow_mut().set_first_child(Some(node.clone())),
+    }
+
+    parent.borrow_mut().set_last_child(Rc::downgrade(node));
+    node.borrow_mut().set_parent(Rc::downgrade(parent));
+}
+
+/// https://dom.spec.whatwg.org/#concept-node-remove
+pub fn remove_child(parent: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>) {
+    let previous_sibling = node.borrow().previous_sibling();
+    let next_sibling = node.borrow().next_sibling();
+    match previous_sibling.upgrade() {
+        Some(previous_sibling) => previous_sibling.borrow_mut().set_next_sibling(next_sibling.clone()),
+        None => parent.borrow_mut().set_first_child(next_sibling.clone()),
+    }
+    match next_sibling {
+        Some(next_sibling) => next_sibling.borrow_mut().set_previous_sibling(previous_sibling),
+        None => parent.borrow_mut().set_last_child(previous_sibling),
+    }
+
+    let mut node = node.borrow_mut();
+    node.set_parent(Weak::new());
+    node.set_previous_sibling(Weak::new());
+    node.set_next_sibling(None);
+}
+
 pub fn get_target_element_node(
     node: Option<Rc<RefCell<Node>>>,
     element_kind: ElementKind,
 ) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            if n.borrow().kind()
-                == NodeKind::Element(Element::new(&element_kind.to_string(), Vec::new()))
-            {
-                return Some(n.clone());
-            }
-            let result1 = get_target_element_node(n.borrow().first_child(), element_kind);
-            let result2 = get_target_element_node(n.borrow().next_sibling(), element_kind);
-            if result1.is_none() && result2.is_none() {
-                return None;
-            }
-            if result1.is_none() {
-                return result2;
-            }
-            result1
-        }
-        None => None,
-    }
+    NodeIterator::new(node?).find(|n| n.borrow().element_kind() == Some(element_kind))
+}
+
+pub fn get_title_content(root: Rc<RefCell<Node>>) -> String {
+    let title_node = match get_target_element_node(Some(root), ElementKind::Title) {
+        Some(n) => n,
+        None => return "".to_string(),
+    };
+    let text_node = match title_node.borrow().first_child() {
+        Some(node) => node,
+        None => return "".to_string(),
+    };
+    let content = match &text_node.borrow().kind() {
+        NodeKind::Text(s) => s.trim().to_string(),
+        _ => "".to_string(),
+    };
+    content
 }
 
 pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
@@ -47,4 +79,303 @@ pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
         _ => "".to_string(),
     };
     content
-}
\ No newline at end of file
+}
+
+/// Returns the closest ancestor of `node` (including itself) whose kind is `element_kind`.
+pub fn get_ancestor_element_node(
+    node: Rc<RefCell<Node>>,
+    element_kind: ElementKind,
+) -> Option<Rc<RefCell<Node>>> {
+    let mut current = Some(node);
+    while let Some(n) = current {
+        if n.borrow().element_kind() == Some(element_kind) {
+            return Some(n);
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    None
+}
+
+/// Collects the name/value pairs of the text inputs in `form`, in tree order.
+/// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#constructing-the-form-data-set
+pub fn get_form_fields(form: Rc<RefCell<Node>>) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    for node in NodeIterator::new(form) {
+        let element = match node.borrow().get_element() {
+            Some(e) if e.kind() == ElementKind::Input => e,
+            _ => continue,
+        };
+        let input_type = element.get_attribute("type").unwrap_or("text".to_string());
+        if !input_type.eq_ignore_ascii_case("text") {
+            continue;
+        }
+        let name = match element.get_attribute("name") {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+        fields.push((name, element.get_attribute("value").unwrap_or_default()));
+    }
+    fields
+}
+
+/// A hyperlink made by an `<a>` element with an href. Missing attributes are empty strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    href: String,
+    text: String,
+    title: String,
+    target: String,
+}
+
+impl Link {
+    pub fn new(href: String, text: String, title: String, target: String) -> Self {
+        Self {
+            href,
+            text,
+            title,
+            target,
+        }
+    }
+
+    pub fn href(&self) -> String {
+        self.href.clone()
+    }
+
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    /// https://html.spec.whatwg.org/multipage/dom.html#the-title-attribute
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    /// https://html.spec.whatwg.org/multipage/links.html#attr-hyperlink-target
+    pub fn target(&self) -> String {
+        self.target.clone()
+    }
+}
+
+/// Returns every `<a>` element with an href under `root`, in tree order.
+pub fn get_links(root: Rc<RefCell<Node>>) -> Vec<Link> {
+    NodeIterator::new(root)
+        .filter(|node| node.borrow().element_kind() == Some(ElementKind::A))
+        .filter_map(|node| {
+            let element = node.borrow().get_element()?;
+            let href = element.get_attribute("href")?;
+            let text: String = NodeIterator::new(node.clone())
+                .filter_map(|n| match n.borrow().kind() {
+                    NodeKind::Text(s) => Some(s),
+                    _ => None,
+                })
+                .collect();
+            Some(Link::new(
+                href,
+                text.trim().to_string(),
+                element.get_attribute("title").unwrap_or_default(),
+                element.get_attribute("target").unwrap_or_default(),
+            ))
+        })
+        .collect()
+}
+
+/// Inserts `c` into the text node `node` before the `caret`-th character, and returns the caret
+/// after the inserted character. Returns None if `node` isn't a text node.
+pub fn insert_char_at(node: &Rc<RefCell<Node>>, caret: usize, c: char) -> Option<usize> {
+    match node.borrow_mut().kind {
+        NodeKind::Text(ref mut s) => {
+            // caret は文字単位なので、バイト位置に直してから挿入する
+            let caret = caret.min(s.chars().count());
+            let index = s.char_indices().nth(caret).map_or(s.len(), |(i, _)| i);
+            s.insert(index, c);
+            Some(caret + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Removes the character before the `caret`-th character of the text node `node`, like
+/// Backspace, and returns the new caret. Returns None if `node` isn't a text node.
+pub fn delete_char_before(node: &Rc<RefCell<Node>>, caret: usize) -> Option<usize> {
+    match node.borrow_mut().kind {
+        NodeKind::Text(ref mut s) => {
+            let caret = caret.min(s.chars().count());
+            if caret == 0 {
+                return Some(0);
+            }
+            let (index, _) = s.char_indices().nth(caret - 1)?;
+            s.remove(index);
+            Some(caret - 1)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the first element under `root` whose id is `id`.
+/// https://dom.spec.whatwg.org/#dom-nonelementparentnode-getelementbyid
+pub fn get_element_by_id(root: Rc<RefCell<Node>>, id: &str) -> Option<Rc<RefCell<Node>>> {
+    NodeIterator::new(root).find(|node| {
+        node.borrow()
+            .get_element()
+            .is_some_and(|e| e.get_attribute("id").as_deref() == Some(id))
+    })
+}
+
+/// Returns the delay in seconds and the URL of `<meta http-equiv="refresh">` under `root`.
+/// The URL is None when the page refreshes itself.
+pub fn get_meta_refresh(root: Rc<RefCell<Node>>) -> Option<(u64, Option<String>)> {
+    NodeIterator::new(root)
+        .filter_map(|node| node.borrow().get_element())
+        .filter(|element| element.kind() == ElementKind::Meta)
+        .filter(|element| {
+            element
+                .get_attribute("http-equiv")
+                .is_some_and(|v| v.eq_ignore_ascii_case("refresh"))
+        })
+        .find_map(|element| parse_refresh(&element.get_attribute("content")?))
+}
+
+/// Parses the content attribute such as "0; url=/next".
+/// https://html.spec.whatwg.org/multipage/semantics.html#shared-declarative-refresh-steps
+pub fn parse_refresh(content: &str) -> Option<(u64, Option<String>)> {
+    let content = content.trim_start();
+    let digits_len = content.find(|c: char| !c.is_ascii_digit()).unwrap_or(content.len());
+    let (digits, rest) = content.split_at(digits_len);
+    if digits.is_empty() && !rest.starts_with('.') {
+        return None;
+    }
+    let delay = digits.parse().unwrap_or(0);
+    // "1.5" のような小数部は無視する
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if rest.is_empty() {
+        return Some((delay, None));
+    }
+    if !rest.starts_with(|c: char| c == ';' || c == ',' || c.is_ascii_whitespace()) {
+        return None;
+    }
+
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix(|c| c == ';' || c == ',').unwrap_or(rest).trim_start();
+    if rest.is_empty() {
+        return Some((delay, None));
+    }
+
+    // "url=" は省略できる
+    let url = match rest.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("url") => {
+            match rest[3..].trim_start().strip_prefix('=') {
+                Some(url) => url.trim_start(),
+                None => rest,
+            }
+        }
+        _ => rest,
+    };
+    let url = match url.chars().next() {
+        Some(quote @ ('"' | '\'')) => match url[1..].split_once(quote) {
+            Some((url, _)) => url,
+            None => &url[1..],
+        },
+        _ => url,
+    };
+
+    Some((delay, Some(url.trim_end().to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_document;
+    use crate::url::build_query;
+    use alloc::vec;
+
+    #[test]
+    fn test_title() {
+        let dom = parse_document("<html><head><title> Test Page </title></head><body>text</body></html>");
+        assert_eq!("Test Page", get_title_content(dom));
+    }
+
+    #[test]
+    fn test_no_title() {
+        let dom = parse_document("<html><head></head><body>text</body></html>");
+        assert_eq!("", get_title_content(dom));
+    }
+
+    #[test]
+    fn test_form_fields() {
+        let dom = parse_document(
+            "<html><body><form action=\"/search\">Query: <input name=\"q\" value=\"rust os\"><input type=\"TEXT\" name=\"lang\"><input value=\"no name\"><input type=\"submit\" name=\"go\" value=\"Go\"></form></body></html>",
+        );
+        let form = get_target_element_node(Some(dom), ElementKind::Form).expect("failed to find a form");
+        let fields = get_form_fields(form.clone());
+        assert_eq!(
+            vec![
+                ("q".to_string(), "rust os".to_string()),
+                ("lang".to_string(), "".to_string()),
+            ],
+            fields
+        );
+        assert_eq!("q=rust%20os&lang=", build_query(&fields));
+
+        let submit = NodeIterator::new(form.clone()).last().expect("failed to get the last node");
+        let ancestor = get_ancestor_element_node(submit, ElementKind::Form).expect("failed to find an ancestor");
+        assert!(Rc::ptr_eq(&form, &ancestor));
+    }
+
+    #[test]
+    fn test_links() {
+        let dom = parse_document(
+            "<html><body><p><a href=\"x\" title=\"T\" target=\"_blank\">Open <b>x</b></a><a>no href</a><a href=\"/y\">y</a></p></body></html>",
+        );
+        assert_eq!(
+            vec![
+                Link::new(
+                    "x".to_string(),
+                    "Open x".to_string(),
+                    "T".to_string(),
+                    "_blank".to_string()
+                ),
+                Link::new("/y".to_string(), "y".to_string(), String::new(), String::new()),
+            ],
+            get_links(dom)
+        );
+    }
+
+    #[test]
+    fn test_insert_char_at() {
+        let text = Rc::new(RefCell::new(Node::new(NodeKind::Text("héllo".to_string()))));
+        assert_eq!(Some(2), insert_char_at(&text, 1, 'x'));
+        assert_eq!("hxéllo", text.borrow().to_html());
+
+        // 末尾より後ろの caret は末尾として扱う
+        assert_eq!(Some(7), insert_char_at(&text, 100, '!'));
+        assert_eq!("hxéllo!", text.borrow().to_html());
+
+        assert_eq!(Some(2), delete_char_before(&text, 3));
+        assert_eq!("hxllo!", text.borrow().to_html());
+        assert_eq!(Some(0), delete_char_before(&text, 0));
+        assert_eq!("hxllo!", text.borrow().to_html());
+
+        let element = parse_document("<p>a</p>");
+        assert_eq!(None, insert_char_at(&element, 0, 'x'));
+    }
+
+    #[test]
+    fn test_meta_refresh() {
+        let dom = parse_document(
+            "<html><head><meta charset=\"utf-8\"><meta http-equiv=\"Refresh\" content=\"0;url=/next.html\"></head><body></body></html>",
+        );
+        assert_eq!(Some((0, Some("/next.html".to_string()))), get_meta_refresh(dom));
+
+        let dom = parse_document("<html><head><title>t</title></head><body></body></html>");
+        assert_eq!(None, get_meta_refresh(dom));
+    }
+
+    #[test]
+    fn test_parse_refresh() {
+        assert_eq!(Some((5, None)), parse_refresh("5"));
+        assert_eq!(Some((0, Some("a.html".to_string()))), parse_refresh("0; URL = 'a.html' x"));
+        assert_eq!(Some((3, Some("http://example.com/".to_string()))), parse_refresh("3.5, http://example.com/"));
+        assert_eq!(None, parse_refresh("url=/next"));
+        assert_eq!(None, parse_refresh("0x"));
+    }
+}
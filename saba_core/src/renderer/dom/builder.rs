@@ -0,0 +1,111 @@
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use crate::renderer::dom::api::append_child;
+use crate::renderer::dom::node::{Element, Node, NodeKind};
+
+#[derive(Debug, Clone)]
+enum DomChild {
+    Element(DomBuilder),
+    Text(String),
+}
+
+/// Builds a DOM tree without going through the HTML parser, e.g. to prepare DOMs in tests.
+#[derive(Debug, Clone)]
+pub struct DomBuilder {
+    tag: String,
+    /// 名前と値の組。同じ名前を何度指定しても、build したときには最後の値だけが残る
+    attributes: Vec<(String, String)>,
+    children: Vec<DomChild>,
+}
+
+impl DomBuilder {
+    pub fn element(tag: &str) -> Self {
+        Self {
+            tag: tag.to_string(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn attr(mut self, name: &str, value: &str) -> Self {
+        self.attributes.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn text(mut self, s: &str) -> Self {
+        self.children.push(DomChild::Text(s.to_string()));
+        self
+    }
+
+    pub fn child(mut self, child: DomBuilder) -> Self {
+        self.children.push(DomChild::Element(child));
+        self
+    }
+
+    pub fn build(&self) -> Rc<RefCell<Node>> {
+        let mut element = Element::new(&self.tag, Vec::new());
+        for (name, value) in &self.attributes {
+            element.set_attribute(name, value);
+        }
+        let node = Rc::new(RefCell::new(Node::new(NodeKind::Element(element))));
+
+        for child in &self.children {
+            let child_node = match child {
+                DomChild::Element(builder) => builder.build(),
+                DomChild::Text(s) => Rc::new(RefCell::new(Node::new(NodeKind::Text(s.clone())))),
+            };
+            append_child(&node, &child_node);
+        }
+
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    #[test]
+    fn test_sample_body() {
+        let html = r#"<html><head><title>Sample</title></head><body><h1 id="title">H1 title</h1><p><a href="http://example.com/1">Link1</a><a href="/2">Link2</a></p></body></html>"#;
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let parsed = get_target_element_node(Some(window.borrow().document()), ElementKind::Body)
+            .expect("body should exist");
+
+        let built = DomBuilder::element("body")
+            .child(DomBuilder::element("h1").attr("id", "title").text("H1 title"))
+            .child(
+                DomBuilder::element("p")
+                    .child(DomBuilder::element("a").attr("href", "http://example.com/1").text("Link1"))
+                    .child(DomBuilder::element("a").attr("href", "/2").text("Link2")),
+            )
+            .build();
+
+        assert_eq!(parsed.borrow().to_html(), built.borrow().to_html());
+
+        // 親と兄弟のリンクも、パーサーと同じようにつながっている
+        let h1 = built.borrow().first_child().expect("h1 should exist");
+        let p = h1.borrow().next_sibling().expect("p should exist");
+        assert!(Rc::ptr_eq(&built, &p.borrow().parent().upgrade().expect("parent should exist")));
+        assert!(Rc::ptr_eq(&h1, &p.borrow().previous_sibling().upgrade().expect("h1 should exist")));
+        assert!(Rc::ptr_eq(&p, &built.borrow().last_child().upgrade().expect("p should exist")));
+    }
+
+    #[test]
+    fn test_duplicate_attribute() {
+        // パーサーと同じく、同じ名前の属性は1つだけになる
+        let built = DomBuilder::element("a")
+            .attr("href", "/1")
+            .attr("title", "t")
+            .attr("href", "/2")
+            .build();
+        assert_eq!("<a href=\"/2\" title=\"t\"></a>", built.borrow().to_html());
+    }
+}
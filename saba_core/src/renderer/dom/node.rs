@@ -3,6 +3,8 @@ use alloc::format;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 use core::fmt::Display;
@@ -12,6 +14,8 @@ use core::str::FromStr;
 #[derive(Debug, Clone)]
 pub struct Window {
     document: Rc<RefCell<Node>>,
+    doctype_name: Option<String>,
+    base_href: Option<String>,
 }
 
 impl Default for Window {
@@ -24,6 +28,8 @@ impl Window {
     pub fn new() -> Self {
         let window = Self {
             document: Rc::new(RefCell::new(Node::new(NodeKind::Document))),
+            doctype_name: None,
+            base_href: None,
         };
 
         window
@@ -37,12 +43,33 @@ impl Window {
     pub fn document(&self) -> Rc<RefCell<Node>> {
         self.document.clone()
     }
+
+    /// The name of the document's DOCTYPE, e.g. "html" for `<!DOCTYPE html>`, or None if the
+    /// document doesn't have one. Legacy DOCTYPEs are kept as they are written.
+    pub fn doctype_name(&self) -> Option<String> {
+        self.doctype_name.clone()
+    }
+
+    pub fn set_doctype_name(&mut self, name: String) {
+        self.doctype_name = Some(name);
+    }
+
+    /// The href of the first `<base>` element with one, which relative URLs are resolved against.
+    /// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    pub fn base_href(&self) -> Option<String> {
+        self.base_href.clone()
+    }
+
+    pub fn set_base_href(&mut self, href: String) {
+        self.base_href = Some(href);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Node {
     pub kind: NodeKind,
     window: Weak<RefCell<Window>>,
+    // 子と次の兄弟だけを Rc で持ち、親や前の兄弟へは循環参照にならないように Weak で辿る
     parent: Weak<RefCell<Node>>,
     first_child: Option<Rc<RefCell<Node>>>,
     last_child: Weak<RefCell<Node>>,
@@ -124,6 +151,119 @@ impl Node {
             NodeKind::Element(ref e) => Some(e.kind()),
         }
     }
+
+    /// https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+    /// The node is taken as parsed with scripting disabled; see `to_html_with_scripting`.
+    pub fn to_html(&self) -> String {
+        self.to_html_with_scripting(false)
+    }
+
+    /// Serializes the node like `to_html`. If `scripting` is true, the text in `<noscript>` is
+    /// written as is, like the text in `<script>` and `<style>`, since it was parsed as raw text.
+    pub fn to_html_with_scripting(&self, scripting: bool) -> String {
+        let mut result = String::new();
+
+        match self.kind {
+            NodeKind::Document => self.children_to_html(&mut result, scripting),
+            NodeKind::Element(ref e) => {
+                result.push('<');
+                result.push_str(&e.kind().to_string());
+                for attr in e.attributes() {
+                    result.push_str(&format!(
+                        " {}=\"{}\"",
+                        attr.name(),
+                        escape_html(&attr.value(), true)
+                    ));
+                }
+
+                if e.is_void_element() {
+                    result.push_str(" />");
+                    return result;
+                }
+
+                result.push('>');
+                self.children_to_html(&mut result, scripting);
+                result.push_str(&format!("</{}>", e.kind()));
+            }
+            NodeKind::Text(ref s) => {
+                // 生のテキストとして読んだ要素の中身は、エスケープすると元のスクリプトなどと変わってしまう
+                let raw_text = match self.parent().upgrade() {
+                    Some(parent) => match parent.borrow().element_kind() {
+                        Some(ElementKind::Script) | Some(ElementKind::Style) => true,
+                        Some(ElementKind::Noscript) => scripting,
+                        _ => false,
+                    },
+                    None => false,
+                };
+                if raw_text {
+                    result.push_str(s);
+                } else {
+                    result.push_str(&escape_html(s, false));
+                }
+            }
+        }
+
+        result
+    }
+
+    fn children_to_html(&self, result: &mut String, scripting: bool) {
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            result.push_str(&c.borrow().to_html_with_scripting(scripting));
+            child = c.borrow().next_sibling();
+        }
+    }
+}
+
+fn escape_html(s: &str, in_attribute: bool) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' if !in_attribute => result.push_str("&lt;"),
+            '>' if !in_attribute => result.push_str("&gt;"),
+            '"' if in_attribute => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Returns true if changing the attribute `name` can change the rendering, e.g. because
+/// selectors or the label of an `<input>` depend on it.
+pub fn affects_layout(name: &str) -> bool {
+    matches!(name, "id" | "class" | "type" | "value")
+}
+
+/// Pre-order depth-first traversal over a node and its descendants.
+/// No borrow is held between calls to `next`, so yielded nodes can be borrowed mutably.
+#[derive(Debug, Clone)]
+pub struct NodeIterator {
+    stack: Vec<Rc<RefCell<Node>>>,
+}
+
+impl NodeIterator {
+    pub fn new(root: Rc<RefCell<Node>>) -> Self {
+        Self { stack: vec![root] }
+    }
+}
+
+impl Iterator for NodeIterator {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        let mut children = Vec::new();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+            children.push(c);
+        }
+        self.stack.extend(children.into_iter().rev());
+
+        Some(node)
+    }
 }
 
 impl PartialEq for Node {
@@ -180,11 +320,35 @@ impl Element {
             ElementKind::Body
             | ElementKind::H1
             | ElementKind::H2
-            | ElementKind::P => true,
+            | ElementKind::H3
+            | ElementKind::H4
+            | ElementKind::H5
+            | ElementKind::H6
+            | ElementKind::Div
+            | ElementKind::Center
+            | ElementKind::P
+            | ElementKind::Form
+            | ElementKind::Table
+            | ElementKind::Tbody
+            | ElementKind::Tr
+            | ElementKind::Td
+            | ElementKind::Th => true,
             _ => false,
         }
     }
 
+    /// https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+    pub fn is_void_element(&self) -> bool {
+        matches!(
+            self.kind,
+            ElementKind::Base
+                | ElementKind::Link
+                | ElementKind::Meta
+                | ElementKind::Br
+                | ElementKind::Input
+        )
+    }
+
     pub fn attributes(&self) -> Vec<Attribute> {
         self.attributes.clone()
     }
@@ -197,6 +361,37 @@ impl Element {
         }
         None
     }
+
+    /// Updates the value of the attribute `name`, or appends the attribute if it doesn't exist.
+    /// https://dom.spec.whatwg.org/#dom-element-setattribute
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        if let Some(attr) = self.attributes.iter_mut().find(|attr| attr.name() == name) {
+            attr.set_value(value.to_string());
+            return;
+        }
+
+        let mut attr = Attribute::new();
+        for c in name.chars() {
+            attr.add_char(c, true);
+        }
+        for c in value.chars() {
+            attr.add_char(c, false);
+        }
+        self.attributes.push(attr);
+    }
+
+    /// Returns the space-separated tokens of the class attribute.
+    /// https://dom.spec.whatwg.org/#dom-element-classlist
+    pub fn class_list(&self) -> Vec<String> {
+        match self.get_attribute("class") {
+            Some(class) => class.split_ascii_whitespace().map(String::from).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn has_class(&self, name: &str) -> bool {
+        self.class_list().iter().any(|class| class == name)
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -206,19 +401,67 @@ pub enum ElementKind {
     Html,
     /// https://html.spec.whatwg.org/multipage/semantics.html#the-head-element
     Head,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-title-element
+    Title,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-base-element
+    Base,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-link-element
+    Link,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-meta-element
+    Meta,
     /// https://html.spec.whatwg.org/multipage/semantics.html#the-style-element
     Style,
     /// https://html.spec.whatwg.org/multipage/scripting.html#the-script-element
     Script,
+    /// https://html.spec.whatwg.org/multipage/scripting.html#the-noscript-element
+    Noscript,
     /// https://html.spec.whatwg.org/multipage/sections.html#the-body-element
     Body,
+    /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-div-element
+    Div,
+    /// https://html.spec.whatwg.org/multipage/obsolete.html#center
+    Center,
     /// https://html.spec.whatwg.org/multipage/grouping-content.html#the-p-element
     P,
     /// https://html.spec.whatwg.org/multipage/sections.html#the-h1,-h2,-h3,-h4,-h5,-and-h6-elements
     H1,
     H2,
+    H3,
+    H4,
+    H5,
+    H6,
     /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-a-element
     A,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-b-element
+    B,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-i-element
+    I,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-strong-element
+    Strong,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-em-element
+    Em,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-code-element
+    Code,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-kbd-element
+    Kbd,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-samp-element
+    Samp,
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-br-element
+    Br,
+    /// https://html.spec.whatwg.org/multipage/forms.html#the-form-element
+    Form,
+    /// https://html.spec.whatwg.org/multipage/input.html#the-input-element
+    Input,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-table-element
+    Table,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-tbody-element
+    Tbody,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-tr-element
+    Tr,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-td-element
+    Td,
+    /// https://html.spec.whatwg.org/multipage/tables.html#the-th-element
+    Th,
 }
 
 impl FromStr for ElementKind {
@@ -228,13 +471,39 @@ impl FromStr for ElementKind {
         match s {
             "html" => Ok(ElementKind::Html),
             "head" => Ok(ElementKind::Head),
+            "title" => Ok(ElementKind::Title),
+            "base" => Ok(ElementKind::Base),
+            "link" => Ok(ElementKind::Link),
+            "meta" => Ok(ElementKind::Meta),
             "style" => Ok(ElementKind::Style),
             "script" => Ok(ElementKind::Script),
+            "noscript" => Ok(ElementKind::Noscript),
             "body" => Ok(ElementKind::Body),
+            "div" => Ok(ElementKind::Div),
+            "center" => Ok(ElementKind::Center),
             "p" => Ok(ElementKind::P),
             "h1" => Ok(ElementKind::H1),
             "h2" => Ok(ElementKind::H2),
+            "h3" => Ok(ElementKind::H3),
+            "h4" => Ok(ElementKind::H4),
+            "h5" => Ok(ElementKind::H5),
+            "h6" => Ok(ElementKind::H6),
             "a" => Ok(ElementKind::A),
+            "b" => Ok(ElementKind::B),
+            "i" => Ok(ElementKind::I),
+            "strong" => Ok(ElementKind::Strong),
+            "em" => Ok(ElementKind::Em),
+            "code" => Ok(ElementKind::Code),
+            "kbd" => Ok(ElementKind::Kbd),
+            "samp" => Ok(ElementKind::Samp),
+            "br" => Ok(ElementKind::Br),
+            "form" => Ok(ElementKind::Form),
+            "input" => Ok(ElementKind::Input),
+            "table" => Ok(ElementKind::Table),
+            "tbody" => Ok(ElementKind::Tbody),
+            "tr" => Ok(ElementKind::Tr),
+            "td" => Ok(ElementKind::Td),
+            "th" => Ok(ElementKind::Th),
             _ => Err(format!("unimplemented element name {:?}", s)),
         }
     }
@@ -245,14 +514,205 @@ impl Display for ElementKind {
         let s = match self {
             ElementKind::Html => "html",
             ElementKind::Head => "head",
+            ElementKind::Title => "title",
+            ElementKind::Base => "base",
+            ElementKind::Link => "link",
+            ElementKind::Meta => "meta",
             ElementKind::Style => "style",
             ElementKind::Script => "script",
+            ElementKind::Noscript => "noscript",
             ElementKind::Body => "body",
+            ElementKind::Div => "div",
+            ElementKind::Center => "center",
             ElementKind::H1 => "h1",
             ElementKind::H2 => "h2",
+            ElementKind::H3 => "h3",
+            ElementKind::H4 => "h4",
+            ElementKind::H5 => "h5",
+            ElementKind::H6 => "h6",
             ElementKind::P => "p",
             ElementKind::A => "a",
+            ElementKind::B => "b",
+            ElementKind::I => "i",
+            ElementKind::Strong => "strong",
+            ElementKind::Em => "em",
+            ElementKind::Code => "code",
+            ElementKind::Kbd => "kbd",
+            ElementKind::Samp => "samp",
+            ElementKind::Br => "br",
+            ElementKind::Form => "form",
+            ElementKind::Input => "input",
+            ElementKind::Table => "table",
+            ElementKind::Tbody => "tbody",
+            ElementKind::Tr => "tr",
+            ElementKind::Td => "td",
+            ElementKind::Th => "th",
         };
         write!(f, "{}", s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::append_child;
+
+    #[test]
+    fn test_node_iterator() {
+        use crate::renderer::html::parser::HtmlParser;
+        use crate::renderer::html::token::HtmlTokenizer;
+
+        let html = r#"<html>
+<head></head>
+<body>
+  <h1 id="title">H1 title</h1>
+  <h2 class="class">H2 title</h2>
+  <p>Test text.</p>
+  <p>
+    <a href="example.com">Link1</a>
+    <a href="example.com">Link2</a>
+  </p>
+</body>
+</html>"#
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        let elements = NodeIterator::new(document.clone())
+            .filter(|n| n.borrow().get_element().is_some())
+            .count();
+        assert_eq!(9, elements);
+
+        let order: Vec<String> = NodeIterator::new(document)
+            .filter_map(|n| n.borrow().element_kind())
+            .map(|kind| kind.to_string())
+            .collect();
+        assert_eq!(
+            vec!["html", "head", "body", "h1", "h2", "p", "p", "a", "a"],
+            order
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let mut p_element = Element::new("p", Vec::new());
+        p_element.set_attribute("id", "a");
+        p_element.set_attribute("class", "b \"c\"");
+        let p = Rc::new(RefCell::new(Node::new(NodeKind::Element(p_element))));
+        append_child(&p, &Rc::new(RefCell::new(Node::new(NodeKind::Text("x < y".to_string())))));
+        append_child(
+            &p,
+            &Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new("br", Vec::new()))))),
+        );
+        let mut a_element = Element::new("a", Vec::new());
+        a_element.set_attribute("href", "http://example.com");
+        let a = Rc::new(RefCell::new(Node::new(NodeKind::Element(a_element))));
+        append_child(&a, &Rc::new(RefCell::new(Node::new(NodeKind::Text("link".to_string())))));
+        append_child(&p, &a);
+
+        assert_eq!(
+            "<p id=\"a\" class=\"b &quot;c&quot;\">x &lt; y<br /><a href=\"http://example.com\">link</a></p>",
+            p.borrow().to_html(),
+        );
+    }
+
+    #[test]
+    fn test_navigation() {
+        use crate::renderer::html::parser::HtmlParser;
+        use crate::renderer::html::token::HtmlTokenizer;
+
+        let html = "<html><body><div><p><a>1</a><a><b>2</b></a><a>3</a></p></div></body></html>";
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+
+        let b = NodeIterator::new(document.clone())
+            .find(|n| n.borrow().element_kind() == Some(ElementKind::B))
+            .expect("failed to find b");
+
+        // 深い位置の要素から Document まで親を辿る
+        let mut ancestors = Vec::new();
+        let mut current = b.borrow().parent().upgrade();
+        while let Some(node) = current {
+            current = node.borrow().parent().upgrade();
+            ancestors.push(node);
+        }
+        assert_eq!(
+            vec!["a", "p", "div", "body", "html"],
+            ancestors
+                .iter()
+                .filter_map(|n| n.borrow().element_kind())
+                .map(|kind| kind.to_string())
+                .collect::<Vec<String>>()
+        );
+        assert!(Rc::ptr_eq(&document, ancestors.last().expect("document should exist")));
+
+        // 兄弟を前後に辿る
+        let a = ancestors[0].clone();
+        let first = a.borrow().previous_sibling().upgrade().expect("first a should exist");
+        let third = a.borrow().next_sibling().expect("third a should exist");
+        assert!(first.borrow().previous_sibling().upgrade().is_none());
+        assert!(third.borrow().next_sibling().is_none());
+        assert!(Rc::ptr_eq(
+            &a,
+            &first.borrow().next_sibling().expect("second a should exist")
+        ));
+        assert!(Rc::ptr_eq(
+            &a,
+            &third.borrow().previous_sibling().upgrade().expect("second a should exist")
+        ));
+        assert!(Rc::ptr_eq(
+            &first,
+            &ancestors[1].borrow().first_child().expect("p should have children")
+        ));
+
+        // 親への参照は Weak なので、木を手放せばすべてのノードが解放される
+        let weak_b = Rc::downgrade(&b);
+        drop((b, a, first, third, ancestors, document, window));
+        assert!(weak_b.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_set_attribute() {
+        let attributes = |e: &Element| {
+            e.attributes()
+                .iter()
+                .map(|attr| (attr.name(), attr.value()))
+                .collect::<Vec<(String, String)>>()
+        };
+
+        let mut e = Element::new("a", Vec::new());
+        e.set_attribute("href", "/a");
+        e.set_attribute("href", "/b");
+        assert_eq!(vec![("href".to_string(), "/b".to_string())], attributes(&e));
+
+        e.set_attribute("title", "T");
+        assert_eq!(
+            vec![
+                ("href".to_string(), "/b".to_string()),
+                ("title".to_string(), "T".to_string()),
+            ],
+            attributes(&e)
+        );
+        assert!(!affects_layout("title"));
+        assert!(affects_layout("class"));
+    }
+
+    #[test]
+    fn test_class_list() {
+        let mut e = Element::new("p", Vec::new());
+        e.set_attribute("class", " foo  bar\tfoo ");
+        assert_eq!(vec!["foo", "bar", "foo"], e.class_list());
+        assert!(e.has_class("foo"));
+        assert!(e.has_class("bar"));
+        assert!(!e.has_class("baz"));
+        assert!(!e.has_class("foo bar"));
+
+        let mut e = Element::new("p", Vec::new());
+        e.set_attribute("class", "");
+        assert!(e.class_list().is_empty());
+        assert!(!e.has_class(""));
+
+        let e = Element::new("p", Vec::new());
+        assert!(e.class_list().is_empty());
+    }
+}
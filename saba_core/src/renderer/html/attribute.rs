@@ -28,6 +28,10 @@ impl Attribute {
         self.value.clone()
     }
 
+    pub fn set_value(&mut self, value: String) {
+        self.value = value;
+    }
+
     pub fn add_char(&mut self, c: char, is_name: bool) {
         if is_name {
             self.name.push(c);
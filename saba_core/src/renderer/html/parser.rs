@@ -1,9 +1,13 @@
+use crate::renderer::dom::api::{append_child, remove_child};
 use crate::renderer::dom::node::Element;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeIterator;
 use crate::renderer::dom::node::NodeKind;
 use crate::renderer::dom::node::Window;
 use crate::renderer::html::token::HtmlTokenizer;
+use crate::renderer::html::token::ParseError;
+use crate::renderer::html::token::State;
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::vec::Vec;
@@ -34,6 +38,11 @@ pub struct HtmlParser {
     original_insertion_mode: InsertionMode,
     stack_of_open_elements: Vec<Rc<RefCell<Node>>>,
     t: HtmlTokenizer,
+    /// ブロックコンテナの直下にある、空白だけのテキストノードを取り除くかどうか
+    whitespace_filter: bool,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#scripting-flag
+    scripting: bool,
+    errors: Vec<ParseError>,
 }
 
 impl HtmlParser {
@@ -44,13 +53,84 @@ impl HtmlParser {
             original_insertion_mode: InsertionMode::Initial,
             stack_of_open_elements: Vec::new(),
             t,
+            whitespace_filter: true,
+            scripting: false,
+            errors: Vec::new(),
         }
     }
 
+    /// Returns the errors found while tokenizing and constructing the tree, in the order they
+    /// were found. The parser recovers from them, so a tree is built even if this isn't empty.
+    pub fn parse_errors(&self) -> Vec<ParseError> {
+        let mut errors = self.errors.clone();
+        errors.extend(self.t.parse_errors());
+        errors
+    }
+
+    fn parse_error(&mut self, error: ParseError) {
+        // トークナイザのエラーと見つかった順に並ぶように、先に取り出しておく
+        let tokenizer_errors = self.t.take_parse_errors();
+        self.errors.extend(tokenizer_errors);
+        self.errors.push(error);
+    }
+
+    /// Sets whether whitespace-only text nodes that are direct children of block containers are
+    /// dropped. It's enabled by default.
+    pub fn with_whitespace_filter(mut self, enabled: bool) -> Self {
+        self.whitespace_filter = enabled;
+        self
+    }
+
+    /// Sets whether scripting is enabled for the document. If it is, the contents of
+    /// `<noscript>` are read as text instead of markup. It's disabled by default.
+    pub fn with_scripting(mut self, enabled: bool) -> Self {
+        self.scripting = enabled;
+        self
+    }
+
     pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
+        let window = self.build_tree();
+        if self.whitespace_filter {
+            remove_whitespace_text(&window.borrow().document());
+        }
+        window
+    }
+
+    /// Parses `data` as the next chunk of a document given to a streaming tokenizer
+    /// (`HtmlTokenizer::streaming`). The tree built so far can be read by `window` to start
+    /// rendering before the whole document arrives. `Page` doesn't use this yet; see
+    /// `HtmlTokenizer::streaming`.
+    pub fn feed(&mut self, data: &str) {
+        self.t.feed(data);
+        self.build_tree();
+    }
+
+    /// Parses the rest of the chunks given by `feed` and returns the completed tree.
+    pub fn finish(&mut self) -> Rc<RefCell<Window>> {
+        self.t.finish();
+        self.construct_tree()
+    }
+
+    /// Returns the tree built so far.
+    pub fn window(&self) -> Rc<RefCell<Window>> {
+        self.window.clone()
+    }
+
+    fn build_tree(&mut self) -> Rc<RefCell<Window>> {
         let mut token = self.t.next();
 
         while token.is_some() {
+            // https://html.spec.whatwg.org/multipage/parsing.html#the-initial-insertion-mode
+            // DOCTYPE は文書の先頭でのみ有効で、それ以外の場所では無視する
+            if let Some(HtmlToken::Doctype { ref name }) = token {
+                if self.mode == InsertionMode::Initial {
+                    self.window.borrow_mut().set_doctype_name(name.clone());
+                    self.mode = InsertionMode::BeforeHtml;
+                }
+                token = self.t.next();
+                continue;
+            }
+
             match self.mode {
                 InsertionMode::Initial => {
                     if let Some(HtmlToken::Char(_)) = token {
@@ -132,13 +212,33 @@ impl HtmlParser {
                             self_closing: _,
                             ref attributes
                         }) => {
-                            if tag == "style" || tag == "script" {
+                            let rawtext_noscript = tag == "noscript" && self.scripting;
+                            if tag == "title" || tag == "style" || tag == "script" || rawtext_noscript {
                                 self.insert_element(tag, attributes.to_vec());
+                                // 終了タグまでの `<` や `>` はタグではなく文字として読む
+                                if tag != "title" {
+                                    self.t.switch_context(State::ScriptData);
+                                }
                                 self.original_insertion_mode = self.mode;
                                 self.mode = InsertionMode::Text;
                                 token = self.t.next();
                                 continue;
                             }
+                            if tag == "base" || tag == "link" || tag == "meta" {
+                                // これらは空要素なので、挿入したらすぐにスタックから取り除く
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                // href を持つ最初の base だけが有効
+                                if tag == "base" && self.window.borrow().base_href().is_none() {
+                                    if let Some(href) =
+                                        attributes.iter().find(|a| a.name() == "href")
+                                    {
+                                        self.window.borrow_mut().set_base_href(href.value());
+                                    }
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
                             if tag == "body" {
                                 self.pop_until(ElementKind::Head);
                                 self.mode = InsertionMode::AfterHead;
@@ -158,6 +258,9 @@ impl HtmlParser {
                                 continue;
                             }
                         },
+                        Some(HtmlToken::Doctype { .. }) => {
+                            unreachable!("DOCTYPE should be handled before the insertion modes")
+                        },
                         Some(HtmlToken::Eof) | None => {
                             return self.window.clone();
                         },
@@ -206,16 +309,89 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
-                            "h1" | "h2" => {
+                            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "a" | "b" | "i" | "strong" | "em" | "code" | "kbd" | "samp" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "div" | "center" | "form" => {
                                 self.insert_element(tag, attributes.to_vec());
                                 token = self.t.next();
                                 continue;
                             }
-                            "a" => {
+                            "table" => {
                                 self.insert_element(tag, attributes.to_vec());
                                 token = self.t.next();
                                 continue;
                             }
+                            "noscript" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                // スクリプトが有効なら、中身はマークアップではなく文字として読む
+                                if self.scripting {
+                                    self.t.switch_context(State::ScriptData);
+                                    self.original_insertion_mode = self.mode;
+                                    self.mode = InsertionMode::Text;
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+                            "script" | "style" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                // <head> の中と同じく、終了タグまでの `<` や `>` は文字として読む
+                                self.t.switch_context(State::ScriptData);
+                                self.original_insertion_mode = self.mode;
+                                self.mode = InsertionMode::Text;
+                                token = self.t.next();
+                                continue;
+                            }
+                            "tbody" => {
+                                if self.contain_in_stack(ElementKind::Tbody) {
+                                    self.pop_until(ElementKind::Tbody);
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "tr" => {
+                                // </tr> は省略できるので、開いている行があれば閉じる
+                                if self.contain_in_stack(ElementKind::Tr) {
+                                    self.pop_until(ElementKind::Tr);
+                                }
+                                self.insert_implied_tbody();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "td" | "th" => {
+                                // </td> と </th> は省略できるので、開いているセルがあれば閉じる
+                                if self.contain_in_stack(ElementKind::Td) {
+                                    self.pop_until(ElementKind::Td);
+                                }
+                                if self.contain_in_stack(ElementKind::Th) {
+                                    self.pop_until(ElementKind::Th);
+                                }
+                                if !self.contain_in_stack(ElementKind::Tr) {
+                                    self.insert_implied_tbody();
+                                    self.insert_element("tr", Vec::new());
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "link" | "br" | "input" => {
+                                // 空要素なので、挿入したらすぐにスタックから取り除く
+                                let element_kind = ElementKind::from_str(tag)
+                                    .expect("failed to convert string to ElementKind");
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(element_kind);
+                                token = self.t.next();
+                                continue;
+                            }
                             _ => {
                                 token = self.t.next();
                             }
@@ -240,25 +416,19 @@ impl HtmlParser {
                                     }
                                     continue;
                                 }
-                                "p" => {
-                                    let element_kind = ElementKind::from_str(tag)
-                                        .expect("failed to convert string to ElementKind");
-                                    token = self.t.next();
-                                    self.pop_until(element_kind);
-                                    continue;
-                                }
-                                "h1" | "h2" => {
-                                    let element_kind = ElementKind::from_str(tag)
-                                        .expect("failed to convert string to ElementKind");
-                                    token = self.t.next();
-                                    self.pop_until(element_kind);
-                                    continue;
-                                }
-                                "a" => {
+                                "div" | "center" | "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "a" | "b"
+                                | "i" | "strong" | "em" | "code" | "kbd" | "samp" | "form" | "table" | "tbody"
+                                | "tr" | "td" | "th" | "noscript" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
+                                    // 対応する開始タグがスタックにない終了タグは無視する。
+                                    // ある場合は、間にある要素ごと閉じる（<b><i>text</b></i> など）
+                                    if self.contain_in_stack(element_kind) {
+                                        self.pop_until(element_kind);
+                                    } else {
+                                        self.parse_error(ParseError::UnexpectedEndTag(tag.clone()));
+                                    }
                                     token = self.t.next();
-                                    self.pop_until(element_kind);
                                     continue;
                                 }
                                 _ => {
@@ -267,6 +437,8 @@ impl HtmlParser {
                             }
                         }
                         Some(HtmlToken::Eof) | None => {
+                            // 閉じられていない要素は EOF で全て閉じる
+                            self.pop_all();
                             return self.window.clone();
                         }
                         Some(HtmlToken::Char(c)) => {
@@ -274,6 +446,9 @@ impl HtmlParser {
                             token = self.t.next();
                             continue;
                         }
+                        Some(HtmlToken::Doctype { .. }) => {
+                            unreachable!("DOCTYPE should be handled before the insertion modes")
+                        }
                     }
                 },
                 InsertionMode::Text => {
@@ -294,6 +469,18 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            if tag == "noscript" {
+                                self.pop_until(ElementKind::Noscript);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
+                            if tag == "title" {
+                                self.pop_until(ElementKind::Title);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
                         }
                         Some(HtmlToken::Char(c)) => {
                             self.insert_char(c);
@@ -343,6 +530,10 @@ impl HtmlParser {
             }
         }
 
+        // 入力の続きが来たら、開いている要素の中に追加していく
+        if self.t.is_finished() {
+            self.pop_all();
+        }
         self.window.clone()
     }
 
@@ -358,37 +549,7 @@ impl HtmlParser {
         };
         let node = Rc::new(RefCell::new(self.create_element(tag, attributes)));
 
-        if current.borrow().first_child().is_some() {
-            let mut last_sibling = current.borrow().first_child();
-            loop {
-                last_sibling = match last_sibling {
-                    Some(ref node) => {
-                        if node.borrow().next_sibling().is_some() {
-                            node.borrow().next_sibling()
-                        } else {
-                            break;
-                        }
-                    }
-                    None => unimplemented!("last_sibling should be Some"),
-                };
-            }
-
-            last_sibling
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-
-            node
-                .borrow_mut()
-                .set_previous_sibling(Rc::downgrade(
-                    &current.borrow().first_child().expect("failed to get a first child"),
-                ));
-        } else {
-            current.borrow_mut().set_first_child(Some(node.clone()));
-        }
-
-        current.borrow_mut().set_last_child(Rc::downgrade(&node));
-        node.borrow_mut().set_parent(Rc::downgrade(&current));
+        append_child(&current, &node);
 
         self.stack_of_open_elements.push(node);
     }
@@ -407,6 +568,18 @@ impl HtmlParser {
         false
     }
 
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intable
+    /// <table> の直下に行が来たときは、<tbody> を補う
+    fn insert_implied_tbody(&mut self) {
+        let current_is_table = match self.stack_of_open_elements.last() {
+            Some(n) => n.borrow().element_kind() == Some(ElementKind::Table),
+            None => false,
+        };
+        if current_is_table {
+            self.insert_element("tbody", Vec::new());
+        }
+    }
+
     fn pop_until(&mut self, element_kind: ElementKind) {
         assert!(
             self.contain_in_stack(element_kind),
@@ -426,6 +599,10 @@ impl HtmlParser {
         }
     }
 
+    fn pop_all(&mut self) {
+        self.stack_of_open_elements.clear();
+    }
+
     fn contain_in_stack(&self, element_kind: ElementKind) -> bool {
         for i in 0..self.stack_of_open_elements.len() {
             if self.stack_of_open_elements[i].borrow().element_kind() == Some(element_kind) {
@@ -448,42 +625,65 @@ impl HtmlParser {
             None => return,
         };
 
-        if let NodeKind::Text(ref mut s) = current.borrow_mut().kind {
-            s.push(c);
-            return;
-        }
-
-        if c == ' ' || c == '\n' {
-            return;
+        // 直前の子がテキストノードであれば、そこに文字を追加する
+        let last_child = current.borrow().last_child().upgrade();
+        if let Some(last_child) = last_child {
+            if let NodeKind::Text(ref mut s) = last_child.borrow_mut().kind {
+                s.push(c);
+                return;
+            }
         }
 
         let node = Rc::new(RefCell::new(self.create_char(c)));
+        append_child(&current, &node);
+    }
+}
 
-        if current.borrow().first_child().is_some() {
-            current
-                .borrow()
-                .first_child()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &current.borrow().first_child().expect("failed to get a first child"),
-            ));
-        } else {
-            current.borrow_mut().set_first_child(Some(node.clone()));
-        }
+/// ブロックコンテナの直下にある空白だけのテキストノードのうち、先頭や末尾、ブロック要素の隣にあるものは
+/// インデントや改行なので表示に影響しない。インライン要素の間やインライン要素の中の空白は単語の区切りなので残す
+fn remove_whitespace_text(root: &Rc<RefCell<Node>>) {
+    // 兄弟がない (ブロックの端) か、ブロック要素であれば境界とみなす
+    let is_boundary = |sibling: Option<Rc<RefCell<Node>>>| match sibling {
+        Some(sibling) => match sibling.borrow().kind() {
+            NodeKind::Element(e) => e.is_block_element(),
+            _ => false,
+        },
+        None => true,
+    };
 
-        current.borrow_mut().set_last_child(Rc::downgrade(&node));
-        node.borrow_mut().set_parent(Rc::downgrade(&current));
+    // 取り除くと兄弟が変わるので、先に対象をすべて決めておく
+    let targets: Vec<Rc<RefCell<Node>>> = NodeIterator::new(root.clone())
+        .filter(|node| match node.borrow().kind() {
+            NodeKind::Text(s) => s.chars().all(|c| c.is_ascii_whitespace()),
+            _ => false,
+        })
+        .filter(|node| {
+            let is_block_container = match node.borrow().parent().upgrade() {
+                Some(parent) => match parent.borrow().kind() {
+                    NodeKind::Document => true,
+                    NodeKind::Element(e) => e.is_block_element(),
+                    NodeKind::Text(_) => false,
+                },
+                None => false,
+            };
+            let previous = node.borrow().previous_sibling().upgrade();
+            let next = node.borrow().next_sibling();
+            is_block_container && (is_boundary(previous) || is_boundary(next))
+        })
+        .collect();
 
-        self.stack_of_open_elements.push(node);
+    for node in targets {
+        let parent = node.borrow().parent().upgrade();
+        if let Some(parent) = parent {
+            remove_child(&parent, &node);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::renderer::dom::node::NodeIterator;
     use alloc::string::ToString;
     use alloc::vec;
 
@@ -639,4 +839,367 @@ mod tests {
             text,
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_unclosed_p() {
+        let html = "<html><head></head><body><p>text".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let p = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new("p", Vec::new()))))),
+            p,
+        );
+
+        let text = p
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of p");
+        assert!(matches!(text.borrow().kind(), NodeKind::Text(s) if s == "text"));
+    }
+
+    #[test]
+    fn test_misnested_tags() {
+        let html = "<html><head></head><body><b><i>text</b></i>after</body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head");
+
+        let b = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new("b", Vec::new()))))),
+            b,
+        );
+
+        let i = b
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of b");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new("i", Vec::new()))))),
+            i,
+        );
+
+        let text = i
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of i");
+        assert!(matches!(text.borrow().kind(), NodeKind::Text(s) if s == "text"));
+
+        let after = b
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of b");
+        assert!(matches!(after.borrow().kind(), NodeKind::Text(s) if s == "after"));
+    }
+
+    #[test]
+    fn test_element_after_text() {
+        let html = "<html><head></head><body><form>Name: <input name=\"q\">!</form></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let form = NodeIterator::new(document)
+            .find(|n| n.borrow().element_kind() == Some(ElementKind::Form))
+            .expect("failed to find a form");
+
+        let text = form
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of form");
+        assert!(matches!(text.borrow().kind(), NodeKind::Text(s) if s == "Name: "));
+        assert!(text.borrow().first_child().is_none());
+
+        let input = text
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of text");
+        assert_eq!(Some(ElementKind::Input), input.borrow().element_kind());
+        assert!(input.borrow().first_child().is_none());
+
+        let last = input
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of input");
+        assert!(matches!(last.borrow().kind(), NodeKind::Text(s) if s == "!"));
+        assert!(Rc::ptr_eq(
+            &last,
+            &form.borrow().last_child().upgrade().expect("failed to get a last child")
+        ));
+    }
+
+    #[test]
+    fn test_link_in_head() {
+        let html = "<html><head><link rel=\"stylesheet\" href=\"a.css\"><title>t</title></head><body></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let head = NodeIterator::new(document)
+            .find(|n| n.borrow().element_kind() == Some(ElementKind::Head))
+            .expect("failed to find a head");
+
+        let link = head
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of head");
+        assert_eq!(Some(ElementKind::Link), link.borrow().element_kind());
+        assert!(link.borrow().first_child().is_none());
+
+        let title = link
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of link");
+        assert_eq!(Some(ElementKind::Title), title.borrow().element_kind());
+    }
+
+    #[test]
+    fn test_doctype() {
+        let html = "<!DOCTYPE html>\n<html><head></head><body><!DOCTYPE foo><p>a</p></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        assert_eq!(Some("html".to_string()), window.borrow().doctype_name());
+
+        let document = window.borrow().document();
+        let html = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document");
+        assert_eq!(Some(ElementKind::Html), html.borrow().element_kind());
+        assert!(html.borrow().next_sibling().is_none());
+        assert_eq!(
+            1,
+            NodeIterator::new(document)
+                .filter(|n| n.borrow().element_kind() == Some(ElementKind::P))
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_no_doctype() {
+        let html = "<html><head></head><body></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        assert_eq!(None, window.borrow().doctype_name());
+    }
+
+    #[test]
+    fn test_comments_between_paragraphs() {
+        let html = "<html><body><p>a</p><![CDATA[<p>x</p>]]><?php echo 1; ?><!-- <p>y</p> --><p>b</p></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let body = NodeIterator::new(window.borrow().document())
+            .find(|n| n.borrow().element_kind() == Some(ElementKind::Body))
+            .expect("failed to find a body");
+
+        let first = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        let second = first
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of p");
+        assert_eq!(Some(ElementKind::P), first.borrow().element_kind());
+        assert_eq!(Some(ElementKind::P), second.borrow().element_kind());
+        assert!(second.borrow().next_sibling().is_none());
+        assert_eq!("<body><p>a</p><p>b</p></body>", body.borrow().to_html());
+    }
+
+    #[test]
+    fn test_script_and_style_contents() {
+        let html = "<html><head><script>if (a < b) {} </div></script><style>p > a { }</style></head></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        let text_of = |kind: ElementKind| {
+            let element = NodeIterator::new(document.clone())
+                .find(|n| n.borrow().element_kind() == Some(kind))
+                .expect("failed to find an element");
+            let text = element
+                .borrow()
+                .first_child()
+                .expect("failed to get a text");
+            let kind = text.borrow().kind.clone();
+            match kind {
+                NodeKind::Text(s) => s,
+                _ => panic!("first child should be a text"),
+            }
+        };
+        assert_eq!("if (a < b) {} </div>", text_of(ElementKind::Script));
+        assert_eq!("p > a { }", text_of(ElementKind::Style));
+    }
+
+    #[test]
+    fn test_base_href() {
+        let html = "<html><head><base target=\"_blank\"><base href=\"http://cdn.example.com/\"><base href=\"/ignored/\"></head><body></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        assert_eq!(Some("http://cdn.example.com/".to_string()), window.borrow().base_href());
+
+        let html = "<html><head></head><body></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        assert_eq!(None, window.borrow().base_href());
+    }
+
+    #[test]
+    fn test_whitespace_filter() {
+        let html = "<html>\n<head></head>\n<body>\n  <div>\n    <p><b>a</b> <i>b</i></p>\n  </div>\n</body>\n</html>";
+        let child_counts = |whitespace_filter: bool| {
+            let window = HtmlParser::new(HtmlTokenizer::new(html.to_string()))
+                .with_whitespace_filter(whitespace_filter)
+                .construct_tree();
+            let document = window.borrow().document();
+            let count = |kind: ElementKind| {
+                let node = NodeIterator::new(document.clone())
+                    .find(|n| n.borrow().element_kind() == Some(kind))
+                    .expect("element should exist");
+                let mut count = 0;
+                let mut child = node.borrow().first_child();
+                while let Some(c) = child {
+                    count += 1;
+                    child = c.borrow().next_sibling();
+                }
+                count
+            };
+            (count(ElementKind::Body), count(ElementKind::Div), count(ElementKind::P))
+        };
+
+        // 既定では、インデントだけのテキストノードは取り除かれるが、<b> と <i> の間の空白は残る
+        assert_eq!((1, 1, 3), child_counts(true));
+        assert_eq!((3, 3, 3), child_counts(false));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let html = "<html><body><p>text</div></p><a href=\"/x\"title=\"t\">link</a></b></body></html>";
+        let mut parser = HtmlParser::new(HtmlTokenizer::new(html.to_string()));
+        let window = parser.construct_tree();
+        assert_eq!(
+            vec![
+                ParseError::UnexpectedEndTag("div".to_string()),
+                ParseError::MissingWhitespaceBetweenAttributes,
+                ParseError::UnexpectedEndTag("b".to_string()),
+            ],
+            parser.parse_errors()
+        );
+
+        // エラーがあっても木は作られる
+        let a = NodeIterator::new(window.borrow().document())
+            .find(|n| n.borrow().element_kind() == Some(ElementKind::A))
+            .expect("a should exist");
+        let a = a.borrow().get_element().expect("a should be an element");
+        assert_eq!(Some("/x".to_string()), a.get_attribute("href"));
+        assert_eq!(Some("t".to_string()), a.get_attribute("title"));
+    }
+
+    #[test]
+    fn test_feed_one_char_at_a_time() {
+        let html = "<!DOCTYPE html><html><head><title>Sample</title><style>p { color: red; }</style></head><body><h1 id=\"title\">H1 title</h1><p>Text <a href=\"/next\">link</a><br></p><script>var a = '</p>';</script><ul><li>1<li>2</ul>text</body></html>";
+        let expected = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+
+        let mut parser = HtmlParser::new(HtmlTokenizer::streaming());
+        for c in html.chars() {
+            parser.feed(&c.to_string());
+        }
+        // 最後まで読む前でも、届いた分の木はできている
+        assert!(parser.window().borrow().document().borrow().first_child().is_some());
+        let window = parser.finish();
+
+        assert_eq!(
+            expected.borrow().document().borrow().to_html(),
+            window.borrow().document().borrow().to_html()
+        );
+        assert_eq!(
+            expected.borrow().doctype_name(),
+            window.borrow().doctype_name()
+        );
+    }
+
+    #[test]
+    fn test_noscript() {
+        let html = "<html><body><noscript><p>no <b>js</b></p></noscript>text</body></html>";
+        let body_of = |scripting: bool| {
+            let window = HtmlParser::new(HtmlTokenizer::new(html.to_string()))
+                .with_scripting(scripting)
+                .construct_tree();
+            let body = NodeIterator::new(window.borrow().document())
+                .find(|n| n.borrow().element_kind() == Some(ElementKind::Body))
+                .expect("failed to find a body");
+            let html = body.borrow().to_html_with_scripting(scripting);
+            html
+        };
+
+        assert_eq!(
+            "<body><noscript><p>no <b>js</b></p></noscript>text</body>",
+            body_of(false)
+        );
+        // スクリプトが有効なら、中身は1つのテキストになり、そのまま書き出される
+        assert_eq!(
+            "<body><noscript><p>no <b>js</b></p></noscript>text</body>",
+            body_of(true)
+        );
+    }
+
+    #[test]
+    fn test_script_in_body() {
+        let html = "<html><body><p>a</p><script>if (a < b) { x = '<p>'; }</script><style>p > a { color: red; }</style><p>b</p></body></html>";
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let body = NodeIterator::new(window.borrow().document())
+            .find(|n| n.borrow().element_kind() == Some(ElementKind::Body))
+            .expect("failed to find a body");
+
+        // スクリプトとスタイルの中身は1つのテキストになり、後ろの <p> は body の子のまま
+        assert_eq!(
+            "<body><p>a</p><script>if (a < b) { x = '<p>'; }</script><style>p > a { color: red; }</style><p>b</p></body>",
+            body.borrow().to_html()
+        );
+    }
+
+    #[test]
+    fn test_table_implied_tags() {
+        let html = "<html><body><table>\n  <tr><td>a<td>b</tr>\n  <tr><th>c</th><td>d</table></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let body = NodeIterator::new(window.borrow().document())
+            .find(|n| n.borrow().element_kind() == Some(ElementKind::Body))
+            .expect("failed to find a body");
+
+        // <tbody> が補われ、省略された </td> と </tr> は次のセルや行の開始で閉じられる
+        assert_eq!(
+            "<body><table><tbody><tr><td>a</td><td>b</td></tr><tr><th>c</th><td>d</td></tr></tbody></table></body>",
+            body.borrow().to_html()
+        );
+    }
+}
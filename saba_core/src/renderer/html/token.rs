@@ -1,4 +1,4 @@
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use crate::renderer::html::attribute::Attribute;
 
@@ -10,6 +10,13 @@ pub struct HtmlTokenizer {
     latest_token: Option<HtmlToken>,
     input: Vec<char>,
     buf: String,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#appropriate-end-tag-token
+    last_start_tag: String,
+    errors: Vec<ParseError>,
+    /// 入力がすべて与えられたかどうか。false の間は、入力の終わりに来ても続きを待つ
+    finished: bool,
+    /// 入力の終わりで続きを待っているかどうか
+    waiting: bool,
 }
 
 impl HtmlTokenizer {
@@ -21,8 +28,54 @@ impl HtmlTokenizer {
             latest_token: None,
             input: html.chars().collect(),
             buf: String::new(),
+            last_start_tag: String::new(),
+            errors: Vec::new(),
+            finished: true,
+            waiting: false,
         }
     }
+
+    /// Creates a tokenizer whose input is given in chunks by `feed`, so that tokens can be read
+    /// before the whole document arrives. `finish` must be called after the last chunk.
+    ///
+    /// This is an API for embedders only. `Page` still parses the whole body at once, because
+    /// `HttpClient` decodes the transfer and content encodings after the response is received.
+    pub fn streaming() -> Self {
+        let mut tokenizer = Self::new(String::new());
+        tokenizer.finished = false;
+        tokenizer
+    }
+
+    /// Appends `data` to the input. A token split across chunks is returned once it's complete.
+    pub fn feed(&mut self, data: &str) {
+        assert!(!self.finished, "`feed` should not be called after `finish`");
+        self.input.extend(data.chars());
+    }
+
+    /// Marks the end of the input given by `feed`.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the parse errors found so far. Tokenizing continues after an error.
+    pub fn parse_errors(&self) -> Vec<ParseError> {
+        self.errors.clone()
+    }
+
+    /// Returns the parse errors found so far and clears them.
+    pub fn take_parse_errors(&mut self) -> Vec<ParseError> {
+        core::mem::take(&mut self.errors)
+    }
+
+    /// Switches the tokenizer state. The parser uses this to read the contents of `<script>`
+    /// and `<style>` as text.
+    pub fn switch_context(&mut self, state: State) {
+        self.state = state;
+    }
 }
 
 impl HtmlTokenizer {
@@ -36,6 +89,30 @@ impl HtmlTokenizer {
         c
     }
 
+    /// Returns true if the next input matches `s` ASCII case-insensitively.
+    fn next_input_matches(&self, s: &str) -> bool {
+        let len = s.chars().count();
+        if self.input.len() < self.pos + len {
+            return false;
+        }
+        self.input[self.pos..self.pos + len]
+            .iter()
+            .zip(s.chars())
+            .all(|(a, b)| a.eq_ignore_ascii_case(&b))
+    }
+
+    /// Returns true if the input ends with a prefix of `s` and more input may follow, so
+    /// `next_input_matches(s)` can't be decided yet.
+    fn needs_more_input(&self, s: &str) -> bool {
+        let rest = &self.input[self.pos..];
+        !self.finished
+            && rest.len() < s.chars().count()
+            && rest
+                .iter()
+                .zip(s.chars())
+                .all(|(a, b)| a.eq_ignore_ascii_case(&b))
+    }
+
     fn reconsume_input(&mut self) -> char {
         self.reconsume = false;
         self.input[self.pos - 1]
@@ -76,6 +153,9 @@ impl HtmlTokenizer {
 
         let t = self.latest_token.as_ref().cloned();
         self.latest_token = None;
+        if let Some(HtmlToken::StartTag { ref tag, .. }) = t {
+            self.last_start_tag = tag.clone();
+        }
         assert!(self.latest_token.is_none());
 
         t
@@ -138,11 +218,28 @@ impl Iterator for HtmlTokenizer {
     type Item = HtmlToken;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos >= self.input.len() {
+        // 続きを待っている間に入力が終わった場合は、一度に読んだ場合と同じように Eof を返す
+        if !self.reconsume && self.pos >= self.input.len() && !(self.finished && self.waiting) {
             return None;
         }
+        self.waiting = false;
 
         loop {
+            if !self.reconsume && self.pos >= self.input.len() {
+                // 続きの入力を待つ。途中まで読んだトークンは状態として残しておく
+                if !self.finished {
+                    self.waiting = true;
+                    return None;
+                }
+
+                // タグやコメントの途中で入力が終わった
+                if let Some(error) = eof_error(&self.state) {
+                    self.errors.push(error);
+                }
+                self.state = State::Data;
+                return Some(HtmlToken::Eof);
+            }
+
             let c = match self.reconsume {
                 true => self.reconsume_input(),
                 false => self.consume_next_input(),
@@ -167,6 +264,42 @@ impl Iterator for HtmlTokenizer {
                         continue;
                     }
 
+                    // `<!` の後が DOCTYPE なのかコメントなのかは、続きの入力が来るまで決められない
+                    if c == '!'
+                        && (self.needs_more_input("DOCTYPE")
+                            || self.needs_more_input("--")
+                            || self.needs_more_input("[CDATA["))
+                    {
+                        self.reconsume = true;
+                        return None;
+                    }
+
+                    if c == '!' && self.next_input_matches("DOCTYPE") {
+                        self.pos += "DOCTYPE".len();
+                        self.buf = String::new();
+                        self.state = State::Doctype;
+                        continue;
+                    }
+
+                    // https://html.spec.whatwg.org/multipage/parsing.html#markup-declaration-open-state
+                    // コメント、CDATA セクション、`<?php ...>` のような不正なコメントは読み飛ばす
+                    if c == '!' && self.next_input_matches("--") {
+                        self.pos += "--".len();
+                        self.state = State::Comment;
+                        continue;
+                    }
+
+                    if c == '!' && self.next_input_matches("[CDATA[") {
+                        self.pos += "[CDATA[".len();
+                        self.state = State::CdataSection;
+                        continue;
+                    }
+
+                    if c == '!' || c == '?' {
+                        self.state = State::BogusComment;
+                        continue;
+                    }
+
                     if c.is_ascii_alphabetic() {
                         self.reconsume = true;
                         self.state = State::TagName;
@@ -283,6 +416,10 @@ impl Iterator for HtmlTokenizer {
                         continue;
                     }
 
+                    if c == '>' {
+                        self.errors.push(ParseError::MissingAttributeValue);
+                    }
+
                     if c == '"' {
                         self.state = State::AttributeValueDoubleQuoted;
                         continue;
@@ -335,6 +472,11 @@ impl Iterator for HtmlTokenizer {
                         return Some(HtmlToken::Eof);
                     }
 
+                    if matches!(c, '"' | '\'' | '<' | '=' | '`') {
+                        self.errors
+                            .push(ParseError::UnexpectedCharacterInUnquotedAttributeValue(c));
+                    }
+
                     self.append_attribute(c, false);
                 },
                 State::AfterAttributeValueQuoted => {
@@ -357,8 +499,9 @@ impl Iterator for HtmlTokenizer {
                         return Some(HtmlToken::Eof);
                     }
 
+                    self.errors.push(ParseError::MissingWhitespaceBetweenAttributes);
                     self.reconsume = true;
-                    self.state = State::BeforeAttributeValue;
+                    self.state = State::BeforeAttributeName;
                 },
                 State::SelfClosingStartTag => {
                     if c == '>' {
@@ -407,7 +550,8 @@ impl Iterator for HtmlTokenizer {
                     return Some(HtmlToken::Char('<'));
                 },
                 State::ScriptDataEndTagName => {
-                    if c == '>' {
+                    // `<script>` の中の `</div>` のように、開始タグと対応しない終了タグは文字として扱う
+                    if c == '>' && self.buf.eq_ignore_ascii_case(&self.last_start_tag) {
                         self.state = State::Data;
                         return self.take_latest_token();
                     }
@@ -423,6 +567,50 @@ impl Iterator for HtmlTokenizer {
                     self.buf.push(c);
                     continue;
                 },
+                State::Doctype => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return Some(HtmlToken::Doctype {
+                            name: doctype_name(&self.buf),
+                        });
+                    }
+
+                    if self.is_eof() {
+                        return Some(HtmlToken::Eof);
+                    }
+
+                    self.buf.push(c);
+                },
+                State::Comment => {
+                    if c == '-' && self.needs_more_input("->") {
+                        self.reconsume = true;
+                        return None;
+                    }
+
+                    if c == '-' && self.next_input_matches("->") {
+                        self.pos += "->".len();
+                        self.state = State::Data;
+                        continue;
+                    }
+                },
+                State::CdataSection => {
+                    if c == ']' && self.needs_more_input("]>") {
+                        self.reconsume = true;
+                        return None;
+                    }
+
+                    if c == ']' && self.next_input_matches("]>") {
+                        self.pos += "]>".len();
+                        self.state = State::Data;
+                        continue;
+                    }
+                },
+                State::BogusComment => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        continue;
+                    }
+                },
                 State::TemporaryBuffer => {
                     self.reconsume = true;
 
@@ -454,10 +642,67 @@ pub enum HtmlToken {
     EndTag {
         tag: String,
     },
+    /// https://html.spec.whatwg.org/multipage/parsing.html#the-doctype
+    Doctype {
+        name: String,
+    },
     Char(char),
     Eof,
 }
 
+/// Non-fatal errors found while parsing HTML. The parser recovers from them and keeps going.
+/// https://html.spec.whatwg.org/multipage/parsing.html#parse-errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input ends right after `<` or `</`.
+    EofBeforeTagName,
+    /// The input ends inside a tag, e.g. in an attribute value without the closing quote.
+    EofInTag,
+    EofInDoctype,
+    EofInComment,
+    EofInCdata,
+    /// An attribute has `=` but no value, e.g. `<div id=>`.
+    MissingAttributeValue,
+    /// An attribute follows a quoted attribute value without whitespace, e.g. `<div a="1"b="2">`.
+    MissingWhitespaceBetweenAttributes,
+    /// An unquoted attribute value contains one of `"`, `'`, `<`, `=` or `` ` ``.
+    UnexpectedCharacterInUnquotedAttributeValue(char),
+    /// An end tag doesn't have a corresponding open element, e.g. `</p>` without `<p>`.
+    UnexpectedEndTag(String),
+}
+
+/// 各状態で入力が終わったときのエラー
+fn eof_error(state: &State) -> Option<ParseError> {
+    match state {
+        State::TagOpen | State::EndTagOpen => Some(ParseError::EofBeforeTagName),
+        State::TagName
+        | State::BeforeAttributeName
+        | State::AttributeName
+        | State::AfterAttributeName
+        | State::BeforeAttributeValue
+        | State::AttributeValueDoubleQuoted
+        | State::AttributeValueSingleQuoted
+        | State::AttributeValueUnquoted
+        | State::AfterAttributeValueQuoted
+        | State::SelfClosingStartTag => Some(ParseError::EofInTag),
+        State::Doctype => Some(ParseError::EofInDoctype),
+        State::Comment => Some(ParseError::EofInComment),
+        State::CdataSection => Some(ParseError::EofInCdata),
+        _ => None,
+    }
+}
+
+/// `<!DOCTYPE html>` の名前は小文字にする。PUBLIC 識別子などを持つ古い DOCTYPE は、
+/// そのままの文字列を残す
+fn doctype_name(s: &str) -> String {
+    let s = s.trim();
+    if s.contains(char::is_whitespace) {
+        s.to_string()
+    } else {
+        s.to_ascii_lowercase()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum State {
     /// https://html.spec.whatwg.org/multipage/parsing.html#data-state
@@ -487,6 +732,8 @@ pub enum State {
     /// https://html.spec.whatwg.org/multipage/parsing.html#self-closing-start-tag-state
     SelfClosingStartTag,
     /// https://html.spec.whatwg.org/multipage/parsing.html#script-data-state
+    /// `<style>` の RAWTEXT も、スクリプトのエスケープを除けば同じなのでこの状態で読む
+    /// https://html.spec.whatwg.org/multipage/parsing.html#rawtext-state
     ScriptData,
     /// https://html.spec.whatwg.org/multipage/parsing.html#script-data-less-than-sign-state
     ScriptDataLessThanSign,
@@ -494,6 +741,14 @@ pub enum State {
     ScriptDataEndTagOpen,
     /// https://html.spec.whatwg.org/multipage/parsing.html#script-data-end-tag-name-state
     ScriptDataEndTagName,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#doctype-state
+    Doctype,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#comment-state
+    Comment,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#cdata-section-state
+    CdataSection,
+    /// https://html.spec.whatwg.org/multipage/parsing.html#bogus-comment-state
+    BogusComment,
     /// https://html.spec.whatwg.org/multipage/parsing.html#temporary-buffer
     TemporaryBuffer,
 }
@@ -589,4 +844,143 @@ mod tests {
             assert_eq!(Some(e), tokenizer.next());
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_doctype() {
+        let html = "<!DOCTYPE HTML><!doctype html PUBLIC \"-//W3C//DTD HTML 4.01//EN\"><p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [
+            HtmlToken::Doctype {
+                name: "html".to_string(),
+            },
+            HtmlToken::Doctype {
+                name: "html PUBLIC \"-//W3C//DTD HTML 4.01//EN\"".to_string(),
+            },
+            HtmlToken::StartTag {
+                tag: "p".to_string(),
+                self_closing: false,
+                attributes: Vec::new(),
+            },
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    #[test]
+    fn test_skip_comments() {
+        let html = "a<!-- <p> -- --><![CDATA[x<y]]]><?php echo 1; ?><!x>b<!-- unclosed".to_string();
+        let tokenizer = HtmlTokenizer::new(html);
+        let expected = vec![
+            HtmlToken::Char('a'),
+            HtmlToken::Char('b'),
+            HtmlToken::Eof,
+        ];
+        assert_eq!(expected, tokenizer.collect::<Vec<HtmlToken>>());
+    }
+
+    #[test]
+    fn test_script_data() {
+        let html = "if (a < b) {}</p></SCRIPT><p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        tokenizer.last_start_tag = "script".to_string();
+        tokenizer.switch_context(State::ScriptData);
+
+        let mut expected = "if (a < b) {}</p>"
+            .chars()
+            .map(HtmlToken::Char)
+            .collect::<Vec<HtmlToken>>();
+        expected.push(HtmlToken::EndTag {
+            tag: "script".to_string(),
+        });
+        expected.push(HtmlToken::StartTag {
+            tag: "p".to_string(),
+            self_closing: false,
+            attributes: Vec::new(),
+        });
+        assert_eq!(expected, tokenizer.collect::<Vec<HtmlToken>>());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut tokenizer = HtmlTokenizer::new("<a href=x\"y id=>a</a><b c=\"1\"d=\"2\">".to_string());
+        while tokenizer.next().is_some() {}
+        assert_eq!(
+            vec![
+                ParseError::UnexpectedCharacterInUnquotedAttributeValue('"'),
+                ParseError::MissingAttributeValue,
+                ParseError::MissingWhitespaceBetweenAttributes,
+            ],
+            tokenizer.parse_errors()
+        );
+
+        // 閉じられていない引用符は、入力の最後までタグとして読まれる
+        let mut tokenizer = HtmlTokenizer::new("<p>text</p><a href=\"/next>next</a>".to_string());
+        let tokens: Vec<HtmlToken> = tokenizer.by_ref().collect();
+        assert_eq!(Some(&HtmlToken::Eof), tokens.last());
+        assert_eq!(vec![ParseError::EofInTag], tokenizer.parse_errors());
+
+        let mut tokenizer = HtmlTokenizer::new("<p>text<!-- unclosed".to_string());
+        while tokenizer.next().is_some() {}
+        assert_eq!(vec![ParseError::EofInComment], tokenizer.parse_errors());
+    }
+
+    /// 1 文字ずつ与えたときに返されるトークンを、入力を与えるたびに読めるだけ読んで集める
+    fn tokenize_one_char_at_a_time(html: &str) -> (Vec<HtmlToken>, Vec<ParseError>) {
+        let mut tokenizer = HtmlTokenizer::streaming();
+        let mut tokens = Vec::new();
+        for c in html.chars() {
+            tokenizer.feed(&c.to_string());
+            tokens.extend(tokenizer.by_ref());
+        }
+        tokenizer.finish();
+        tokens.extend(tokenizer.by_ref());
+        (tokens, tokenizer.parse_errors())
+    }
+
+    #[test]
+    fn test_feed_split_tag_name() {
+        let mut tokenizer = HtmlTokenizer::streaming();
+        tokenizer.feed("<di");
+        assert_eq!(None, tokenizer.next());
+        tokenizer.feed("v class=\"a");
+        assert_eq!(None, tokenizer.next());
+        tokenizer.feed("\">");
+
+        let mut attr = Attribute::new();
+        "class".chars().for_each(|c| attr.add_char(c, true));
+        attr.add_char('a', false);
+        assert_eq!(
+            Some(HtmlToken::StartTag {
+                tag: "div".to_string(),
+                self_closing: false,
+                attributes: vec![attr],
+            }),
+            tokenizer.next()
+        );
+        assert_eq!(None, tokenizer.next());
+        tokenizer.finish();
+        assert_eq!(None, tokenizer.next());
+    }
+
+    #[test]
+    fn test_feed_one_char_at_a_time() {
+        let htmls = [
+            "<!DOCTYPE html><html><head><title>Sample</title><style>p > a {color: red;}</style></head><body><h1 id=\"title\">H1 title</h1><!-- comment --><p class=a data-x='1'>Text<br/></p><script>if (1 < 2) { a = '</p>'; }</script><![CDATA[x]]></body></html>",
+            "<p>text</p><a href=\"/next>next</a>",
+            "<p>text<!-- unclosed",
+            "<p>a<",
+            "<script>a</b",
+        ];
+        for html in htmls {
+            let mut tokenizer = HtmlTokenizer::new(html.to_string());
+            let expected: Vec<HtmlToken> = tokenizer.by_ref().collect();
+            assert_eq!(
+                (expected, tokenizer.parse_errors()),
+                tokenize_one_char_at_a_time(html),
+                "{}",
+                html
+            );
+        }
+    }
+}
@@ -1,32 +1,118 @@
+use alloc::format;
 use alloc::rc::Rc;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
-use core::iter::Peekable;
-use crate::renderer::js::token::{JsLexer, Token};
+use core::fmt::{Display, Formatter};
+use crate::renderer::js::token::{JsLexer, Span, Token};
+
+/// A syntax error found while parsing, e.g. "unexpected token '=' at line 1, column 5".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    message: String,
+    span: Span,
+}
+
+impl SyntaxError {
+    pub fn new(message: String, span: Span) -> Self {
+        Self { message, span }
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.span.line, self.span.column
+        )
+    }
+}
+
+/// Peekable な JsLexer。次のトークンの位置も返せる
+struct TokenStream {
+    lexer: JsLexer,
+    peeked: Option<Option<(Token, Span)>>,
+}
+
+impl TokenStream {
+    fn new(lexer: JsLexer) -> Self {
+        Self {
+            lexer,
+            peeked: None,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        let lexer = &mut self.lexer;
+        self.peeked
+            .get_or_insert_with(|| lexer.next_with_span())
+            .as_ref()
+            .map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        match self.peeked.take() {
+            Some(peeked) => peeked.map(|(token, _)| token),
+            None => self.lexer.next(),
+        }
+    }
+
+    /// Returns the position of the next token, or the end of the input.
+    fn span(&mut self) -> Span {
+        match &self.peeked {
+            Some(Some((_, span))) => *span,
+            _ => self.lexer.current_span(),
+        }
+    }
+}
 
 pub struct JsParser {
-    t: Peekable<JsLexer>,
+    t: TokenStream,
+    errors: Vec<SyntaxError>,
+    /// 読んでいる文を囲むループの数。break と continue はループの中でしか書けない
+    loop_depth: usize,
 }
 
 impl JsParser {
     pub fn new(t: JsLexer) -> Self {
-        Self { t: t.peekable()}
+        Self {
+            t: TokenStream::new(t),
+            errors: Vec::new(),
+            loop_depth: 0,
+        }
+    }
+
+    /// Returns the syntax errors found by `parse_ast` in the order they appear in the source.
+    pub fn errors(&self) -> Vec<SyntaxError> {
+        self.errors.clone()
+    }
+
+    fn unexpected(&mut self, token: Option<Token>, span: Span) {
+        let message = match token {
+            Some(token) => format!("unexpected token '{}'", token),
+            None => "unexpected end of input".to_string(),
+        };
+        self.errors.push(SyntaxError::new(message, span));
     }
 
     pub fn parse_ast(&mut self) -> Program {
         let mut program = Program::new();
 
-        let mut body = Vec::new();
-
         loop {
+            let span = self.t.span();
             let node = self.source_element();
 
             match node {
-                Some(n) => body.push(n),
-                None => {
-                    program.set_body(body);
-                    return program
-                }
+                Some(n) => program.push(n, span),
+                None => return program,
             }
         }
     }
@@ -47,17 +133,50 @@ impl JsParser {
         };
 
         let node = match t {
-            Token::Keyword(keyword) => {
-                if keyword == "var" {
-                    assert!(self.t.next().is_some());
-                    self.variable_declaration()
+            // 空文
+            Token::Punctuator(';') => {
+                assert!(self.t.next().is_some());
+                return Node::new_expression_statement(None);
+            }
+            Token::Keyword(keyword) if keyword == "var" => {
+                assert!(self.t.next().is_some());
+                self.variable_declaration()
+            }
+            Token::Keyword(keyword) if keyword == "do" => {
+                assert!(self.t.next().is_some());
+                self.do_while_statement()
+            }
+            Token::Punctuator('{') => {
+                assert!(self.t.next().is_some());
+                self.block_statement()
+            }
+            // https://262.ecma-international.org/#sec-break-statement
+            // https://262.ecma-international.org/#sec-continue-statement
+            Token::Keyword(keyword) if keyword == "break" || keyword == "continue" => {
+                let keyword = keyword.clone();
+                let span = self.t.span();
+                assert!(self.t.next().is_some());
+                if self.loop_depth == 0 {
+                    self.errors.push(SyntaxError::new(
+                        format!("illegal {} statement outside of a loop", keyword),
+                        span,
+                    ));
+                    // 続く文の解析を続けられるように、空文として扱う
+                    Node::new_expression_statement(None)
+                } else if keyword == "break" {
+                    Node::new_break_statement()
                 } else {
-                    None
+                    Node::new_continue_statement()
                 }
             }
             _ => Node::new_expression_statement(self.assignment_expression()),
         };
 
+        // https://262.ecma-international.org/#sec-automatic-semicolon-insertion
+        // セミコロンは省略できる。式は次のトークンで続けられなくなったところで終わるので、
+        // `var x = 1\nvar y = 2` は2つの文になる。一方で、次の行が式を続けられる場合
+        // (`a\n+ b` や、関数呼び出しを実装したときの `a\n(b)`) は JavaScript と同じく
+        // 1つの式として扱う
         if let Some(Token::Punctuator(c)) = self.t.peek() {
             if c == &';' {
                 assert!(self.t.next().is_some());
@@ -67,8 +186,55 @@ impl JsParser {
         node
     }
 
+    /// https://262.ecma-international.org/#prod-Block
+    /// `{` の次から `}` までを読む
+    fn block_statement(&mut self) -> Option<Rc<Node>> {
+        let mut body = Vec::new();
+
+        loop {
+            match self.t.peek() {
+                Some(Token::Punctuator('}')) => {
+                    assert!(self.t.next().is_some());
+                    return Node::new_block_statement(body);
+                }
+                Some(_) => body.push(self.statement()),
+                None => {
+                    let span = self.t.span();
+                    self.unexpected(None, span);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// https://262.ecma-international.org/#sec-do-while-statement
+    /// `do` の次から `while (test)` までを読む
+    fn do_while_statement(&mut self) -> Option<Rc<Node>> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        self.expect(Token::Keyword("while".to_string()))?;
+        self.expect(Token::Punctuator('('))?;
+        let test = self.assignment_expression();
+        self.expect(Token::Punctuator(')'))?;
+        Node::new_do_while_statement(body, test)
+    }
+
+    /// Consumes the next token if it's `expected`. Otherwise records a syntax error and
+    /// returns None.
+    fn expect(&mut self, expected: Token) -> Option<()> {
+        let span = self.t.span();
+        match self.t.next() {
+            Some(t) if t == expected => Some(()),
+            t => {
+                self.unexpected(t, span);
+                None
+            }
+        }
+    }
+
     fn assignment_expression(&mut self) -> Option<Rc<Node>> {
-        let expr = self.additive_expression();
+        let expr = self.equality_expression();
 
         let t = match self.t.peek() {
             Some(t) => t,
@@ -84,107 +250,339 @@ impl JsParser {
         }
     }
 
+    /// https://262.ecma-international.org/#prod-EqualityExpression
+    fn equality_expression(&mut self) -> Option<Rc<Node>> {
+        let mut left = self.additive_expression();
+
+        loop {
+            let operator = match self.t.peek() {
+                Some(Token::Operator(op)) if matches!(op.as_str(), "===" | "!==" | "==" | "!=") => {
+                    op.clone()
+                }
+                _ => return left,
+            };
+            assert!(self.t.next().is_some());
+            left = Node::new_equality_expression(operator, left, self.additive_expression());
+        }
+    }
+
     fn additive_expression(&mut self) -> Option<Rc<Node>> {
-        let left = self.left_hand_side_expression();
+        let mut left = self.multiplicative_expression();
 
-        let t = match self.t.peek() {
-            Some(token) => token.clone(),
-            None => return left,
-        };
+        // 左結合にするため、演算子が続く限り左側に積み上げる
+        loop {
+            let c = match self.t.peek() {
+                Some(Token::Punctuator(c)) if *c == '+' || *c == '-' => *c,
+                _ => return left,
+            };
+            assert!(self.t.next().is_some());
+            left = Node::new_additive_expression(c, left, self.multiplicative_expression());
+        }
+    }
 
-        match t {
-            Token::Punctuator(c) => match c {
-                '+' | '-' => {
-                    assert!(self.t.next().is_some());
-                    Node::new_additive_expression(c, left, self.assignment_expression())
-                }
-                _ => left,
-            },
-            _ => left,
+    /// https://262.ecma-international.org/#prod-MultiplicativeExpression
+    fn multiplicative_expression(&mut self) -> Option<Rc<Node>> {
+        let mut left = self.unary_expression();
+
+        loop {
+            let c = match self.t.peek() {
+                Some(Token::Punctuator(c)) if *c == '*' || *c == '/' => *c,
+                _ => return left,
+            };
+            assert!(self.t.next().is_some());
+            left = Node::new_multiplicative_expression(c, left, self.unary_expression());
         }
     }
 
+    /// https://262.ecma-international.org/#prod-UnaryExpression
+    fn unary_expression(&mut self) -> Option<Rc<Node>> {
+        match self.t.peek() {
+            Some(Token::Keyword(keyword)) if keyword == "typeof" => {
+                assert!(self.t.next().is_some());
+                Node::new_unary_expression("typeof".to_string(), self.unary_expression())
+            }
+            _ => self.left_hand_side_expression(),
+        }
+    }
+
+    /// https://262.ecma-international.org/#prod-LeftHandSideExpression
     fn left_hand_side_expression(&mut self) -> Option<Rc<Node>> {
-        self.member_assignment()
+        let mut expr = self.member_assignment();
+
+        // `a.b(c).d(e)` のように呼び出しとプロパティアクセスが続く場合
+        while let Some(Token::Punctuator('(')) = self.t.peek() {
+            assert!(self.t.next().is_some());
+            let call = Node::new_call_expression(expr, self.arguments()?);
+            expr = self.property_accesses(call);
+        }
+
+        expr
     }
 
+    /// https://262.ecma-international.org/#prod-Arguments
+    /// `(` の次から `)` までを読む。引数の後には `,` か `)` が1つだけ続く。
+    /// 閉じ括弧がない場合や、引数の区切りが正しくない場合は None を返す
+    fn arguments(&mut self) -> Option<Vec<Option<Rc<Node>>>> {
+        let mut arguments = Vec::new();
+
+        if let Some(Token::Punctuator(')')) = self.t.peek() {
+            assert!(self.t.next().is_some());
+            return Some(arguments);
+        }
+
+        loop {
+            arguments.push(self.assignment_expression());
+
+            let span = self.t.span();
+            match self.t.next() {
+                Some(Token::Punctuator(',')) => {}
+                Some(Token::Punctuator(')')) => return Some(arguments),
+                t => {
+                    self.unexpected(t, span);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// https://262.ecma-international.org/#prod-MemberExpression
     fn member_assignment(&mut self) -> Option<Rc<Node>> {
-        self.primary_expression()
+        let expr = self.primary_expression();
+        self.property_accesses(expr)
+    }
+
+    /// `.name` と `[expression]` が続く限り読む。`a.b[c]` は `(a.b)[c]` になる
+    fn property_accesses(&mut self, mut expr: Option<Rc<Node>>) -> Option<Rc<Node>> {
+        loop {
+            match self.t.peek() {
+                Some(Token::Punctuator('.')) => {
+                    assert!(self.t.next().is_some());
+                    expr = Node::new_member_expression(expr, self.identifier());
+                }
+                Some(Token::Punctuator('[')) => {
+                    assert!(self.t.next().is_some());
+                    let property = self.assignment_expression();
+                    let span = self.t.span();
+                    match self.t.next() {
+                        Some(Token::Punctuator(']')) => {}
+                        t => {
+                            self.unexpected(t, span);
+                            return None;
+                        }
+                    }
+                    expr = Node::new_computed_member_expression(expr, property);
+                }
+                _ => return expr,
+            }
+        }
     }
 
     fn primary_expression(&mut self) -> Option<Rc<Node>> {
+        let span = self.t.span();
         let t = match self.t.next() {
             Some(t) => t,
-            None => return None,
+            None => {
+                self.unexpected(None, span);
+                return None;
+            }
         };
 
         match t {
             Token::Identifier(value) => Node::new_identifier(value),
             Token::StringLiteral(value) => Node::new_string_literal(value),
             Token::Number(value) => Node::new_numeric_literal(value),
-            _ => None,
+            Token::Keyword(keyword) if keyword == "true" => Node::new_boolean_literal(true),
+            Token::Keyword(keyword) if keyword == "false" => Node::new_boolean_literal(false),
+            Token::Keyword(keyword) if keyword == "null" => Node::new_null_literal(),
+            // undefined はグローバルオブジェクトのプロパティなので、変数として参照する
+            Token::Keyword(keyword) if keyword == "undefined" => Node::new_identifier(keyword),
+            Token::Punctuator('[') => self.array_literal(),
+            // 文の先頭の `{` はブロックとして読むので、ここに来るのは式の中の `{` だけ
+            Token::Punctuator('{') => self.object_literal(),
+            _ => {
+                self.unexpected(Some(t), span);
+                None
+            }
         }
     }
 
-    fn variable_declaration(&mut self) -> Option<Rc<Node>> {
-        let ident = self.identifier();
+    /// https://262.ecma-international.org/#prod-ArrayLiteral
+    /// `[` の次から `]` までを読む。`[1, 2,]` のように末尾のカンマは無視する
+    fn array_literal(&mut self) -> Option<Rc<Node>> {
+        let mut elements = Vec::new();
 
-        let declarator = Node::new_variable_declarator(ident, self.initialiser());
+        loop {
+            if let Some(Token::Punctuator(']')) = self.t.peek() {
+                assert!(self.t.next().is_some());
+                return Node::new_array_expression(elements);
+            }
+            elements.push(self.assignment_expression());
 
+            let span = self.t.span();
+            match self.t.next() {
+                Some(Token::Punctuator(',')) => {}
+                Some(Token::Punctuator(']')) => return Node::new_array_expression(elements),
+                t => {
+                    self.unexpected(t, span);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// https://262.ecma-international.org/#prod-ObjectLiteral
+    /// `{` の次から `}` までを読む。プロパティ名には識別子、文字列、数値が使える
+    fn object_literal(&mut self) -> Option<Rc<Node>> {
+        let mut properties = Vec::new();
+
+        loop {
+            if let Some(Token::Punctuator('}')) = self.t.peek() {
+                assert!(self.t.next().is_some());
+                return Node::new_object_expression(properties);
+            }
+
+            let span = self.t.span();
+            let key = match self.t.next() {
+                Some(Token::Identifier(name)) => Node::new_identifier(name),
+                Some(Token::StringLiteral(value)) => Node::new_string_literal(value),
+                Some(Token::Number(value)) => Node::new_numeric_literal(value),
+                t => {
+                    self.unexpected(t, span);
+                    return None;
+                }
+            };
+            self.expect(Token::Punctuator(':'))?;
+            properties.push(Node::new_property(key, self.assignment_expression()));
+
+            let span = self.t.span();
+            match self.t.next() {
+                Some(Token::Punctuator(',')) => {}
+                Some(Token::Punctuator('}')) => return Node::new_object_expression(properties),
+                t => {
+                    self.unexpected(t, span);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// https://262.ecma-international.org/#prod-VariableDeclarationList
+    fn variable_declaration(&mut self) -> Option<Rc<Node>> {
         let mut declarations = Vec::new();
-        declarations.push(declarator);
+
+        loop {
+            let ident = self.identifier();
+            let declarator = Node::new_variable_declarator(ident, self.initialiser());
+            declarations.push(declarator);
+
+            match self.t.peek() {
+                Some(Token::Punctuator(',')) => {
+                    assert!(self.t.next().is_some());
+                }
+                _ => break,
+            }
+
+            // `var a = 1,;` のように末尾にカンマがある場合は構文エラーとして扱う
+            if !matches!(self.t.peek(), Some(Token::Identifier(_))) {
+                let span = self.t.span();
+                let token = self.t.peek().cloned();
+                self.unexpected(token, span);
+                return None;
+            }
+        }
 
         Node::new_variable_declaration(declarations)
     }
 
     fn identifier(&mut self) -> Option<Rc<Node>> {
+        let span = self.t.span();
         let t = match self.t.next() {
             Some(t) => t,
-            None => return None,
+            None => {
+                self.unexpected(None, span);
+                return None;
+            }
         };
 
         match t {
             Token::Identifier(name) => Node::new_identifier(name),
-            _ => None,
+            _ => {
+                self.unexpected(Some(t), span);
+                None
+            }
         }
     }
 
     fn initialiser(&mut self) -> Option<Rc<Node>> {
-        let t = match self.t.next() {
-            Some(t) => t,
-            None => return None,
-        };
-
-        match t {
-            Token::Punctuator(c) => match c {
-                '=' => self.assignment_expression(),
-                _ => None,
+        match self.t.peek() {
+            Some(Token::Punctuator('=')) => {
+                assert!(self.t.next().is_some());
+                self.assignment_expression()
             }
             _ => None,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct Program {
     body: Vec<Rc<Node>>,
+    /// body のそれぞれの文が始まる位置
+    spans: Vec<Span>,
+}
+
+/// Programs are equal if they have the same statements, wherever they are in the source.
+impl PartialEq for Program {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body
+    }
 }
 
 impl Program {
     pub fn new() -> Self {
-        Self { body: Vec::new() }
+        Self {
+            body: Vec::new(),
+            spans: Vec::new(),
+        }
     }
 
+    /// Replaces the statements. Their positions are unknown, so they are all at the start.
     pub fn set_body(&mut self, body: Vec<Rc<Node>>) {
+        self.spans = body.iter().map(|_| Span::default()).collect();
         self.body = body;
     }
 
+    /// Appends a top-level statement that starts at `span` in the source.
+    pub fn push(&mut self, node: Rc<Node>, span: Span) {
+        self.body.push(node);
+        self.spans.push(span);
+    }
+
     pub fn body(&self) -> &Vec<Rc<Node>> {
         &self.body
     }
+
+    /// Returns the number of top-level statements.
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    /// Returns the `i`-th top-level statement.
+    pub fn get(&self, i: usize) -> Option<&Rc<Node>> {
+        self.body.get(i)
+    }
+
+    /// Returns where the `i`-th top-level statement starts in the source.
+    pub fn span(&self, i: usize) -> Option<Span> {
+        self.spans.get(i).copied()
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     ExpressionStatement(Option<Rc<Node>>),
     AdditiveExpression {
@@ -192,16 +590,53 @@ pub enum Node {
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
     },
+    /// `*` and `/`
+    MultiplicativeExpression {
+        operator: char,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    },
     AssignmentExpression {
         operator: char,
         left: Option<Rc<Node>>,
         right: Option<Rc<Node>>,
     },
+    /// https://github.com/estree/estree/blob/master/es5.md#memberexpression
+    /// `computed` is true for `object[property]`, where `property` is an expression.
     MemberExpression {
         object: Option<Rc<Node>>,
         property: Option<Rc<Node>>,
+        computed: bool,
     },
-    NumericLiteral(u64),
+    /// https://github.com/estree/estree/blob/master/es5.md#arrayexpression
+    ArrayExpression { elements: Vec<Option<Rc<Node>>> },
+    /// https://github.com/estree/estree/blob/master/es5.md#objectexpression
+    ObjectExpression { properties: Vec<Option<Rc<Node>>> },
+    /// https://github.com/estree/estree/blob/master/es5.md#property
+    /// `key` is an `Identifier`, a `StringLiteral` or a `NumericLiteral`.
+    Property {
+        key: Option<Rc<Node>>,
+        value: Option<Rc<Node>>,
+    },
+    /// https://github.com/estree/estree/blob/master/es5.md#callexpression
+    CallExpression {
+        callee: Option<Rc<Node>>,
+        arguments: Vec<Option<Rc<Node>>>,
+    },
+    /// https://github.com/estree/estree/blob/master/es5.md#unaryexpression
+    UnaryExpression {
+        operator: String,
+        argument: Option<Rc<Node>>,
+    },
+    /// `===`, `!==`, `==` and `!=`
+    EqualityExpression {
+        operator: String,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    },
+    NumericLiteral(f64),
+    BooleanLiteral(bool),
+    NullLiteral,
     VariableDeclaration { declarations: Vec<Option<Rc<Node>>> },
     VariableDeclarator {
         id: Option<Rc<Node>>,
@@ -209,6 +644,17 @@ pub enum Node {
     },
     Identifier(String),
     StringLiteral(String),
+    /// https://github.com/estree/estree/blob/master/es5.md#blockstatement
+    BlockStatement { body: Vec<Option<Rc<Node>>> },
+    /// https://github.com/estree/estree/blob/master/es5.md#dowhilestatement
+    DoWhileStatement {
+        body: Option<Rc<Node>>,
+        test: Option<Rc<Node>>,
+    },
+    /// https://github.com/estree/estree/blob/master/es5.md#breakstatement
+    BreakStatement,
+    /// https://github.com/estree/estree/blob/master/es5.md#continuestatement
+    ContinueStatement,
 }
 
 impl Node {
@@ -228,6 +674,18 @@ impl Node {
         }))
     }
 
+    pub fn new_multiplicative_expression(
+        operator: char,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::MultiplicativeExpression {
+            operator,
+            left,
+            right,
+        }))
+    }
+
     pub fn new_assignment_expression(
         operator: char,
         left: Option<Rc<Node>>,
@@ -247,10 +705,65 @@ impl Node {
         Some(Rc::new(Self::MemberExpression {
             object,
             property,
+            computed: false,
+        }))
+    }
+
+    pub fn new_computed_member_expression(
+        object: Option<Rc<Node>>,
+        property: Option<Rc<Node>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::MemberExpression {
+            object,
+            property,
+            computed: true,
         }))
     }
 
-    pub fn new_numeric_literal(value: u64) -> Option<Rc<Self>> {
+    pub fn new_array_expression(elements: Vec<Option<Rc<Node>>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::ArrayExpression { elements }))
+    }
+
+    pub fn new_object_expression(properties: Vec<Option<Rc<Node>>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::ObjectExpression { properties }))
+    }
+
+    pub fn new_property(key: Option<Rc<Node>>, value: Option<Rc<Node>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::Property { key, value }))
+    }
+
+    pub fn new_call_expression(
+        callee: Option<Rc<Node>>,
+        arguments: Vec<Option<Rc<Node>>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::CallExpression { callee, arguments }))
+    }
+
+    pub fn new_unary_expression(operator: String, argument: Option<Rc<Node>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::UnaryExpression { operator, argument }))
+    }
+
+    pub fn new_equality_expression(
+        operator: String,
+        left: Option<Rc<Node>>,
+        right: Option<Rc<Node>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::EqualityExpression {
+            operator,
+            left,
+            right,
+        }))
+    }
+
+    pub fn new_boolean_literal(value: bool) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::BooleanLiteral(value)))
+    }
+
+    pub fn new_null_literal() -> Option<Rc<Self>> {
+        Some(Rc::new(Self::NullLiteral))
+    }
+
+    pub fn new_numeric_literal(value: f64) -> Option<Rc<Self>> {
         Some(Rc::new(Self::NumericLiteral(value)))
     }
 
@@ -272,11 +785,31 @@ impl Node {
     pub fn new_string_literal(value: String) -> Option<Rc<Self>> {
         Some(Rc::new(Self::StringLiteral(value)))
     }
+
+    pub fn new_block_statement(body: Vec<Option<Rc<Node>>>) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::BlockStatement { body }))
+    }
+
+    pub fn new_do_while_statement(
+        body: Option<Rc<Node>>,
+        test: Option<Rc<Node>>,
+    ) -> Option<Rc<Self>> {
+        Some(Rc::new(Self::DoWhileStatement { body, test }))
+    }
+
+    pub fn new_break_statement() -> Option<Rc<Self>> {
+        Some(Rc::new(Self::BreakStatement))
+    }
+
+    pub fn new_continue_statement() -> Option<Rc<Self>> {
+        Some(Rc::new(Self::ContinueStatement))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use alloc::string::ToString;
+    use alloc::vec;
     use super::*;
 
     #[test]
@@ -296,7 +829,7 @@ mod tests {
         let mut expected = Program::new();
         let mut body = Vec::new();
         body.push(Rc::new(Node::ExpressionStatement(Some(Rc::new(
-            Node::NumericLiteral(42)
+            Node::NumericLiteral(42.0)
         )))));
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
@@ -312,8 +845,8 @@ mod tests {
         body.push(Rc::new(Node::ExpressionStatement(Some(Rc::new(
             Node::AdditiveExpression {
                 operator: '+',
-                left: Some(Rc::new(Node::NumericLiteral(1))),
-                right: Some(Rc::new(Node::NumericLiteral(2))),
+                left: Some(Rc::new(Node::NumericLiteral(1.0))),
+                right: Some(Rc::new(Node::NumericLiteral(2.0))),
             }
         )))));
         expected.set_body(body);
@@ -350,7 +883,7 @@ mod tests {
             declarations: [
                 Some(Rc::new(Node::VariableDeclarator {
                     id: Some(Rc::new(Node::Identifier("foo".to_string()))),
-                    init: Some(Rc::new(Node::NumericLiteral(42))),
+                    init: Some(Rc::new(Node::NumericLiteral(42.0))),
                 }))
             ].to_vec(),
         }));
@@ -361,7 +894,7 @@ mod tests {
                     init: Some(Rc::new(Node::AdditiveExpression {
                         operator: '+',
                         left: Some(Rc::new(Node::Identifier("foo".to_string()))),
-                        right: Some(Rc::new(Node::NumericLiteral(1))),
+                        right: Some(Rc::new(Node::NumericLiteral(1.0))),
                     })),
                 }))
             ].to_vec(),
@@ -369,4 +902,431 @@ mod tests {
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_typeof_and_strict_equality() {
+        let js = "typeof 1 + 2 === \"number\"".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::EqualityExpression {
+                operator: "===".to_string(),
+                left: Some(Rc::new(Node::AdditiveExpression {
+                    operator: '+',
+                    left: Some(Rc::new(Node::UnaryExpression {
+                        operator: "typeof".to_string(),
+                        argument: Some(Rc::new(Node::NumericLiteral(1.0))),
+                    })),
+                    right: Some(Rc::new(Node::NumericLiteral(2.0))),
+                })),
+                right: Some(Rc::new(Node::StringLiteral("number".to_string()))),
+            }
+        ))))];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_sub_is_left_associative() {
+        let js = "3 - 2 - 1".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::AdditiveExpression {
+                operator: '-',
+                left: Some(Rc::new(Node::AdditiveExpression {
+                    operator: '-',
+                    left: Some(Rc::new(Node::NumericLiteral(3.0))),
+                    right: Some(Rc::new(Node::NumericLiteral(2.0))),
+                })),
+                right: Some(Rc::new(Node::NumericLiteral(1.0))),
+            }
+        ))))];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_multiple_declarators() {
+        let js = "var a = 1, b = 2, c;".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::VariableDeclaration {
+            declarations: vec![
+                Some(Rc::new(Node::VariableDeclarator {
+                    id: Some(Rc::new(Node::Identifier("a".to_string()))),
+                    init: Some(Rc::new(Node::NumericLiteral(1.0))),
+                })),
+                Some(Rc::new(Node::VariableDeclarator {
+                    id: Some(Rc::new(Node::Identifier("b".to_string()))),
+                    init: Some(Rc::new(Node::NumericLiteral(2.0))),
+                })),
+                Some(Rc::new(Node::VariableDeclarator {
+                    id: Some(Rc::new(Node::Identifier("c".to_string()))),
+                    init: None,
+                })),
+            ],
+        })];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_trailing_comma_in_declaration() {
+        let js = "var a = 1,;".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        assert_eq!(Program::new(), parser.parse_ast());
+        assert_eq!(
+            vec!["unexpected token ';' at line 1, column 11".to_string()],
+            parser
+                .errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_statements_without_semicolon() {
+        let js = "var x = 1\r\nvar y = 2\n\tx\ny".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![
+            Rc::new(Node::VariableDeclaration {
+                declarations: vec![Some(Rc::new(Node::VariableDeclarator {
+                    id: Some(Rc::new(Node::Identifier("x".to_string()))),
+                    init: Some(Rc::new(Node::NumericLiteral(1.0))),
+                }))],
+            }),
+            Rc::new(Node::VariableDeclaration {
+                declarations: vec![Some(Rc::new(Node::VariableDeclarator {
+                    id: Some(Rc::new(Node::Identifier("y".to_string()))),
+                    init: Some(Rc::new(Node::NumericLiteral(2.0))),
+                }))],
+            }),
+            Rc::new(Node::ExpressionStatement(Some(Rc::new(Node::Identifier("x".to_string()))))),
+            Rc::new(Node::ExpressionStatement(Some(Rc::new(Node::Identifier("y".to_string()))))),
+        ];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_expression_continues_on_next_line() {
+        let js = "x\n+ 1".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::AdditiveExpression {
+                operator: '+',
+                left: Some(Rc::new(Node::Identifier("x".to_string()))),
+                right: Some(Rc::new(Node::NumericLiteral(1.0))),
+            },
+        ))))];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_program_accessors() {
+        let program = JsParser::new(JsLexer::new("".to_string())).parse_ast();
+        assert!(program.is_empty());
+        assert_eq!(0, program.len());
+        assert_eq!(None, program.get(0));
+
+        let js = "var a = 1; a + 2; 3".to_string();
+        let program = JsParser::new(JsLexer::new(js)).parse_ast();
+        assert!(!program.is_empty());
+        assert_eq!(3, program.len());
+        assert!(matches!(program.get(0).map(|n| n.as_ref()), Some(Node::VariableDeclaration { .. })));
+        assert_eq!(
+            Some(&Rc::new(Node::ExpressionStatement(Some(Rc::new(Node::NumericLiteral(3.0)))))),
+            program.get(2)
+        );
+        assert_eq!(None, program.get(3));
+    }
+
+    #[test]
+    fn test_multiplicative_precedence() {
+        let js = "1 + 2 * 3 / 4".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::AdditiveExpression {
+                operator: '+',
+                left: Some(Rc::new(Node::NumericLiteral(1.0))),
+                right: Some(Rc::new(Node::MultiplicativeExpression {
+                    operator: '/',
+                    left: Some(Rc::new(Node::MultiplicativeExpression {
+                        operator: '*',
+                        left: Some(Rc::new(Node::NumericLiteral(2.0))),
+                        right: Some(Rc::new(Node::NumericLiteral(3.0))),
+                    })),
+                    right: Some(Rc::new(Node::NumericLiteral(4.0))),
+                })),
+            },
+        ))))];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_member_expression() {
+        let js = "\"a\".length.b".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::MemberExpression {
+                object: Some(Rc::new(Node::MemberExpression {
+                    object: Some(Rc::new(Node::StringLiteral("a".to_string()))),
+                    property: Some(Rc::new(Node::Identifier("length".to_string()))),
+                    computed: false,
+                })),
+                property: Some(Rc::new(Node::Identifier("b".to_string()))),
+                computed: false,
+            },
+        ))))];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_call_expression() {
+        let js = "s.indexOf(\"c\", 1)".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::CallExpression {
+                callee: Some(Rc::new(Node::MemberExpression {
+                    object: Some(Rc::new(Node::Identifier("s".to_string()))),
+                    property: Some(Rc::new(Node::Identifier("indexOf".to_string()))),
+                    computed: false,
+                })),
+                arguments: vec![
+                    Some(Rc::new(Node::StringLiteral("c".to_string()))),
+                    Some(Rc::new(Node::NumericLiteral(1.0))),
+                ],
+            },
+        ))))];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
+
+    #[test]
+    fn test_call_arguments_syntax_error() {
+        let errors = |js: &str| {
+            let mut parser = JsParser::new(JsLexer::new(js.to_string()));
+            parser.parse_ast();
+            parser
+                .errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+        };
+
+        // 引数の間にはカンマが必要
+        assert_eq!(
+            "unexpected token '1' at line 1, column 19",
+            errors("\"abc\".indexOf(\"b\" 1)")[0]
+        );
+        assert!(!errors("f(,,1)").is_empty());
+        assert!(!errors("f(1,)").is_empty());
+        assert!(errors("f(); f(1); f(1, \"a\")").is_empty());
+    }
+
+    #[test]
+    fn test_syntax_error_span() {
+        let js = "var a = 1;\nvar b = 2;\nvar = 5".to_string();
+        let mut parser = JsParser::new(JsLexer::new(js));
+        parser.parse_ast();
+        let expected = vec![SyntaxError::new(
+            "unexpected token '='".to_string(),
+            Span {
+                offset: 26,
+                line: 3,
+                column: 5,
+            },
+        )];
+        assert_eq!(expected, parser.errors());
+        assert_eq!(
+            "unexpected token '=' at line 3, column 5",
+            parser.errors()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_statement_spans() {
+        let js = "var a = 1;\n  a = 2; a".to_string();
+        let program = JsParser::new(JsLexer::new(js)).parse_ast();
+        assert_eq!(3, program.len());
+        assert_eq!(Some(Span { offset: 0, line: 1, column: 1 }), program.span(0));
+        assert_eq!(Some(Span { offset: 13, line: 2, column: 3 }), program.span(1));
+        assert_eq!(Some(Span { offset: 20, line: 2, column: 10 }), program.span(2));
+        assert_eq!(None, program.span(3));
+    }
+
+    #[test]
+    fn test_syntax_error_end_of_input() {
+        let mut parser = JsParser::new(JsLexer::new("s.charAt(1".to_string()));
+        parser.parse_ast();
+        assert_eq!(
+            vec!["unexpected end of input at line 1, column 11".to_string()],
+            parser
+                .errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+        );
+
+        let mut parser = JsParser::new(JsLexer::new("var a = 1;; a + 2".to_string()));
+        parser.parse_ast();
+        assert!(parser.errors().is_empty());
+    }
+
+    #[test]
+    fn test_computed_member_expression() {
+        let js = "s[i + 1].length".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::ExpressionStatement(Some(Rc::new(
+            Node::MemberExpression {
+                object: Some(Rc::new(Node::MemberExpression {
+                    object: Some(Rc::new(Node::Identifier("s".to_string()))),
+                    property: Some(Rc::new(Node::AdditiveExpression {
+                        operator: '+',
+                        left: Some(Rc::new(Node::Identifier("i".to_string()))),
+                        right: Some(Rc::new(Node::NumericLiteral(1.0))),
+                    })),
+                    computed: true,
+                })),
+                property: Some(Rc::new(Node::Identifier("length".to_string()))),
+                computed: false,
+            },
+        ))))];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+        assert!(parser.errors().is_empty());
+    }
+
+    #[test]
+    fn test_array_and_object_literals() {
+        let js = "var a = [1, \"b\",], o = {a: 1, \"b\": [], 2: {}}".to_string();
+        let mut parser = JsParser::new(JsLexer::new(js));
+        let property = |key: Node, value: Node| {
+            Some(Rc::new(Node::Property {
+                key: Some(Rc::new(key)),
+                value: Some(Rc::new(value)),
+            }))
+        };
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::VariableDeclaration {
+            declarations: vec![
+                Some(Rc::new(Node::VariableDeclarator {
+                    id: Some(Rc::new(Node::Identifier("a".to_string()))),
+                    init: Some(Rc::new(Node::ArrayExpression {
+                        elements: vec![
+                            Some(Rc::new(Node::NumericLiteral(1.0))),
+                            Some(Rc::new(Node::StringLiteral("b".to_string()))),
+                        ],
+                    })),
+                })),
+                Some(Rc::new(Node::VariableDeclarator {
+                    id: Some(Rc::new(Node::Identifier("o".to_string()))),
+                    init: Some(Rc::new(Node::ObjectExpression {
+                        properties: vec![
+                            property(Node::Identifier("a".to_string()), Node::NumericLiteral(1.0)),
+                            property(
+                                Node::StringLiteral("b".to_string()),
+                                Node::ArrayExpression { elements: vec![] },
+                            ),
+                            property(
+                                Node::NumericLiteral(2.0),
+                                Node::ObjectExpression { properties: vec![] },
+                            ),
+                        ],
+                    })),
+                })),
+            ],
+        })];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+        assert!(parser.errors().is_empty());
+
+        let mut parser = JsParser::new(JsLexer::new("var o = {a 1}".to_string()));
+        parser.parse_ast();
+        assert_eq!(
+            Some("unexpected token '1' at line 1, column 12".to_string()),
+            parser.errors().first().map(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn test_do_while_statement() {
+        let js = "do { i = i + 1; } while (i !== 3)".to_string();
+        let mut parser = JsParser::new(JsLexer::new(js));
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::DoWhileStatement {
+            body: Some(Rc::new(Node::BlockStatement {
+                body: vec![Some(Rc::new(Node::ExpressionStatement(Some(Rc::new(
+                    Node::AssignmentExpression {
+                        operator: '=',
+                        left: Some(Rc::new(Node::Identifier("i".to_string()))),
+                        right: Some(Rc::new(Node::AdditiveExpression {
+                            operator: '+',
+                            left: Some(Rc::new(Node::Identifier("i".to_string()))),
+                            right: Some(Rc::new(Node::NumericLiteral(1.0))),
+                        })),
+                    },
+                )))))],
+            })),
+            test: Some(Rc::new(Node::EqualityExpression {
+                operator: "!==".to_string(),
+                left: Some(Rc::new(Node::Identifier("i".to_string()))),
+                right: Some(Rc::new(Node::NumericLiteral(3.0))),
+            })),
+        })];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+        assert!(parser.errors().is_empty());
+
+        let mut parser = JsParser::new(JsLexer::new("do { i = 1; } (i)".to_string()));
+        parser.parse_ast();
+        assert_eq!(
+            "unexpected token '(' at line 1, column 15",
+            parser.errors()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_break_outside_of_loop() {
+        let mut parser = JsParser::new(JsLexer::new("do { break; } while (true);\ncontinue".to_string()));
+        let program = parser.parse_ast();
+        assert_eq!(
+            vec!["illegal continue statement outside of a loop at line 2, column 1".to_string()],
+            parser
+                .errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+        );
+        assert_eq!(
+            Some(&Rc::new(Node::DoWhileStatement {
+                body: Some(Rc::new(Node::BlockStatement {
+                    body: vec![Some(Rc::new(Node::BreakStatement))],
+                })),
+                test: Some(Rc::new(Node::BooleanLiteral(true))),
+            })),
+            program.get(0)
+        );
+    }
+}
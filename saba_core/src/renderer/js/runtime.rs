@@ -1,20 +1,168 @@
+use alloc::format;
 use alloc::rc::Rc;
-use core::ops::{Add, Sub};
-use core::borrow::Borrow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::{Display, Formatter};
+use core::ops::{Add, Div, Mul, Sub};
+use crate::error::Error;
+use crate::renderer::dom::api::get_element_by_id;
+use crate::renderer::dom::node::{self as dom, affects_layout};
 use crate::renderer::js::ast::{Node, Program};
+use crate::renderer::js::token::Span;
 
+/// 終わらないループやループの入れ子でブラウザが固まらないように、1回の実行で評価するノードの数を制限する
+pub const DEFAULT_MAX_OPERATIONS: usize = 1_000_000;
+
+/// https://262.ecma-international.org/#sec-environment-records
 #[derive(Debug, Clone)]
-pub struct JsRuntime {}
+pub struct Environment {
+    variables: Vec<(String, RuntimeValue)>,
+    outer: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new(outer: Option<Rc<RefCell<Environment>>>) -> Self {
+        Self {
+            variables: Vec::new(),
+            outer,
+        }
+    }
+
+    pub fn get_variable(&self, name: &str) -> Option<RuntimeValue> {
+        for (n, value) in &self.variables {
+            if n == name {
+                return Some(value.clone());
+            }
+        }
+
+        match &self.outer {
+            Some(outer) => outer.borrow().get_variable(name),
+            None => None,
+        }
+    }
+
+    /// Declares `name` in this environment. Declaring a name that already exists here updates
+    /// its value instead of adding another binding.
+    pub fn add_variable(&mut self, name: String, value: RuntimeValue) {
+        match self.variables.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.variables.push((name, value)),
+        }
+    }
+
+    fn has_own_variable(&self, name: &str) -> bool {
+        self.variables.iter().any(|(n, _)| n == name)
+    }
+
+    /// Returns false if `name` isn't declared in this or any outer environment.
+    pub fn update_variable(&mut self, name: &str, value: RuntimeValue) -> bool {
+        for (n, v) in self.variables.iter_mut() {
+            if n == name {
+                *v = value;
+                return true;
+            }
+        }
+
+        match &self.outer {
+            Some(outer) => outer.borrow_mut().update_variable(name, value),
+            None => false,
+        }
+    }
+}
+
+/// A `break` or `continue` that is leaving the statements up to the innermost loop.
+/// https://262.ecma-international.org/#sec-completion-record-specification-type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Completion {
+    Break,
+    Continue,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsRuntime {
+    env: Rc<RefCell<Environment>>,
+    completion: Option<Completion>,
+    /// The document that scripts see as `document`.
+    document: Option<Rc<RefCell<dom::Node>>>,
+    /// 描画に影響する DOM の変更があったかどうか
+    dom_modified: bool,
+    max_operations: usize,
+    /// The number of nodes evaluated by the current `execute`.
+    operations: usize,
+    timed_out: bool,
+    /// 上限に達したときに評価していた、トップレベルの文の位置
+    timeout_span: Option<Span>,
+}
 
 impl JsRuntime {
     pub fn new() -> JsRuntime {
-        Self {}
+        Self {
+            env: Rc::new(RefCell::new(Self::global_environment())),
+            completion: None,
+            document: None,
+            dom_modified: false,
+            max_operations: DEFAULT_MAX_OPERATIONS,
+            operations: 0,
+            timed_out: false,
+            timeout_span: None,
+        }
+    }
+
+    /// Makes `execute` fail with `Error::ScriptTimeout` once it evaluates more than
+    /// `max_operations` nodes.
+    pub fn set_max_operations(&mut self, max_operations: usize) {
+        self.max_operations = max_operations;
+    }
+
+    /// Makes `document` available to scripts.
+    pub fn set_document(&mut self, document: Rc<RefCell<dom::Node>>) {
+        self.document = Some(document);
     }
 
-    pub fn execute(&mut self, program: &Program) {
-        for node in program.body() {
+    /// Returns true if the last `execute` changed the DOM in a way that needs a re-layout, e.g.
+    /// by `setAttribute("class", ...)`.
+    pub fn is_dom_modified(&self) -> bool {
+        self.dom_modified
+    }
+
+    /// https://262.ecma-international.org/#sec-value-properties-of-the-global-object
+    fn global_environment() -> Environment {
+        let mut global = Environment::new(None);
+        global.add_variable("NaN".to_string(), RuntimeValue::Number(f64::NAN));
+        global.add_variable("Infinity".to_string(), RuntimeValue::Number(f64::INFINITY));
+        global.add_variable("undefined".to_string(), RuntimeValue::Undefined);
+        global
+    }
+
+    /// Drops all scopes and bindings so that the next page starts from a fresh global scope.
+    pub fn reset(&mut self) {
+        // 値はスコープを参照しないので循環参照はできず、古い環境はここで解放される
+        self.env = Rc::new(RefCell::new(Self::global_environment()));
+        self.completion = None;
+        self.document = None;
+        self.dom_modified = false;
+    }
+
+    pub fn execute(&mut self, program: &Program) -> Result<(), Error> {
+        self.operations = 0;
+        self.timed_out = false;
+        self.timeout_span = None;
+        self.dom_modified = false;
+        for (i, node) in program.body().iter().enumerate() {
             self.eval(&Some(node.clone()));
+            if self.timed_out {
+                self.timeout_span = program.span(i);
+                return Err(Error::ScriptTimeout);
+            }
         }
+        Ok(())
+    }
+
+    /// Returns where the top-level statement that was running starts in the source, if the last
+    /// `execute` failed with `Error::ScriptTimeout`.
+    pub fn timeout_span(&self) -> Option<Span> {
+        self.timeout_span
     }
 
     fn eval(
@@ -26,7 +174,14 @@ impl JsRuntime {
             None => return None,
         };
 
-        match node.borrow() {
+        // 上限に達したら、残りのノードは評価せずに抜ける
+        if self.operations >= self.max_operations {
+            self.timed_out = true;
+            return None;
+        }
+        self.operations += 1;
+
+        match node.as_ref() {
             Node::ExpressionStatement(expr) => self.eval(&expr),
             Node::AdditiveExpression {
                 operator,
@@ -50,36 +205,697 @@ impl JsRuntime {
                     None
                 }
             }
+            Node::MultiplicativeExpression {
+                operator,
+                left,
+                right,
+            } => {
+                let left_value = self.eval(left)?;
+                let right_value = self.eval(right)?;
+
+                match operator {
+                    '*' => Some(left_value * right_value),
+                    '/' => Some(left_value / right_value),
+                    _ => None,
+                }
+            }
             Node::AssignmentExpression {
-                operator: _,
-                left: _,
-                right: _,
+                operator,
+                left,
+                right,
             } => {
+                if operator != &'=' {
+                    return None;
+                }
+
+                let name = match left.as_deref() {
+                    Some(Node::Identifier(name)) => name,
+                    _ => return None,
+                };
+                let value = self.eval(right)?;
+                // 宣言されていない変数への代入は、新しい変数を作る
+                if !self.env.borrow_mut().update_variable(name, value.clone()) {
+                    self.env.borrow_mut().add_variable(name.clone(), value.clone());
+                }
+                Some(value)
+            }
+            Node::VariableDeclaration { declarations } => {
+                for declaration in declarations {
+                    self.eval(declaration);
+                }
+                None
+            }
+            Node::VariableDeclarator { id, init } => {
+                let name = match id.as_deref() {
+                    Some(Node::Identifier(name)) => name,
+                    _ => return None,
+                };
+                // `var a;` のように初期値のない再宣言では、値は変わらない
+                if init.is_none() && self.env.borrow().has_own_variable(name) {
+                    return None;
+                }
+                let value = self.eval(init).unwrap_or(RuntimeValue::Undefined);
+                self.env.borrow_mut().add_variable(name.clone(), value);
                 None
             }
+            Node::Identifier(name) => {
+                let value = self.env.borrow().get_variable(name);
+                match (value, &self.document) {
+                    (Some(value), _) => Some(value),
+                    (None, Some(document)) if name == "document" => {
+                        Some(RuntimeValue::DomNode(document.clone()))
+                    }
+                    (None, _) => Some(RuntimeValue::Undefined),
+                }
+            }
             Node::MemberExpression {
-                object: _,
-                property: _,
+                object,
+                property,
+                computed,
             } => {
-                None
-            },
+                let object = self.eval(object)?;
+                let key = self.property_key(property, *computed)?;
+                Some(object.get_property(&key))
+            }
+            Node::ArrayExpression { elements } => {
+                let elements = self.eval_arguments(elements)?;
+                Some(RuntimeValue::Array(Rc::new(elements)))
+            }
+            Node::ObjectExpression { properties } => {
+                let mut object: Vec<(String, RuntimeValue)> = Vec::new();
+                for property in properties {
+                    let (key, value) = match property.as_deref() {
+                        Some(Node::Property { key, value }) => (key, value),
+                        _ => return None,
+                    };
+                    let key = self.property_key(key, false)?;
+                    let value = self.eval(value)?;
+                    // 同じ名前のプロパティは後のもので上書きする
+                    match object.iter_mut().find(|(name, _)| *name == key) {
+                        Some((_, v)) => *v = value,
+                        None => object.push((key, value)),
+                    }
+                }
+                Some(RuntimeValue::Object(Rc::new(object)))
+            }
+            // プロパティはオブジェクトの中でだけ評価する
+            Node::Property { .. } => None,
+            Node::CallExpression { callee, arguments } => {
+                // 今のところ呼び出せるのは組み込みの関数とメソッドだけ。
+                // 同じ名前の変数がなければ、組み込みのものを呼ぶ
+                let (object, property, computed) = match callee.as_deref() {
+                    Some(Node::MemberExpression {
+                        object,
+                        property,
+                        computed,
+                    }) => (object, property, *computed),
+                    Some(Node::Identifier(name)) if !self.is_declared(name) => {
+                        let args = self.eval_arguments(arguments)?;
+                        return call_global_function(name, &args);
+                    }
+                    _ => return None,
+                };
+                if let Some(Node::Identifier(object_name)) = object.as_deref() {
+                    if object_name == "Math" && !self.is_declared(object_name) {
+                        let name = self.property_key(property, computed)?;
+                        let args = self.eval_arguments(arguments)?;
+                        return call_math_function(&name, &args);
+                    }
+                }
+                let object = self.eval(object)?;
+                let name = self.property_key(property, computed)?;
+                let args = self.eval_arguments(arguments)?;
+                let result = object.call_method(&name, &args);
+                if let (RuntimeValue::DomNode(_), "setAttribute", Some(attribute)) =
+                    (&object, name.as_str(), args.first())
+                {
+                    if result.is_some() && affects_layout(&attribute.to_string()) {
+                        self.dom_modified = true;
+                    }
+                }
+                result
+            }
+            Node::UnaryExpression { operator, argument } => {
+                let value = self.eval(argument)?;
+
+                match operator.as_str() {
+                    "typeof" => Some(RuntimeValue::StringLiteral(value.type_of().to_string())),
+                    _ => None,
+                }
+            }
+            Node::EqualityExpression {
+                operator,
+                left,
+                right,
+            } => {
+                let left_value = self.eval(left)?;
+                let right_value = self.eval(right)?;
+
+                // https://262.ecma-international.org/#sec-isstrictlyequal
+                // NaN は自分自身を含めてどの値とも等しくならない
+                match operator.as_str() {
+                    "===" => Some(RuntimeValue::Boolean(left_value == right_value)),
+                    "!==" => Some(RuntimeValue::Boolean(left_value != right_value)),
+                    "==" => Some(RuntimeValue::Boolean(left_value.is_loosely_equal(&right_value))),
+                    "!=" => Some(RuntimeValue::Boolean(!left_value.is_loosely_equal(&right_value))),
+                    _ => None,
+                }
+            }
             Node::NumericLiteral(value) => Some(RuntimeValue::Number(*value)),
-            _ => todo!(),
+            Node::BooleanLiteral(value) => Some(RuntimeValue::Boolean(*value)),
+            Node::NullLiteral => Some(RuntimeValue::Null),
+            Node::StringLiteral(value) => Some(RuntimeValue::StringLiteral(value.clone())),
+            Node::BlockStatement { body } => {
+                for statement in body {
+                    self.eval(statement);
+                    // break と continue の後の文は実行しない
+                    if self.completion.is_some() || self.timed_out {
+                        break;
+                    }
+                }
+                None
+            }
+            Node::BreakStatement => {
+                self.completion = Some(Completion::Break);
+                None
+            }
+            Node::ContinueStatement => {
+                self.completion = Some(Completion::Continue);
+                None
+            }
+            // https://262.ecma-international.org/#sec-do-while-statement-runtime-semantics-loopevaluation
+            Node::DoWhileStatement { body, test } => {
+                // 条件を確かめる前に、本体を必ず1回は実行する。終わらないループは、評価するノードの数が
+                // 上限に達して条件が評価されなくなったところで抜ける
+                loop {
+                    self.eval(body);
+                    // continue は条件の確認に進む
+                    if self.completion.take() == Some(Completion::Break) {
+                        break;
+                    }
+                    match self.eval(test) {
+                        Some(value) if value.is_truthy() => {}
+                        _ => break,
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    fn is_declared(&self, name: &str) -> bool {
+        self.env.borrow().get_variable(name).is_some()
+    }
+
+    /// Evaluates the arguments of a call from left to right.
+    fn eval_arguments(&mut self, arguments: &[Option<Rc<Node>>]) -> Option<Vec<RuntimeValue>> {
+        let mut args = Vec::new();
+        for argument in arguments {
+            args.push(self.eval(argument)?);
+        }
+        Some(args)
+    }
+
+    /// Returns the property name of `object.property` or `object[property]`.
+    /// https://262.ecma-international.org/#sec-evaluate-property-access-with-expression-key
+    fn property_key(&mut self, property: &Option<Rc<Node>>, computed: bool) -> Option<String> {
+        if computed {
+            // `s[1]` のような数値のキーは "1" になる
+            return Some(self.eval(property)?.to_string());
+        }
+
+        // オブジェクトリテラルのプロパティ名には、文字列と数値も書ける
+        match property.as_deref() {
+            Some(Node::Identifier(name)) | Some(Node::StringLiteral(name)) => Some(name.clone()),
+            Some(Node::NumericLiteral(value)) => Some(RuntimeValue::Number(*value).to_string()),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum RuntimeValue {
-    Number(u64),
+    /// https://262.ecma-international.org/#sec-ecmascript-language-types-number-type
+    Number(f64),
+    StringLiteral(String),
+    Boolean(bool),
+    Null,
+    Undefined,
+    /// A node of the DOM such as `document` or an element returned by `getElementById`.
+    DomNode(Rc<RefCell<dom::Node>>),
+    /// An object created by an object literal. Properties keep the order they were defined in.
+    /// https://262.ecma-international.org/#sec-object-type
+    Object(Rc<Vec<(String, RuntimeValue)>>),
+    /// https://262.ecma-international.org/#sec-array-exotic-objects
+    Array(Rc<Vec<RuntimeValue>>),
+}
+
+/// https://262.ecma-international.org/#sec-isstrictlyequal
+impl PartialEq for RuntimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (RuntimeValue::Number(a), RuntimeValue::Number(b)) => a == b,
+            (RuntimeValue::StringLiteral(a), RuntimeValue::StringLiteral(b)) => a == b,
+            (RuntimeValue::Boolean(a), RuntimeValue::Boolean(b)) => a == b,
+            (RuntimeValue::Null, RuntimeValue::Null) => true,
+            (RuntimeValue::Undefined, RuntimeValue::Undefined) => true,
+            // オブジェクトは同じものかどうかで比べる
+            (RuntimeValue::DomNode(a), RuntimeValue::DomNode(b)) => Rc::ptr_eq(a, b),
+            (RuntimeValue::Object(a), RuntimeValue::Object(b)) => Rc::ptr_eq(a, b),
+            (RuntimeValue::Array(a), RuntimeValue::Array(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl RuntimeValue {
+    /// https://262.ecma-international.org/#sec-typeof-operator
+    pub fn type_of(&self) -> &'static str {
+        match self {
+            RuntimeValue::Number(_) => "number",
+            RuntimeValue::StringLiteral(_) => "string",
+            RuntimeValue::Boolean(_) => "boolean",
+            // 歴史的な理由で null は "object" になる
+            RuntimeValue::Null
+            | RuntimeValue::DomNode(_)
+            | RuntimeValue::Object(_)
+            | RuntimeValue::Array(_) => "object",
+            RuntimeValue::Undefined => "undefined",
+        }
+    }
+
+    /// Returns the value of the property `name`, or Undefined if it doesn't exist.
+    pub fn get_property(&self, name: &str) -> RuntimeValue {
+        match (self, name) {
+            // https://262.ecma-international.org/#sec-properties-of-string-instances-length
+            (RuntimeValue::StringLiteral(value), "length") => {
+                RuntimeValue::Number(value.chars().count() as f64)
+            }
+            // https://262.ecma-international.org/#sec-stringgetownproperty
+            (RuntimeValue::StringLiteral(value), key) => match array_index(key) {
+                Some(index) => match value.chars().nth(index) {
+                    Some(c) => RuntimeValue::StringLiteral(c.to_string()),
+                    None => RuntimeValue::Undefined,
+                },
+                None => RuntimeValue::Undefined,
+            },
+            (RuntimeValue::Array(elements), "length") => RuntimeValue::Number(elements.len() as f64),
+            (RuntimeValue::Array(elements), key) => array_index(key)
+                .and_then(|index| elements.get(index).cloned())
+                .unwrap_or(RuntimeValue::Undefined),
+            (RuntimeValue::Object(properties), key) => properties
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+                .unwrap_or(RuntimeValue::Undefined),
+            _ => RuntimeValue::Undefined,
+        }
+    }
+
+    /// Calls the builtin method `name` with `args`. Returns None if there is no such method.
+    pub fn call_method(&self, name: &str, args: &[RuntimeValue]) -> Option<RuntimeValue> {
+        if let RuntimeValue::DomNode(node) = self {
+            return call_dom_method(node, name, args);
+        }
+        let value = match self {
+            RuntimeValue::StringLiteral(value) => value,
+            _ => return None,
+        };
+        // 足りない引数は undefined として扱う
+        let arg = args.first().cloned().unwrap_or(RuntimeValue::Undefined);
+
+        match name {
+            // https://262.ecma-international.org/#sec-string.prototype.charat
+            "charAt" => {
+                let position = arg.to_number();
+                // NaN は 0 として扱う
+                let position = if position.is_nan() { 0.0 } else { position };
+                let c = if position < 0.0 {
+                    None
+                } else {
+                    value.chars().nth(position as usize)
+                };
+                Some(RuntimeValue::StringLiteral(
+                    c.map(|c| c.to_string()).unwrap_or_default(),
+                ))
+            }
+            // https://262.ecma-international.org/#sec-string.prototype.indexof
+            "indexOf" => {
+                let search = arg.to_string();
+                // 検索を始める位置は文字数で数え、文字列の範囲に収める。NaN や undefined は 0 として扱う
+                let position = args.get(1).map(|p| p.to_number()).unwrap_or(0.0);
+                let position = if position.is_nan() { 0.0 } else { position };
+                let length = value.chars().count();
+                let start = position.clamp(0.0, length as f64) as usize;
+                let offset = value
+                    .char_indices()
+                    .nth(start)
+                    .map(|(i, _)| i)
+                    .unwrap_or(value.len());
+                let index = match value[offset..].find(&search) {
+                    Some(i) => (start + value[offset..offset + i].chars().count()) as f64,
+                    None => -1.0,
+                };
+                Some(RuntimeValue::Number(index))
+            }
+            _ => None,
+        }
+    }
+
+    /// Serializes the value as JSON like `JSON.stringify`, so that results are easy to compare.
+    /// Undefined and non-finite numbers become `null`.
+    /// https://262.ecma-international.org/#sec-serializejsonproperty
+    pub fn to_json(&self) -> String {
+        match self {
+            RuntimeValue::Number(value) if value.is_finite() => self.to_string(),
+            RuntimeValue::StringLiteral(value) => quote_json_string(value),
+            RuntimeValue::Boolean(value) => format!("{}", value),
+            RuntimeValue::Number(_) | RuntimeValue::Null | RuntimeValue::Undefined => {
+                "null".to_string()
+            }
+            // DOM のノードには列挙できるプロパティがない
+            RuntimeValue::DomNode(_) => "{}".to_string(),
+            // https://262.ecma-international.org/#sec-serializejsonarray
+            RuntimeValue::Array(elements) => {
+                let elements = elements.iter().map(|e| e.to_json()).collect::<Vec<String>>();
+                format!("[{}]", elements.join(","))
+            }
+            // https://262.ecma-international.org/#sec-serializejsonobject
+            // 値が undefined のプロパティは書き出さない
+            RuntimeValue::Object(properties) => {
+                let properties = properties
+                    .iter()
+                    .filter(|(_, value)| *value != RuntimeValue::Undefined)
+                    .map(|(name, value)| format!("{}:{}", quote_json_string(name), value.to_json()))
+                    .collect::<Vec<String>>();
+                format!("{{{}}}", properties.join(","))
+            }
+        }
+    }
+
+    /// https://262.ecma-international.org/#sec-tonumber
+    pub fn to_number(&self) -> f64 {
+        match self {
+            RuntimeValue::Number(value) => *value,
+            RuntimeValue::StringLiteral(value) => {
+                let value = value.trim();
+                if value.is_empty() {
+                    0.0
+                } else {
+                    value.parse().unwrap_or(f64::NAN)
+                }
+            }
+            RuntimeValue::Boolean(value) => {
+                if *value {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            RuntimeValue::Null => 0.0,
+            RuntimeValue::Undefined | RuntimeValue::DomNode(_) => f64::NAN,
+            // https://262.ecma-international.org/#sec-toprimitive
+            // オブジェクトは文字列に変換してから数値にする。[] は 0、[5] は 5 になる
+            RuntimeValue::Object(_) | RuntimeValue::Array(_) => {
+                RuntimeValue::StringLiteral(self.to_string()).to_number()
+            }
+        }
+    }
+
+    /// Converts the value to a boolean with the rules shared by conditions and logical operators.
+    /// https://262.ecma-international.org/#sec-toboolean
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            RuntimeValue::Number(value) => !(value.is_nan() || *value == 0.0),
+            RuntimeValue::StringLiteral(value) => !value.is_empty(),
+            RuntimeValue::Boolean(value) => *value,
+            RuntimeValue::Null | RuntimeValue::Undefined => false,
+            RuntimeValue::DomNode(_) | RuntimeValue::Object(_) | RuntimeValue::Array(_) => true,
+        }
+    }
+
+    /// https://262.ecma-international.org/#sec-islooselyequal
+    pub fn is_loosely_equal(&self, other: &RuntimeValue) -> bool {
+        match (self, other) {
+            // null と undefined は互いにだけ等しい
+            (
+                RuntimeValue::Null | RuntimeValue::Undefined,
+                RuntimeValue::Null | RuntimeValue::Undefined,
+            ) => true,
+            (RuntimeValue::Null | RuntimeValue::Undefined, _)
+            | (_, RuntimeValue::Null | RuntimeValue::Undefined) => false,
+            (RuntimeValue::StringLiteral(a), RuntimeValue::StringLiteral(b)) => a == b,
+            // オブジェクト同士は同じものかどうかで比べる
+            (
+                RuntimeValue::Object(_) | RuntimeValue::Array(_) | RuntimeValue::DomNode(_),
+                RuntimeValue::Object(_) | RuntimeValue::Array(_) | RuntimeValue::DomNode(_),
+            ) => self == other,
+            // 文字列とオブジェクトは、オブジェクトを文字列に変換して比べる
+            (RuntimeValue::StringLiteral(a), RuntimeValue::Object(_) | RuntimeValue::Array(_)) => {
+                *a == other.to_string()
+            }
+            (RuntimeValue::Object(_) | RuntimeValue::Array(_), RuntimeValue::StringLiteral(b)) => {
+                self.to_string() == *b
+            }
+            // 型が異なる場合は数値に変換して比べる
+            _ => self.to_number() == other.to_number(),
+        }
+    }
+}
+
+/// Calls the DOM method `name` of `node` with `args`. Returns None if there is no such method.
+fn call_dom_method(
+    node: &Rc<RefCell<dom::Node>>,
+    name: &str,
+    args: &[RuntimeValue],
+) -> Option<RuntimeValue> {
+    // 足りない引数は undefined として扱う
+    let arg = |i: usize| args.get(i).cloned().unwrap_or(RuntimeValue::Undefined).to_string();
+    let kind = node.borrow().kind().clone();
+
+    match (kind, name) {
+        (dom::NodeKind::Document, "getElementById") => {
+            match get_element_by_id(node.clone(), &arg(0)) {
+                Some(element) => Some(RuntimeValue::DomNode(element)),
+                None => Some(RuntimeValue::Null),
+            }
+        }
+        // https://dom.spec.whatwg.org/#dom-element-getattribute
+        (dom::NodeKind::Element(e), "getAttribute") => match e.get_attribute(&arg(0)) {
+            Some(value) => Some(RuntimeValue::StringLiteral(value)),
+            None => Some(RuntimeValue::Null),
+        },
+        (dom::NodeKind::Element(_), "setAttribute") => {
+            if let dom::NodeKind::Element(ref mut e) = node.borrow_mut().kind {
+                e.set_attribute(&arg(0), &arg(1));
+            }
+            Some(RuntimeValue::Undefined)
+        }
+        _ => None,
+    }
+}
+
+/// Calls the global function `name` with `args`. Returns None if there is no such function.
+/// https://262.ecma-international.org/#sec-function-properties-of-the-global-object
+fn call_global_function(name: &str, args: &[RuntimeValue]) -> Option<RuntimeValue> {
+    let arg = args.first().cloned().unwrap_or(RuntimeValue::Undefined);
+
+    match name {
+        "parseInt" => {
+            let radix = args.get(1).map(|radix| radix.to_number()).unwrap_or(f64::NAN);
+            Some(RuntimeValue::Number(parse_int(&arg.to_string(), radix)))
+        }
+        // https://262.ecma-international.org/#sec-string-constructor-string-value
+        // 引数がなければ空文字列になる
+        "String" => match args.first() {
+            Some(value) => Some(RuntimeValue::StringLiteral(value.to_string())),
+            None => Some(RuntimeValue::StringLiteral(String::new())),
+        },
+        _ => None,
+    }
+}
+
+/// Parses the integer at the start of `input` like `parseInt`. Returns NaN if there is none.
+/// https://262.ecma-international.org/#sec-parseint-string-radix
+fn parse_int(input: &str, radix: f64) -> f64 {
+    let mut s = input.trim_start();
+    let sign = match s.chars().next() {
+        Some('-') => -1.0,
+        _ => 1.0,
+    };
+    if s.starts_with('-') || s.starts_with('+') {
+        s = &s[1..];
+    }
+
+    // 基数が指定されていなければ 10 進数。ただし "0x" で始まる場合は 16 進数
+    let mut radix = if radix.is_nan() { 0 } else { radix as u32 };
+    if radix == 0 || radix == 16 {
+        if let Some(rest) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            s = rest;
+            radix = 16;
+        }
+    }
+    if radix == 0 {
+        radix = 10;
+    }
+    if !(2..=36).contains(&radix) {
+        return f64::NAN;
+    }
+
+    // 数字でない文字が現れたら、そこまでの数を返す（"42abc" は 42）
+    let mut result = None;
+    for c in s.chars() {
+        let digit = match c.to_digit(radix) {
+            Some(d) => d,
+            None => break,
+        };
+        result = Some(result.unwrap_or(0.0) * radix as f64 + digit as f64);
+    }
+    match result {
+        Some(value) => sign * value,
+        None => f64::NAN,
+    }
+}
+
+/// Calls `Math.name` with `args`. Returns None if there is no such function.
+/// https://262.ecma-international.org/#sec-function-properties-of-the-math-object
+fn call_math_function(name: &str, args: &[RuntimeValue]) -> Option<RuntimeValue> {
+    let numbers = args.iter().map(|arg| arg.to_number());
+    // 足りない引数は undefined、つまり NaN として扱う
+    let first = args.first().map(|arg| arg.to_number()).unwrap_or(f64::NAN);
+
+    let result = match name {
+        // 引数がなければ、どの数とも比べられるように -Infinity を返す
+        // https://262.ecma-international.org/#sec-math.max
+        "max" => numbers.fold(f64::NEG_INFINITY, |max, n| {
+            if max.is_nan() || n.is_nan() {
+                f64::NAN
+            } else {
+                max.max(n)
+            }
+        }),
+        // https://262.ecma-international.org/#sec-math.min
+        "min" => numbers.fold(f64::INFINITY, |min, n| {
+            if min.is_nan() || n.is_nan() {
+                f64::NAN
+            } else {
+                min.min(n)
+            }
+        }),
+        // https://262.ecma-international.org/#sec-math.abs
+        "abs" => abs(first),
+        // https://262.ecma-international.org/#sec-math.floor
+        "floor" => floor(first),
+        _ => return None,
+    };
+    Some(RuntimeValue::Number(result))
+}
+
+/// no_std では f64::abs が使えないので、符号ビットを落とす。-0 は 0 になる
+fn abs(value: f64) -> f64 {
+    f64::from_bits(value.to_bits() & !(1 << 63))
+}
+
+/// no_std では f64::floor が使えないので、整数部分を切り出して求める
+fn floor(value: f64) -> f64 {
+    // 2^52 以上の数はすでに整数。NaN と Infinity もそのまま返す
+    if !value.is_finite() || abs(value) >= 4_503_599_627_370_496.0 {
+        return value;
+    }
+    let truncated = value as i64 as f64;
+    if truncated > value {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+/// Returns the index that `key` names, or None if it isn't an array index. "01" and "1.5" aren't.
+/// https://262.ecma-international.org/#array-index
+fn array_index(key: &str) -> Option<usize> {
+    match key.parse::<usize>() {
+        Ok(index) if index.to_string() == key => Some(index),
+        _ => None,
+    }
+}
+
+/// https://262.ecma-international.org/#sec-quotejsonstring
+fn quote_json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\u{8}' => result.push_str("\\b"),
+            '\u{c}' => result.push_str("\\f"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+impl Display for RuntimeValue {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let s = match self {
+            // https://262.ecma-international.org/#sec-numeric-types-number-tostring
+            RuntimeValue::Number(value) if value.is_nan() => "NaN".to_string(),
+            RuntimeValue::Number(value) if value.is_infinite() => {
+                if value.is_sign_positive() {
+                    "Infinity".to_string()
+                } else {
+                    "-Infinity".to_string()
+                }
+            }
+            RuntimeValue::Number(value) => format!("{}", value),
+            RuntimeValue::StringLiteral(value) => value.to_string(),
+            RuntimeValue::Boolean(value) => format!("{}", value),
+            RuntimeValue::Null => "null".to_string(),
+            RuntimeValue::Undefined => "undefined".to_string(),
+            RuntimeValue::DomNode(node) => match node.borrow().kind() {
+                dom::NodeKind::Document => "[object HTMLDocument]".to_string(),
+                dom::NodeKind::Element(_) => "[object HTMLElement]".to_string(),
+                dom::NodeKind::Text(_) => "[object Text]".to_string(),
+            },
+            // https://262.ecma-international.org/#sec-array.prototype.join
+            // null と undefined の要素は空文字列になる
+            RuntimeValue::Array(elements) => elements
+                .iter()
+                .map(|e| match e {
+                    RuntimeValue::Null | RuntimeValue::Undefined => String::new(),
+                    e => e.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join(","),
+            RuntimeValue::Object(_) => "[object Object]".to_string(),
+        };
+        write!(f, "{}", s)
+    }
 }
 
 impl Add<RuntimeValue> for RuntimeValue {
     type Output = RuntimeValue;
 
+    /// https://262.ecma-international.org/#sec-applystringornumericbinaryoperator
     fn add(self, rhs: RuntimeValue) -> Self::Output {
-        let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs);
-        RuntimeValue::Number(left_num + right_num)
+        // どちらかが文字列なら、もう一方も文字列に変換して連結する。
+        // オブジェクトは文字列に変換されるので、[1] + 1 は "11" になる
+        let is_string = |value: &RuntimeValue| {
+            matches!(
+                value,
+                RuntimeValue::StringLiteral(_) | RuntimeValue::Object(_) | RuntimeValue::Array(_)
+            )
+        };
+        if is_string(&self) || is_string(&rhs) {
+            return RuntimeValue::StringLiteral(format!("{}{}", self, rhs));
+        }
+
+        // それ以外は数値として足す（true + 1 は 2、null + 1 は 1）
+        RuntimeValue::Number(self.to_number() + rhs.to_number())
     }
 }
 
@@ -87,16 +903,34 @@ impl Sub<RuntimeValue> for RuntimeValue {
     type Output = RuntimeValue;
 
     fn sub(self, rhs: RuntimeValue) -> Self::Output {
-        let (RuntimeValue::Number(left_num), RuntimeValue::Number(right_num)) = (&self, &rhs);
-        RuntimeValue::Number(left_num - right_num)
+        RuntimeValue::Number(self.to_number() - rhs.to_number())
+    }
+}
+
+impl Mul<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    fn mul(self, rhs: RuntimeValue) -> Self::Output {
+        RuntimeValue::Number(self.to_number() * rhs.to_number())
+    }
+}
+
+/// 0 で割ると、例外ではなく Infinity や NaN になる
+impl Div<RuntimeValue> for RuntimeValue {
+    type Output = RuntimeValue;
+
+    fn div(self, rhs: RuntimeValue) -> Self::Output {
+        RuntimeValue::Number(self.to_number() / rhs.to_number())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use alloc::string::ToString;
+    use alloc::vec;
     use crate::renderer::js::ast::JsParser;
     use crate::renderer::js::token::JsLexer;
+    use crate::testing::parse_document;
     use super::*;
 
     #[test]
@@ -106,7 +940,7 @@ mod tests {
         let mut parser = JsParser::new(lexer);
         let ast = parser.parse_ast();
         let mut runtime = JsRuntime::new();
-        let expected = [Some(RuntimeValue::Number(42))];
+        let expected = [Some(RuntimeValue::Number(42.0))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -123,7 +957,7 @@ mod tests {
         let mut parser = JsParser::new(lexer);
         let ast = parser.parse_ast();
         let mut runtime = JsRuntime::new();
-        let expected = [Some(RuntimeValue::Number(3))];
+        let expected = [Some(RuntimeValue::Number(3.0))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -140,7 +974,134 @@ mod tests {
         let mut parser = JsParser::new(lexer);
         let ast = parser.parse_ast();
         let mut runtime = JsRuntime::new();
-        let expected = [Some(RuntimeValue::Number(1))];
+        let expected = [Some(RuntimeValue::Number(1.0))];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()));
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+    }
+
+    #[test]
+    fn test_typeof() {
+        let js = "typeof 5; typeof \"x\"; typeof true; typeof typeof 5".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new();
+        let expected = [
+            Some(RuntimeValue::StringLiteral("number".to_string())),
+            Some(RuntimeValue::StringLiteral("string".to_string())),
+            Some(RuntimeValue::StringLiteral("boolean".to_string())),
+            Some(RuntimeValue::StringLiteral("string".to_string())),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()));
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+        assert_eq!(expected.len(), i);
+    }
+
+    #[test]
+    fn test_strict_equality() {
+        let js = "5 === \"5\"; 5 === 5; 5 !== \"5\"; \"a\" === \"a\"; true !== true".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new();
+        let expected = [
+            Some(RuntimeValue::Boolean(false)),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(false)),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()));
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+        assert_eq!(expected.len(), i);
+    }
+
+    #[test]
+    fn test_add_string() {
+        let js = "\"a\" + 1 + true".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new();
+        let result = runtime.eval(&Some(ast.body()[0].clone()));
+        assert_eq!(Some(RuntimeValue::StringLiteral("a1true".to_string())), result);
+    }
+
+    #[test]
+    fn test_add_mixed_types() {
+        let expected = [
+            "v=1", "v=true", "v=false", "v=null", "v=undefined", "v=NaN", "true!", "null!",
+            "undefined!", "1!",
+        ];
+        let results = eval_all(
+            "\"v=\" + 1; \"v=\" + true; \"v=\" + false; \"v=\" + null; \"v=\" + undefined; \"v=\" + 0 / 0; true + \"!\"; null + \"!\"; undefined + \"!\"; 1 + \"!\"",
+        );
+        assert_eq!(expected.len(), results.len());
+        for (expected, result) in expected.iter().zip(results) {
+            assert_eq!(Some(RuntimeValue::StringLiteral(expected.to_string())), result);
+        }
+
+        // 文字列がなければ数値として足す
+        assert_eq!(
+            vec![
+                Some(RuntimeValue::Number(2.0)),
+                Some(RuntimeValue::Number(1.0)),
+                Some(RuntimeValue::StringLiteral("2a".to_string())),
+            ],
+            eval_all("true + 1; null + 1; 1 + 1 + \"a\"")
+        );
+        let result = eval_all("undefined + 1")[0].clone();
+        assert!(matches!(result, Some(RuntimeValue::Number(n)) if n.is_nan()));
+    }
+
+    #[test]
+    fn test_multiple_declarators() {
+        let js = "var a = 1, b = a + 1, c; a; b; c; c = b + 1; c".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new();
+        let expected = [
+            None,
+            Some(RuntimeValue::Number(1.0)),
+            Some(RuntimeValue::Number(2.0)),
+            Some(RuntimeValue::Undefined),
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Number(3.0)),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()));
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+        assert_eq!(expected.len(), i);
+    }
+
+    #[test]
+    fn test_statements_without_semicolon() {
+        let js = "var x = 1\nvar y = 2\nx + y".to_string();
+        let lexer = JsLexer::new(js);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new();
+        let expected = [None, None, Some(RuntimeValue::Number(3.0))];
         let mut i = 0;
 
         for node in ast.body() {
@@ -148,5 +1109,439 @@ mod tests {
             assert_eq!(expected[i], result);
             i += 1;
         }
+        assert_eq!(expected.len(), i);
+    }
+
+    fn eval_all(js: &str) -> Vec<Option<RuntimeValue>> {
+        let lexer = JsLexer::new(js.to_string());
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new();
+        ast.body()
+            .iter()
+            .map(|node| runtime.eval(&Some(node.clone())))
+            .collect()
+    }
+
+    #[test]
+    fn test_redeclaration() {
+        // 同じ名前を宣言し直すと、前の変数の値を更新する
+        assert_eq!(Some(RuntimeValue::Number(2.0)), eval_all("var a = 1; var a = 2; a")[2]);
+        assert_eq!(Some(RuntimeValue::Number(2.0)), eval_all("var a = 1, a = 2; a")[1]);
+        // 初期値のない再宣言では、値は変わらない
+        assert_eq!(Some(RuntimeValue::Number(1.0)), eval_all("var a = 1; var a; a")[2]);
+    }
+
+    #[test]
+    fn test_mul_div() {
+        let expected = vec![
+            Some(RuntimeValue::Number(7.0)),
+            Some(RuntimeValue::Number(2.5)),
+        ];
+        assert_eq!(expected, eval_all("1 + 2 * 3; 5 / 2"));
+    }
+
+    #[test]
+    fn test_non_finite_numbers() {
+        let results = eval_all("1 / 0; 0 - 1 / 0; 0 / 0; NaN; Infinity");
+        let strings = results
+            .iter()
+            .map(|r| r.clone().expect("should have a value").to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(vec!["Infinity", "-Infinity", "NaN", "NaN", "Infinity"], strings);
+    }
+
+    #[test]
+    fn test_nan_comparison() {
+        let expected = vec![
+            Some(RuntimeValue::Boolean(false)),
+            Some(RuntimeValue::Boolean(false)),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(false)),
+        ];
+        assert_eq!(expected, eval_all("NaN == NaN; NaN === 0 / 0; NaN != NaN; 1 / 0 == NaN"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_loose_equality() {
+        let expected = vec![
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(false)),
+            Some(RuntimeValue::Boolean(true)),
+        ];
+        assert_eq!(expected, eval_all("5 == \"5\"; 1 == true; \"a\" == \"b\"; 1 != 2"));
+    }
+
+    #[test]
+    fn test_string_length() {
+        let expected = vec![
+            Some(RuntimeValue::Number(5.0)),
+            Some(RuntimeValue::Number(0.0)),
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Undefined),
+        ];
+        assert_eq!(
+            expected,
+            eval_all("\"hello\".length; \"\".length; var s = \"abc\"; s.length; s.foo")
+                .into_iter()
+                .filter(|r| r.is_some())
+                .collect::<Vec<Option<RuntimeValue>>>()
+        );
+    }
+
+    #[test]
+    fn test_string_methods() {
+        let expected = vec![
+            Some(RuntimeValue::StringLiteral("b".to_string())),
+            Some(RuntimeValue::StringLiteral("".to_string())),
+            Some(RuntimeValue::Number(2.0)),
+            Some(RuntimeValue::Number(-1.0)),
+            Some(RuntimeValue::Number(1.0)),
+        ];
+        assert_eq!(
+            expected,
+            eval_all(
+                "\"abc\".charAt(1); \"abc\".charAt(3); \"abc\".indexOf(\"c\"); \"abc\".indexOf(\"d\"); \"abc\".charAt(0).length"
+            )
+        );
+    }
+
+    #[test]
+    fn test_index_of_from_index() {
+        let expected = vec![
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Number(0.0)),
+            Some(RuntimeValue::Number(-1.0)),
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Number(4.0)),
+        ];
+        // 開始位置は負の数なら 0、長すぎれば文字列の長さになり、バイトではなく文字で数える
+        assert_eq!(
+            expected,
+            eval_all(
+                "\"abcabc\".indexOf(\"a\", 1); \"abc\".indexOf(\"a\", 0 - 5); \"abc\".indexOf(\"a\", 10); \"abc\".indexOf(\"\", 10); \"ああいあい\".indexOf(\"い\", 3)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_keyword_prefixed_variables() {
+        let expected = vec![
+            None,
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Boolean(true)),
+        ];
+        assert_eq!(
+            expected,
+            eval_all("var variable = 1, trueish = 2; variable + trueish; undefined === trueish.foo")
+        );
+    }
+
+    #[test]
+    fn test_null() {
+        let expected = vec![
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(false)),
+            Some(RuntimeValue::Boolean(false)),
+            Some(RuntimeValue::StringLiteral("object".to_string())),
+            Some(RuntimeValue::StringLiteral("a null".to_string())),
+        ];
+        assert_eq!(
+            expected,
+            eval_all(
+                "null === null; null == undefined; null === undefined; null == 0; typeof null; \"a \" + null"
+            )
+        );
+        assert!(!RuntimeValue::Null.is_truthy());
+        assert!(!RuntimeValue::Undefined.is_truthy());
+        assert!(!RuntimeValue::Number(f64::NAN).is_truthy());
+        assert!(RuntimeValue::StringLiteral("0".to_string()).is_truthy());
+    }
+
+    #[test]
+    fn test_is_truthy() {
+        let values = [
+            (RuntimeValue::Number(0.0), false),
+            (RuntimeValue::Number(-0.0), false),
+            (RuntimeValue::Number(f64::NAN), false),
+            (RuntimeValue::Number(-1.5), true),
+            (RuntimeValue::Number(f64::INFINITY), true),
+            (RuntimeValue::StringLiteral("".to_string()), false),
+            // "0" や "false" も空でない文字列なので true
+            (RuntimeValue::StringLiteral("0".to_string()), true),
+            (RuntimeValue::StringLiteral("false".to_string()), true),
+            (RuntimeValue::Boolean(true), true),
+            (RuntimeValue::Boolean(false), false),
+            (RuntimeValue::Null, false),
+            (RuntimeValue::Undefined, false),
+        ];
+        for (value, expected) in values {
+            assert_eq!(expected, value.is_truthy(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_do_while() {
+        // 条件が最初から偽でも、本体は1回実行される
+        assert_eq!(
+            Some(RuntimeValue::Number(1.0)),
+            eval_all("var n = 0; do { n = n + 1; } while (false); n")[2]
+        );
+        assert_eq!(
+            Some(RuntimeValue::Number(5.0)),
+            eval_all("var i = 0; do { i = i + 1 } while (i !== 5) i")[2]
+        );
+
+        // 終わらないループは途中で打ち切らず、評価するノードの数の上限で ScriptTimeout になる
+        let js = "var c = 0; do c = c + 1; while (true); c";
+        let program = JsParser::new(JsLexer::new(js.to_string())).parse_ast();
+        let mut runtime = JsRuntime::new();
+        runtime.set_max_operations(1000);
+        assert_eq!(Err(Error::ScriptTimeout), runtime.execute(&program));
+    }
+
+    #[test]
+    fn test_script_timeout() {
+        // 入れ子のループは全体で 10000 * 10000 回になる
+        let js = "var n = 0, i = 0, j = 0; do { j = 0; do { j = j + 1; n = n + 1 } while (j !== 10000); i = i + 1 } while (i !== 10000); n";
+        let program = JsParser::new(JsLexer::new(js.to_string())).parse_ast();
+        let mut runtime = JsRuntime::new();
+        runtime.set_max_operations(1000);
+        assert_eq!(Err(Error::ScriptTimeout), runtime.execute(&program));
+        assert_eq!(1000, runtime.operations);
+        // 止まったのは 2 つ目の文の do-while
+        assert_eq!(Some(Span { offset: 25, line: 1, column: 26 }), runtime.timeout_span());
+        // 上限に達するまでは評価が進んでいる
+        assert!(runtime.env.borrow().get_variable("n").expect("n is declared").to_number() > 0.0);
+
+        // 上限は実行ごとに数え直す
+        runtime.set_max_operations(DEFAULT_MAX_OPERATIONS);
+        let program = JsParser::new(JsLexer::new("var m = 1 + 2".to_string())).parse_ast();
+        assert_eq!(Ok(()), runtime.execute(&program));
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        // break は一番内側のループだけを抜ける
+        assert_eq!(
+            Some(RuntimeValue::Number(3.0)),
+            eval_all(
+                "var n = 0, inner = 0; do { do { inner = inner + 1; break; inner = 100 } while (true); n = n + 1 } while (n !== 3); inner"
+            )[2]
+        );
+        // continue の後の文は飛ばして、次の繰り返しに進む
+        assert_eq!(
+            vec![Some(RuntimeValue::Number(4.0)), Some(RuntimeValue::Number(0.0))],
+            eval_all("var i = 0, sum = 0; do { i = i + 1; continue; sum = sum + i } while (i !== 4); i; sum")[2..]
+                .to_vec()
+        );
+        // ループの外の break や continue は構文エラーになるが、その後の文は実行される
+        assert_eq!(
+            vec![Some(RuntimeValue::Number(2.0)), Some(RuntimeValue::Number(3.0))],
+            eval_all("var a = 1; break; a = 2; continue; var b = 3; a; b")[5..].to_vec()
+        );
+    }
+
+    #[test]
+    fn test_math() {
+        let expected = vec![
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Number(-2.0)),
+            Some(RuntimeValue::Number(2.5)),
+            Some(RuntimeValue::Number(1.0)),
+            Some(RuntimeValue::Number(-2.0)),
+            Some(RuntimeValue::Number(f64::NEG_INFINITY)),
+            Some(RuntimeValue::Number(f64::INFINITY)),
+        ];
+        assert_eq!(
+            expected,
+            eval_all(
+                "Math.max(1, 3, \"2\"); Math.min(0, 0 - 2); Math.abs(0 - 5 / 2); Math.floor(19 / 10); Math.floor(0 - 3 / 2); Math.max(); Math.min()"
+            )
+        );
+
+        let results = eval_all("Math.max(1, NaN); Math.abs(); Math.floor(\"a\")");
+        for result in results {
+            assert!(matches!(result, Some(RuntimeValue::Number(n)) if n.is_nan()));
+        }
+        assert_eq!(None, eval_all("Math.round(1)")[0]);
+    }
+
+    #[test]
+    fn test_global_functions() {
+        let expected = vec![
+            Some(RuntimeValue::Number(42.0)),
+            Some(RuntimeValue::Number(-7.0)),
+            Some(RuntimeValue::Number(255.0)),
+            Some(RuntimeValue::Number(5.0)),
+            Some(RuntimeValue::StringLiteral("5".to_string())),
+            Some(RuntimeValue::StringLiteral("null".to_string())),
+            Some(RuntimeValue::StringLiteral("".to_string())),
+            Some(RuntimeValue::StringLiteral("false1".to_string())),
+        ];
+        assert_eq!(
+            expected,
+            eval_all(
+                "parseInt(\"42abc\"); parseInt(\"  -7\"); parseInt(\"0xff\"); parseInt(\"101\", 2); String(5); String(null); String(); String(false) + 1"
+            )
+        );
+
+        for result in eval_all("parseInt(\"x\"); parseInt(\"\"); parseInt(\"1\", 37)") {
+            assert!(matches!(result, Some(RuntimeValue::Number(n)) if n.is_nan()));
+        }
+        // 同じ名前の変数があれば、組み込みの関数は呼ばない
+        assert_eq!(None, eval_all("var String = 1; String(5)")[1]);
+    }
+
+    #[test]
+    fn test_dom_methods() {
+        let document = parse_document("<html><body><a id=\"l\" href=\"/a\">a</a></body></html>");
+        let program = JsParser::new(JsLexer::new(
+            "var a = document.getElementById(\"l\"); a.getAttribute(\"href\"); a.setAttribute(\"href\", \"/b\"); a.getAttribute(\"href\"); a === document.getElementById(\"l\"); document.getElementById(\"none\"); typeof a".to_string(),
+        ))
+        .parse_ast();
+        let mut runtime = JsRuntime::new();
+        runtime.set_document(document);
+        let results = program
+            .body()
+            .iter()
+            .map(|node| runtime.eval(&Some(node.clone())))
+            .collect::<Vec<Option<RuntimeValue>>>();
+        assert_eq!(
+            vec![
+                None,
+                Some(RuntimeValue::StringLiteral("/a".to_string())),
+                Some(RuntimeValue::Undefined),
+                Some(RuntimeValue::StringLiteral("/b".to_string())),
+                Some(RuntimeValue::Boolean(true)),
+                Some(RuntimeValue::Null),
+                Some(RuntimeValue::StringLiteral("object".to_string())),
+            ],
+            results
+        );
+        // href は描画に影響しない
+        assert!(!runtime.is_dom_modified());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let values = [
+            RuntimeValue::Number(2.5),
+            RuntimeValue::Number(-3.0),
+            RuntimeValue::Number(f64::NAN),
+            RuntimeValue::StringLiteral("a\"b\\c\nd\u{1}é".to_string()),
+            RuntimeValue::Boolean(true),
+            RuntimeValue::Null,
+            RuntimeValue::Undefined,
+        ];
+        let expected = [
+            "2.5",
+            "-3",
+            "null",
+            "\"a\\\"b\\\\c\\nd\\u0001é\"",
+            "true",
+            "null",
+            "null",
+        ];
+        for (value, json) in values.iter().zip(expected) {
+            assert_eq!(json, value.to_json());
+        }
+    }
+
+    #[test]
+    fn test_computed_member_access() {
+        let expected = vec![
+            None,
+            Some(RuntimeValue::StringLiteral("a".to_string())),
+            Some(RuntimeValue::StringLiteral("c".to_string())),
+            Some(RuntimeValue::StringLiteral("b".to_string())),
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Undefined),
+            Some(RuntimeValue::Undefined),
+            Some(RuntimeValue::Number(1.0)),
+        ];
+        assert_eq!(
+            expected,
+            eval_all(
+                "var s = \"abc\", i = 1; s[0]; s[i + 1]; s[\"1\"]; s[\"length\"]; s[3]; s[\"01\"]; s[\"indexOf\"](\"b\")"
+            )
+        );
+    }
+
+    #[test]
+    fn test_object_and_array_member_access() {
+        let expected = vec![
+            None,
+            Some(RuntimeValue::Number(1.0)),
+            Some(RuntimeValue::StringLiteral("x".to_string())),
+            Some(RuntimeValue::StringLiteral("one".to_string())),
+            Some(RuntimeValue::StringLiteral("one".to_string())),
+            Some(RuntimeValue::Undefined),
+            Some(RuntimeValue::Number(2.0)),
+            Some(RuntimeValue::StringLiteral("y".to_string())),
+            Some(RuntimeValue::Undefined),
+            Some(RuntimeValue::Number(3.0)),
+            Some(RuntimeValue::Number(3.0)),
+        ];
+        // obj[1] は "1" という名前のプロパティを読む
+        assert_eq!(
+            expected,
+            eval_all(
+                "var obj = {a: 1, \"b\": \"x\", 1: \"one\"}, arr = [2, \"y\", [3]], i = 0; obj[\"a\"]; obj.b; obj[1]; obj[\"1\"]; obj.c; arr[0]; arr[i + 1]; arr[3]; arr[2][0]; arr.length"
+            )
+        );
+    }
+
+    #[test]
+    fn test_object_and_array_values() {
+        let expected = vec![
+            None,
+            Some(RuntimeValue::StringLiteral("object".to_string())),
+            Some(RuntimeValue::StringLiteral("object".to_string())),
+            Some(RuntimeValue::Boolean(true)),
+            Some(RuntimeValue::Boolean(false)),
+            Some(RuntimeValue::StringLiteral("1,,a".to_string())),
+            Some(RuntimeValue::StringLiteral("[object Object]!".to_string())),
+            Some(RuntimeValue::Number(5.0)),
+            Some(RuntimeValue::Boolean(true)),
+        ];
+        assert_eq!(
+            expected,
+            eval_all(
+                "var o = {a: 1}, p = o; typeof o; typeof []; o === p; o === {a: 1}; [1, null, \"a\"] + \"\"; o + \"!\"; [5] * 1; [1, 2] == \"1,2\""
+            )
+        );
+
+        let values = eval_all("[1, \"a\", undefined, [true]]; {}");
+        assert_eq!(
+            "[1,\"a\",null,[true]]",
+            values[0].as_ref().expect("failed to eval").to_json()
+        );
+        let values = eval_all("var o = {a: 1, b: undefined, a: [null]}; o");
+        assert_eq!(
+            "{\"a\":[null]}",
+            values[1].as_ref().expect("failed to eval").to_json()
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let lexer = JsLexer::new("var a = 1; b = \"x\"; NaN = 2".to_string());
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new();
+        runtime.execute(&ast).expect("failed to execute");
+        assert_eq!(Some(RuntimeValue::Number(1.0)), runtime.env.borrow().get_variable("a"));
+
+        let old_env = Rc::downgrade(&runtime.env);
+        runtime.reset();
+        // 古い環境を参照しているものは残っていない
+        assert!(old_env.upgrade().is_none());
+        assert_eq!(None, runtime.env.borrow().get_variable("a"));
+        assert_eq!(None, runtime.env.borrow().get_variable("b"));
+        let nan = runtime.env.borrow().get_variable("NaN");
+        assert!(matches!(nan, Some(RuntimeValue::Number(n)) if n.is_nan()));
+    }
+}
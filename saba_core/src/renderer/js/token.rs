@@ -1,18 +1,55 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Punctuator(char),
-    Number(u64),
+    /// An operator that consists of multiple characters such as `===`.
+    Operator(String),
+    Number(f64),
     Identifier(String),
     Keyword(String),
     StringLiteral(String),
 }
 
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Token::Punctuator(c) => write!(f, "{}", c),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Operator(s) | Token::Identifier(s) | Token::Keyword(s) => write!(f, "{}", s),
+            Token::StringLiteral(s) => write!(f, "\"{}\"", s),
+        }
+    }
+}
+
+/// The position of a token in the source. `line` and `column` start from 1.
+/// Syntax errors carry it, and so do the top-level statements of a `Program` so that errors
+/// at run time can be reported by position too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Span {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
 pub struct JsLexer {
     pos: usize,
     input: Vec<char>,
+    /// `input[..counted]` まで数えた位置
+    counted: usize,
+    span: Span,
 }
 
 impl JsLexer {
@@ -20,33 +57,76 @@ impl JsLexer {
         Self {
             pos: 0,
             input: js.chars().collect(),
+            counted: 0,
+            span: Span::default(),
         }
     }
 
-    fn consume_number(&mut self) -> u64 {
-        let mut num = 0;
-
-        loop {
-            if self.input.len() <= self.pos {
-                return num;
+    /// Returns the position of the next token, or the end of the input if there is no token left.
+    pub fn current_span(&mut self) -> Span {
+        self.skip_whitespaces();
+
+        // 毎回先頭から数えないように、前回の位置から進める
+        while self.counted < self.pos && self.counted < self.input.len() {
+            let c = self.input[self.counted];
+            self.span.offset += c.len_utf8();
+            if c == '\n' {
+                self.span.line += 1;
+                self.span.column = 1;
+            } else {
+                self.span.column += 1;
             }
+            self.counted += 1;
+        }
 
-            let c = self.input[self.pos];
+        self.span
+    }
 
-            match c {
-                '0'..='9' => {
-                    num = num * 10 + (c.to_digit(10).unwrap() as u64);
-                    self.pos += 1;
-                }
-                _ => break,
-            }
+    /// Returns the next token with its position.
+    pub fn next_with_span(&mut self) -> Option<(Token, Span)> {
+        let span = self.current_span();
+        self.next().map(|token| (token, span))
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while self.pos < self.input.len() && matches!(self.input[self.pos], ' ' | '\n' | '\r' | '\t')
+        {
+            self.pos += 1;
+        }
+    }
+
+    /// Reads a decimal literal such as `42` or `1.5`. A literal too large for `f64` becomes
+    /// `Infinity`.
+    /// https://262.ecma-international.org/#sec-literals-numeric-literals
+    fn consume_number(&mut self) -> f64 {
+        let mut literal = String::new();
+        self.consume_digits(&mut literal);
+
+        // `1.` の後に数字が続かない場合は、小数点ではなく区切り記号として残す
+        if self.input.get(self.pos) == Some(&'.')
+            && self.input.get(self.pos + 1).is_some_and(|c| c.is_ascii_digit())
+        {
+            literal.push('.');
+            self.pos += 1;
+            self.consume_digits(&mut literal);
         }
 
-        num
+        // 桁数が多すぎる場合も、parse はパニックせずに inf を返す
+        literal.parse().unwrap_or(f64::INFINITY)
+    }
+
+    fn consume_digits(&mut self, literal: &mut String) {
+        while let Some(c) = self.input.get(self.pos).filter(|c| c.is_ascii_digit()) {
+            literal.push(*c);
+            self.pos += 1;
+        }
     }
 
     fn contains(&self, keyword: &str) -> bool {
         for i in 0..keyword.len() {
+            if self.input.len() <= self.pos + i {
+                return false;
+            }
             if keyword
                 .chars()
                 .nth(i)
@@ -62,7 +142,9 @@ impl JsLexer {
 
     fn check_reserved_word(&self) -> Option<String> {
         for word in RESERVED_WORDS {
-            if self.contains(word) {
+            // `variable` のように予約語で始まるだけの識別子は予約語ではない
+            let next = self.input.get(self.pos + word.len());
+            if self.contains(word) && !next.is_some_and(|c| is_identifier_char(*c)) {
                 return Some(word.to_string());
             }
         }
@@ -78,7 +160,7 @@ impl JsLexer {
                 return result;
             }
 
-            if self.input[self.pos].is_ascii_alphanumeric() || self.input[self.pos] == '$' {
+            if is_identifier_char(self.input[self.pos]) {
                 result.push(self.input[self.pos]);
                 self.pos += 1;
             } else {
@@ -107,34 +189,47 @@ impl JsLexer {
     }
 }
 
-static RESERVED_WORDS: [&str; 1] = ["var"];
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// https://262.ecma-international.org/#sec-keywords-and-reserved-words
+/// `undefined` is a property of the global object in JavaScript, but it's lexed as a keyword here.
+static RESERVED_WORDS: [&str; 15] = [
+    "var", "typeof", "true", "false", "null", "undefined", "if", "else", "while", "for",
+    "function", "return", "do", "break", "continue",
+];
+
+/// Operators that start with the same character must be ordered from the longest.
+static OPERATORS: [&str; 4] = ["===", "!==", "==", "!="];
 
 impl Iterator for JsLexer {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespaces();
         if self.input.len() <= self.pos {
             return None;
         }
 
-        while self.input[self.pos] == ' ' || self.input[self.pos] == '\n' {
-            self.pos += 1;
-
-            if self.input.len() <= self.pos {
-                return None;
-            }
-        }
-
         if let Some(keyword) = self.check_reserved_word() {
             self.pos += keyword.len();
             let token = Some(Token::Keyword(keyword));
             return token;
         }
 
+        for operator in OPERATORS {
+            if self.contains(operator) {
+                self.pos += operator.len();
+                return Some(Token::Operator(operator.to_string()));
+            }
+        }
+
         let c = self.input[self.pos];
 
         let token = match c {
-            '+' | '-' | ';' | '=' | '(' | ')' | '{' | '}' | ',' | '.' => {
+            '+' | '-' | '*' | '/' | ';' | ':' | '=' | '(' | ')' | '{' | '}' | '[' | ']' | ','
+            | '.' => {
                 let t = Token::Punctuator(c);
                 self.pos += 1;
                 t
@@ -142,7 +237,12 @@ impl Iterator for JsLexer {
             '0'..='9' => Token::Number(self.consume_number()),
             'a'..='z' | 'A'..='Z' | '_' | '$' => Token::Identifier(self.consume_identifier()),
             '"' => Token::StringLiteral(self.consume_string()),
-            _ => unimplemented!("char {:?} is not supported yet", c),
+            // `<` や `!` のようにまだサポートしない文字も1文字の区切り記号として返し、
+            // パーサーに構文エラーとして報告させる
+            _ => {
+                self.pos += 1;
+                Token::Punctuator(c)
+            }
         };
         Some(token)
     }
@@ -165,7 +265,7 @@ mod tests {
     fn test_num() {
         let input = "42".to_string();
         let mut lexer = JsLexer::new(input).peekable();
-        let expected = vec![Token::Number(42)];
+        let expected = vec![Token::Number(42.0)];
         let mut i = 0;
         while lexer.peek().is_some() {
             assert_eq!(Some(expected[i].clone()), lexer.next());
@@ -179,9 +279,9 @@ mod tests {
         let input = "1 + 2".to_string();
         let mut lexer = JsLexer::new(input).peekable();
         let expected = vec![
-            Token::Number(1),
+            Token::Number(1.0),
             Token::Punctuator('+'),
-            Token::Number(2),
+            Token::Number(2.0),
         ];
         let mut i = 0;
         while lexer.peek().is_some() {
@@ -218,14 +318,14 @@ mod tests {
             Token::Keyword("var".to_string()),
             Token::Identifier("foo".to_string()),
             Token::Punctuator('='),
-            Token::Number(42),
+            Token::Number(42.0),
             Token::Punctuator(';'),
             Token::Keyword("var".to_string()),
             Token::Identifier("result".to_string()),
             Token::Punctuator('='),
             Token::Identifier("foo".to_string()),
             Token::Punctuator('+'),
-            Token::Number(1),
+            Token::Number(1.0),
             Token::Punctuator(';'),
         ];
         let mut i = 0;
@@ -235,4 +335,112 @@ mod tests {
         }
         assert!(lexer.peek().is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_strict_equality() {
+        let input = "typeof a === \"number\" !== false".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = vec![
+            Token::Keyword("typeof".to_string()),
+            Token::Identifier("a".to_string()),
+            Token::Operator("===".to_string()),
+            Token::StringLiteral("number".to_string()),
+            Token::Operator("!==".to_string()),
+            Token::Keyword("false".to_string()),
+        ];
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+    }
+
+    #[test]
+    fn test_keyword_prefix_at_end() {
+        let input = "1 + typ".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = vec![
+            Token::Number(1.0),
+            Token::Punctuator('+'),
+            Token::Identifier("typ".to_string()),
+        ];
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+    }
+
+    #[test]
+    fn test_span() {
+        let mut lexer = JsLexer::new("var a\n  = \"é\" + 1".to_string());
+        let expected = vec![
+            (Token::Keyword("var".to_string()), Span { offset: 0, line: 1, column: 1 }),
+            (Token::Identifier("a".to_string()), Span { offset: 4, line: 1, column: 5 }),
+            (Token::Punctuator('='), Span { offset: 8, line: 2, column: 3 }),
+            (Token::StringLiteral("é".to_string()), Span { offset: 10, line: 2, column: 5 }),
+            // "é" は UTF-8 で2バイト
+            (Token::Punctuator('+'), Span { offset: 15, line: 2, column: 9 }),
+            (Token::Number(1.0), Span { offset: 17, line: 2, column: 11 }),
+        ];
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_with_span() {
+            tokens.push(token);
+        }
+        assert_eq!(expected, tokens);
+        assert_eq!(Span { offset: 18, line: 2, column: 12 }, lexer.current_span());
+    }
+
+    #[test]
+    fn test_keywords_and_identifiers() {
+        let input = "var variable = true; typeof_ falsey false returned return".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = vec![
+            Token::Keyword("var".to_string()),
+            Token::Identifier("variable".to_string()),
+            Token::Punctuator('='),
+            Token::Keyword("true".to_string()),
+            Token::Punctuator(';'),
+            Token::Identifier("typeof_".to_string()),
+            Token::Identifier("falsey".to_string()),
+            Token::Keyword("false".to_string()),
+            Token::Identifier("returned".to_string()),
+            Token::Keyword("return".to_string()),
+        ];
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+    }
+
+    #[test]
+    fn test_unsupported_char() {
+        let input = "a < 2 % !b".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::Punctuator('<'),
+            Token::Number(2.0),
+            Token::Punctuator('%'),
+            Token::Punctuator('!'),
+            Token::Identifier("b".to_string()),
+        ];
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+    }
+
+    #[test]
+    fn test_decimal_and_large_numbers() {
+        let input = "1.5 + 99999999999999999999 + 1".to_string();
+        let lexer = JsLexer::new(input);
+        let expected = vec![
+            Token::Number(1.5),
+            Token::Punctuator('+'),
+            Token::Number(1e20),
+            Token::Punctuator('+'),
+            Token::Number(1.0),
+        ];
+        assert_eq!(expected, lexer.collect::<Vec<Token>>());
+
+        // f64 に収まらないリテラルは Infinity になる
+        let input = "1".repeat(400);
+        assert_eq!(vec![Token::Number(f64::INFINITY)], JsLexer::new(input).collect::<Vec<Token>>());
+
+        // 小数点の後に数字がなければ、区切り記号として読む
+        let input = "1.a".to_string();
+        let expected = vec![
+            Token::Number(1.0),
+            Token::Punctuator('.'),
+            Token::Identifier("a".to_string()),
+        ];
+        assert_eq!(expected, JsLexer::new(input).collect::<Vec<Token>>());
+    }
+}
@@ -2,35 +2,59 @@ use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString};
 use core::cell::RefCell;
+use core::str::FromStr;
 use crate::error::Error;
 use crate::renderer::dom::node::{ElementKind, Node, NodeKind};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ComputedStyle {
     background_color: Option<Color>,
+    background_color_specified: bool,
     color: Option<Color>,
     display: Option<DisplayType>,
     font_size: Option<FontSize>,
+    font_weight: Option<FontWeight>,
+    font_style: Option<FontStyle>,
+    font_family: Option<FontFamily>,
+    white_space: Option<WhiteSpace>,
     text_decoration: Option<TextDecoration>,
+    text_align: Option<TextAlign>,
     height: Option<f64>,
     width: Option<f64>,
+    margin_top: Option<f64>,
+    margin_bottom: Option<f64>,
 }
 
 impl ComputedStyle {
     pub fn new() -> Self {
         Self {
             background_color: None,
+            background_color_specified: false,
             color: None,
             display: None,
             font_size: None,
+            font_weight: None,
+            font_style: None,
+            font_family: None,
+            white_space: None,
             text_decoration: None,
+            text_align: None,
             height: None,
             width: None,
+            margin_top: None,
+            margin_bottom: None,
         }
     }
 
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = Some(color);
+        self.background_color_specified = true;
+    }
+
+    /// Returns true if background-color was set on this element itself, not inherited or
+    /// defaulted.
+    pub fn is_background_color_specified(&self) -> bool {
+        self.background_color_specified
     }
 
     pub fn background_color(&self) -> Color {
@@ -59,16 +83,65 @@ impl ComputedStyle {
             .expect("failed to access CSS property: display")
     }
 
+    pub fn set_font_size(&mut self, font_size: FontSize) {
+        self.font_size = Some(font_size);
+    }
+
     pub fn font_size(&self) -> FontSize {
         self.font_size
             .expect("failed to access CSS property: font_size")
     }
 
+    pub fn set_font_weight(&mut self, font_weight: FontWeight) {
+        self.font_weight = Some(font_weight);
+    }
+
+    pub fn font_weight(&self) -> FontWeight {
+        self.font_weight
+            .expect("failed to access CSS property: font_weight")
+    }
+
+    pub fn set_font_style(&mut self, font_style: FontStyle) {
+        self.font_style = Some(font_style);
+    }
+
+    pub fn font_style(&self) -> FontStyle {
+        self.font_style
+            .expect("failed to access CSS property: font_style")
+    }
+
+    pub fn set_font_family(&mut self, font_family: FontFamily) {
+        self.font_family = Some(font_family);
+    }
+
+    pub fn font_family(&self) -> FontFamily {
+        self.font_family
+            .expect("failed to access CSS property: font_family")
+    }
+
+    pub fn set_white_space(&mut self, white_space: WhiteSpace) {
+        self.white_space = Some(white_space);
+    }
+
+    pub fn white_space(&self) -> WhiteSpace {
+        self.white_space
+            .expect("failed to access CSS property: white_space")
+    }
+
     pub fn text_decoration(&self) -> TextDecoration {
         self.text_decoration
             .expect("failed to access CSS property: text_decoration")
     }
 
+    pub fn set_text_align(&mut self, text_align: TextAlign) {
+        self.text_align = Some(text_align);
+    }
+
+    pub fn text_align(&self) -> TextAlign {
+        self.text_align
+            .expect("failed to access CSS property: text_align")
+    }
+
     pub fn set_height(&mut self, height: f64) {
         self.height = Some(height);
     }
@@ -85,6 +158,22 @@ impl ComputedStyle {
         self.width.expect("failed to access CSS property: width")
     }
 
+    pub fn set_margin_top(&mut self, margin: f64) {
+        self.margin_top = Some(margin);
+    }
+
+    pub fn margin_top(&self) -> f64 {
+        self.margin_top.expect("failed to access CSS property: margin_top")
+    }
+
+    pub fn set_margin_bottom(&mut self, margin: f64) {
+        self.margin_bottom = Some(margin);
+    }
+
+    pub fn margin_bottom(&self) -> f64 {
+        self.margin_bottom.expect("failed to access CSS property: margin_bottom")
+    }
+
     pub fn defaulting(
         &mut self,
         node: &Rc<RefCell<Node>>,
@@ -100,9 +189,24 @@ impl ComputedStyle {
             if self.font_size.is_none() && parent_style.font_size() != FontSize::Medium {
                 self.font_size = Some(parent_style.font_size());
             }
+            if self.font_weight.is_none() && parent_style.font_weight() != FontWeight::Normal {
+                self.font_weight = Some(parent_style.font_weight());
+            }
+            if self.font_style.is_none() && parent_style.font_style() != FontStyle::Normal {
+                self.font_style = Some(parent_style.font_style());
+            }
+            if self.font_family.is_none() && parent_style.font_family() != FontFamily::SansSerif {
+                self.font_family = Some(parent_style.font_family());
+            }
+            if self.white_space.is_none() && parent_style.white_space() != WhiteSpace::Normal {
+                self.white_space = Some(parent_style.white_space());
+            }
             if self.text_decoration.is_none() && parent_style.text_decoration() != TextDecoration::None {
                 self.text_decoration = Some(parent_style.text_decoration());
             }
+            if self.text_align.is_none() && parent_style.text_align() != TextAlign::Left {
+                self.text_align = Some(parent_style.text_align());
+            }
         }
 
         if self.background_color.is_none() {
@@ -115,17 +219,40 @@ impl ComputedStyle {
             self.display = Some(DisplayType::default(node));
         }
         if self.font_size.is_none() {
-            self.font_size = Some(FontSize::default(node));
+            // 見出しなどの大きさは、ユーザーエージェントスタイルシートで指定される
+            self.font_size = Some(FontSize::Medium);
+        }
+        if self.font_weight.is_none() {
+            self.font_weight = Some(FontWeight::Normal);
+        }
+        if self.font_style.is_none() {
+            self.font_style = Some(FontStyle::Normal);
+        }
+        if self.font_family.is_none() {
+            self.font_family = Some(FontFamily::SansSerif);
+        }
+        if self.white_space.is_none() {
+            self.white_space = Some(WhiteSpace::Normal);
         }
         if self.text_decoration.is_none() {
             self.text_decoration = Some(TextDecoration::default(node));
         }
+        if self.text_align.is_none() {
+            self.text_align = Some(TextAlign::default(node));
+        }
         if self.height.is_none() {
             self.height = Some(0.0);
         }
         if self.width.is_none() {
             self.width = Some(0.0);
         }
+        // マージンは親から継承しない
+        if self.margin_top.is_none() {
+            self.margin_top = Some(0.0);
+        }
+        if self.margin_bottom.is_none() {
+            self.margin_bottom = Some(0.0);
+        }
     }
 }
 
@@ -230,22 +357,124 @@ impl Color {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
 pub enum FontSize {
+    XSmall,
+    Small,
     Medium,
+    Large,
     XLarge,
     XXLarge,
 }
 
-impl FontSize {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::H1 => FontSize::XXLarge,
-                ElementKind::H2 => FontSize::XLarge,
-                _ => FontSize::Medium,
-            }
-            _ => FontSize::Medium,
+impl FromStr for FontSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x-small" => Ok(Self::XSmall),
+            "small" => Ok(Self::Small),
+            "medium" => Ok(Self::Medium),
+            "large" => Ok(Self::Large),
+            "x-large" => Ok(Self::XLarge),
+            "xx-large" => Ok(Self::XXLarge),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-size {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css-fonts/#font-weight-prop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontWeight {
+    Normal,
+    Bold,
+}
+
+impl FromStr for FontWeight {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "bold" => Ok(Self::Bold),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-weight {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css-fonts/#font-style-prop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+}
+
+impl FromStr for FontStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            // 斜体のフォントはないので、oblique も italic として扱う
+            "italic" | "oblique" => Ok(Self::Italic),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-style {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// Only the generic families are supported.
+/// https://drafts.csswg.org/css-fonts/#font-family-prop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontFamily {
+    SansSerif,
+    Monospace,
+}
+
+impl FromStr for FontFamily {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            // serif のフォントはないので、sans-serif として扱う
+            "sans-serif" | "serif" => Ok(Self::SansSerif),
+            "monospace" => Ok(Self::Monospace),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-family {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css-text/#white-space-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WhiteSpace {
+    /// Collapses sequences of whitespace into one space and wraps lines.
+    Normal,
+    /// Keeps whitespace and breaks lines only at newlines.
+    Pre,
+}
+
+impl FromStr for WhiteSpace {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "pre" => Ok(Self::Pre),
+            _ => Err(Error::UnexpectedInput(format!(
+                "white-space {:?} is not supported yet",
+                s
+            ))),
         }
     }
 }
@@ -255,19 +484,23 @@ pub enum DisplayType {
     Block,
     Inline,
     DisplayNone,
+    /// https://www.w3.org/TR/css-tables-3/#table-structure
+    Table,
+    TableRow,
+    TableCell,
 }
 
 impl DisplayType {
     fn default(node: &Rc<RefCell<Node>>) -> Self {
         match &node.borrow().kind() {
             NodeKind::Document => DisplayType::Block,
-            NodeKind::Element(e) => {
-                if e.is_block_element() {
-                    DisplayType::Block
-                } else {
-                    DisplayType::Inline
-                }
-            }
+            NodeKind::Element(e) => match e.kind() {
+                ElementKind::Table => DisplayType::Table,
+                ElementKind::Tr => DisplayType::TableRow,
+                ElementKind::Td | ElementKind::Th => DisplayType::TableCell,
+                _ if e.is_block_element() => DisplayType::Block,
+                _ => DisplayType::Inline,
+            },
             NodeKind::Text(_) => DisplayType::Inline,
         }
     }
@@ -277,6 +510,9 @@ impl DisplayType {
             "block" => Ok(Self::Block),
             "inline" => Ok(Self::Inline),
             "none" => Ok(Self::DisplayNone),
+            "table" => Ok(Self::Table),
+            "table-row" => Ok(Self::TableRow),
+            "table-cell" => Ok(Self::TableCell),
             _ => Err(Error::UnexpectedInput(format!(
                 "display {:?} is not supported yet",
                 s
@@ -301,4 +537,38 @@ impl TextDecoration {
             _ => TextDecoration::None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// https://drafts.csswg.org/css-text/#text-align-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+}
+
+impl TextAlign {
+    fn default(node: &Rc<RefCell<Node>>) -> Self {
+        match &node.borrow().kind() {
+            NodeKind::Element(element) => match element.kind() {
+                ElementKind::Center => TextAlign::Center,
+                _ => TextAlign::Left,
+            },
+            _ => TextAlign::Left,
+        }
+    }
+}
+
+impl FromStr for TextAlign {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Self::Left),
+            "center" => Ok(Self::Center),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-align {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
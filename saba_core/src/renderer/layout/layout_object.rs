@@ -1,23 +1,35 @@
 use alloc::rc::{Rc, Weak};
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
-use crate::constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH, CONTENT_AREA_WIDTH, WINDOW_PADDING, WINDOW_WIDTH};
+use core::str::FromStr;
+use crate::constants::{
+    CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH, CONTENT_AREA_WIDTH, MAX_ZOOM, MIN_ZOOM,
+};
 use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::{ComponentValue, Declaration, Selector, StyleSheet};
-use crate::renderer::dom::node::{Node, NodeKind};
-use crate::renderer::layout::computed_style::{Color, ComputedStyle, DisplayType, FontSize};
+use crate::renderer::dom::node::{ElementKind, Node, NodeKind};
+use crate::renderer::layout::computed_style::{
+    Color, ComputedStyle, DisplayType, FontFamily, FontSize, FontStyle, FontWeight, TextAlign,
+    WhiteSpace,
+};
 
 pub fn create_layout_object(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
-    cssom: &StyleSheet
+    cssom: &StyleSheet,
+    zoom: i64,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     if let Some(n) = node {
-        let layout_object = Rc::new(RefCell::new(LayoutObject::new(n.clone(), parent_obj)));
+        let layout_object = Rc::new(RefCell::new(LayoutObject::new(n.clone(), parent_obj, zoom)));
         for rule in &cssom.rules {
-            if layout_object.borrow().is_node_selected(&rule.selector) {
+            let selected = rule
+                .selectors
+                .iter()
+                .any(|selector| layout_object.borrow().is_node_selected(selector));
+            if selected {
                 layout_object
                     .borrow_mut()
                     .cascading_style(rule.declarations.clone());
@@ -50,21 +62,147 @@ fn find_index_for_line_break(line: String, max_index: usize) -> usize {
     max_index
 }
 
-fn split_text(line: String, char_width: i64) -> Vec<String> {
+fn split_text(line: String, char_width: i64, max_width: i64) -> Vec<String> {
     let mut result: Vec<String> = Vec::new();
-    if line.len() as i64 * char_width > (WINDOW_WIDTH + WINDOW_PADDING) {
+    if line.len() as i64 * char_width > max_width {
         let s = line.split_at(find_index_for_line_break(
             line.clone(),
-            ((WINDOW_WIDTH + WINDOW_PADDING) / char_width) as usize,
+            (max_width / char_width) as usize,
         ));
         result.push(s.0.to_string());
-        result.extend(split_text(s.1.trim().to_string(), char_width));
+        result.extend(split_text(s.1.trim().to_string(), char_width, max_width));
     } else {
         result.push(line);
     }
     result
 }
 
+/// Width of a text input in characters.
+pub const INPUT_TEXT_LENGTH: usize = 16;
+
+/// Returns the text drawn for an `<input>` element, or None for other nodes and hidden inputs.
+fn input_label(node_kind: &NodeKind) -> Option<String> {
+    let element = match node_kind {
+        NodeKind::Element(e) if e.kind() == ElementKind::Input => e,
+        _ => return None,
+    };
+    let value = element.get_attribute("value").unwrap_or_default();
+    let input_type = element.get_attribute("type").unwrap_or("text".to_string());
+
+    match input_type.to_ascii_lowercase().as_str() {
+        "hidden" => None,
+        "submit" => {
+            let value = if value.is_empty() { "Submit".to_string() } else { value };
+            Some(format!("[{}]", value))
+        }
+        _ => Some(format!("[{:<width$}]", value, width = INPUT_TEXT_LENGTH)),
+    }
+}
+
+/// 標準の文字の大きさに対する倍率（パーセント）
+pub fn font_percentage(font_size: FontSize) -> i64 {
+    match font_size {
+        FontSize::XSmall => 60,
+        FontSize::Small => 80,
+        FontSize::Medium => 100,
+        FontSize::Large => 150,
+        FontSize::XLarge => 200,
+        FontSize::XXLarge => 300,
+    }
+}
+
+/// 実際に描画される文字の大きさの倍率（パーセント）。
+/// Wasabi のフォントは 100%、200%、300% の 3 段階しかないので、ズームした倍率に最も近いものを選ぶ。
+/// 標準より大きい文字が標準の文字と同じにならないように、ちょうど中間の 150% は大きい方にする。
+/// 標準より小さいフォントはないので、h5 や h6 は太字にすることで本文と区別する。
+/// 描画できない大きさで幅を計算すると文字が重なるため、レイアウトでもこの倍率を使う
+pub fn drawn_font_percentage(font_size: FontSize, zoom: i64) -> i64 {
+    match scale(font_percentage(font_size), zoom) {
+        ..=149 => 100,
+        150..=250 => 200,
+        _ => 300,
+    }
+}
+
+/// 長さにズーム倍率（パーセント）を掛ける
+pub fn scale(value: i64, zoom: i64) -> i64 {
+    value * zoom / 100
+}
+
+pub fn clamp_zoom(zoom: i64) -> i64 {
+    zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// 中央揃えのとき、幅 `line_width` の行を `container_width` の中に置くための x 方向のずれ
+pub fn center_offset(line_width: i64, container_width: i64) -> i64 {
+    if line_width >= container_width {
+        return 0;
+    }
+    (container_width - line_width) / 2
+}
+
+/// 隣接する上下のマージンは、大きい方の値に相殺される
+/// https://www.w3.org/TR/CSS2/box.html#collapsing-margins
+fn collapse_margins(margin_bottom: i64, margin_top: i64) -> i64 {
+    margin_bottom.max(margin_top)
+}
+
+fn is_selected(node: &Rc<RefCell<Node>>, selector: &Selector) -> bool {
+    if let Selector::DescendantSelector(selectors) = selector {
+        let (subject, ancestors) = match selectors.split_last() {
+            Some(s) => s,
+            None => return false,
+        };
+        if !is_selected(node, subject) {
+            return false;
+        }
+
+        // 残りのセレクタを右から順に、祖先をたどりながら照合する
+        let mut current = node.borrow().parent().upgrade();
+        for ancestor_selector in ancestors.iter().rev() {
+            loop {
+                let ancestor = match current {
+                    Some(n) => n,
+                    None => return false,
+                };
+                current = ancestor.borrow().parent().upgrade();
+                if is_selected(&ancestor, ancestor_selector) {
+                    break;
+                }
+            }
+        }
+        return true;
+    }
+
+    if let Selector::CompoundSelector(selectors) = selector {
+        return selectors.iter().all(|s| is_selected(node, s));
+    }
+
+    match &node.borrow().kind() {
+        NodeKind::Element(e) => match selector {
+            Selector::TypeSelector(type_name) => {
+                if e.kind().to_string() == *type_name {
+                    return true;
+                }
+                false
+            }
+            Selector::ClassSelector(class_name) => e.has_class(class_name),
+            Selector::IdSelector(id_name) => {
+                for attr in &e.attributes() {
+                    if attr.name() == "id" && attr.value() == *id_name {
+                        return true;
+                    }
+                }
+                false
+            }
+            Selector::DescendantSelector(_)
+            | Selector::CompoundSelector(_)
+            | Selector::UnknownSelector => false
+        }
+        _ => false,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LayoutObject {
     kind: LayoutObjectKind,
@@ -75,12 +213,16 @@ pub struct LayoutObject {
     style: ComputedStyle,
     point: LayoutPoint,
     size: LayoutSize,
+    zoom: i64,
+    /// テキストを折り返す、コンテンツエリアの幅
+    content_width: i64,
 }
 
 impl LayoutObject {
     pub fn new(
         node: Rc<RefCell<Node>>,
         parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
+        zoom: i64,
     ) -> LayoutObject {
         let parent = match parent_obj {
             Some(p) => Rc::downgrade(p),
@@ -96,58 +238,55 @@ impl LayoutObject {
             style: ComputedStyle::new(),
             point: LayoutPoint::new(0, 0),
             size: LayoutSize::new(0, 0),
+            zoom,
+            content_width: CONTENT_AREA_WIDTH,
         }
     }
 
-    pub fn is_node_selected(&self, selector: &Selector) -> bool {
-        match &self.node_kind() {
-            NodeKind::Element(e) => match selector {
-                Selector::TypeSelector(type_name) => {
-                    if e.kind().to_string() == *type_name {
-                        return true;
-                    }
-                    false
-                }
-                Selector::ClassSelector(class_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "class" && attr.value() == *class_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::IdSelector(id_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "id" && attr.value() == *id_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::UnknownSelector => false
+    /// Wraps text at `content_width` instead of the default width of the content area.
+    pub fn set_content_width(&mut self, content_width: i64) {
+        self.content_width = content_width;
+    }
+
+    /// ブロックの上マージン。インラインの要素の上下のマージンは配置に影響しないので 0 を返す
+    pub fn margin_top(&self) -> i64 {
+        match self.kind {
+            LayoutObjectKind::Block | LayoutObjectKind::Table => {
+                scale(self.style.margin_top() as i64, self.zoom)
             }
-            _ => false,
+            _ => 0,
         }
     }
 
+    /// ブロックの下マージン。インラインの要素の上下のマージンは配置に影響しないので 0 を返す
+    pub fn margin_bottom(&self) -> i64 {
+        match self.kind {
+            LayoutObjectKind::Block | LayoutObjectKind::Table => {
+                scale(self.style.margin_bottom() as i64, self.zoom)
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn is_node_selected(&self, selector: &Selector) -> bool {
+        is_selected(&self.node, selector)
+    }
+
     pub fn cascading_style(&mut self, declarations: Vec<Declaration>) {
         for declaration in declarations {
             match declaration.property.as_str() {
                 "background-color" => {
                     if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(&value) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
+                        // transparent などの解釈できない色は、指定されていないものとして扱う
+                        if let Ok(color) = Color::from_name(&value) {
+                            self.style.set_background_color(color);
+                        }
                         continue;
                     }
                     if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_name(&color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
+                        if let Ok(color) = Color::from_code(color_code) {
+                            self.style.set_background_color(color);
+                        }
                         continue;
                     }
                 }
@@ -157,18 +296,70 @@ impl LayoutObject {
                             Ok(color) => color,
                             Err(_) => Color::black(),
                         };
-                        self.style.set_background_color(color);
+                        self.style.set_color(color);
                         continue;
                     }
                     if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_name(&color_code) {
+                        let color = match Color::from_code(color_code) {
                             Ok(color) => color,
                             Err(_) => Color::black(),
                         };
-                        self.style.set_background_color(color);
+                        self.style.set_color(color);
                         continue;
                     }
                 }
+                "margin-top" => {
+                    if let ComponentValue::Number(value) = declaration.value {
+                        self.style.set_margin_top(value);
+                    }
+                }
+                "margin-bottom" => {
+                    if let ComponentValue::Number(value) = declaration.value {
+                        self.style.set_margin_bottom(value);
+                    }
+                }
+                "font-size" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(font_size) = FontSize::from_str(value) {
+                            self.style.set_font_size(font_size);
+                        }
+                    }
+                }
+                "font-weight" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(font_weight) = FontWeight::from_str(value) {
+                            self.style.set_font_weight(font_weight);
+                        }
+                    }
+                }
+                "font-style" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(font_style) = FontStyle::from_str(value) {
+                            self.style.set_font_style(font_style);
+                        }
+                    }
+                }
+                "font-family" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(font_family) = FontFamily::from_str(value) {
+                            self.style.set_font_family(font_family);
+                        }
+                    }
+                }
+                "white-space" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(white_space) = WhiteSpace::from_str(value) {
+                            self.style.set_white_space(white_space);
+                        }
+                    }
+                }
+                "text-align" => {
+                    if let ComponentValue::Ident(value) = &declaration.value {
+                        if let Ok(text_align) = TextAlign::from_str(value) {
+                            self.style.set_text_align(text_align);
+                        }
+                    }
+                }
                 "display" => {
                     if let ComponentValue::Ident(value) = declaration.value {
                         let display_type = match DisplayType::from_str(&value) {
@@ -198,6 +389,9 @@ impl LayoutObject {
                 match self.style.display() {
                     DisplayType::Block => self.kind = LayoutObjectKind::Block,
                     DisplayType::Inline => self.kind = LayoutObjectKind::Inline,
+                    DisplayType::Table => self.kind = LayoutObjectKind::Table,
+                    DisplayType::TableRow => self.kind = LayoutObjectKind::TableRow,
+                    DisplayType::TableCell => self.kind = LayoutObjectKind::TableCell,
                     DisplayType::DisplayNone => {
                         panic!("should not create a layout object for display:none")
                     }
@@ -217,24 +411,34 @@ impl LayoutObject {
                 let mut height = 0;
                 let mut child = self.first_child();
                 let mut previous_child_kind = LayoutObjectKind::Block;
+                // 直前の兄弟の下マージン。最初の子の上マージンはそのまま足す
+                let mut previous_margin_bottom = 0;
                 while child.is_some() {
                     let c = match child {
                         Some(c) => c,
                         None => panic!("first child should exist"),
                     };
 
-                    if previous_child_kind == LayoutObjectKind::Block
-                        || c.borrow().kind() == LayoutObjectKind::Block
-                    {
+                    if previous_child_kind.is_block_level() || c.borrow().kind().is_block_level() {
+                        height += collapse_margins(previous_margin_bottom, c.borrow().margin_top());
                         height += c.borrow().size.height;
+                        previous_margin_bottom = c.borrow().margin_bottom();
                     }
 
                     previous_child_kind = c.borrow().kind();
                     child = c.borrow().next_sibling();
                 }
-                size.set_height(height);
+                size.set_height(height + previous_margin_bottom);
             }
             LayoutObjectKind::Inline => {
+                if let Some(label) = input_label(&self.node_kind()) {
+                    let percentage = drawn_font_percentage(self.style.font_size(), self.zoom);
+                    size.set_width(scale(CHAR_WIDTH, percentage) * label.chars().count() as i64);
+                    size.set_height(scale(CHAR_HEIGHT_WITH_PADDING, percentage));
+                    self.size = size;
+                    return;
+                }
+
                 let mut width = 0;
                 let mut height = 0;
                 let mut child = self.first_child();
@@ -253,25 +457,47 @@ impl LayoutObject {
                 size.set_width(width);
                 size.set_height(height);
             }
+            LayoutObjectKind::Table => {
+                self.layout_table();
+                return;
+            }
+            LayoutObjectKind::TableRow | LayoutObjectKind::TableCell => {
+                // 行はセルを、セルは中身を横に並べる。幅は後で表全体を見て列ごとに揃える
+                let mut width = 0;
+                let mut height = 0;
+                let mut child = self.first_child();
+                while let Some(c) = child {
+                    width += c.borrow().size.width;
+                    height = height.max(c.borrow().size.height);
+                    child = c.borrow().next_sibling();
+                }
+
+                size.set_width(width);
+                size.set_height(height);
+            }
             LayoutObjectKind::Text => {
                 if let NodeKind::Text(t) = self.node_kind() {
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    let width = CHAR_WIDTH * ratio * t.len() as i64;
-                    if width > CONTENT_AREA_WIDTH {
-                        size.set_width(CONTENT_AREA_WIDTH);
-                        let line_num = if width.wrapping_rem(CONTENT_AREA_WIDTH) == 0 {
-                            width.wrapping_rem(CONTENT_AREA_WIDTH)
+                    let percentage = drawn_font_percentage(self.style.font_size(), self.zoom);
+                    let char_width = scale(CHAR_WIDTH, percentage);
+                    let line_height = scale(CHAR_HEIGHT_WITH_PADDING, percentage);
+                    let width = char_width * t.len() as i64;
+                    if self.style.white_space() == WhiteSpace::Pre {
+                        // 整形済みのテキストは折り返さない
+                        let lines = t.split('\n');
+                        let max_len = lines.clone().map(|l| l.chars().count()).max().unwrap_or(0);
+                        size.set_width(char_width * max_len as i64);
+                        size.set_height(line_height * lines.count() as i64);
+                    } else if width > self.content_width {
+                        size.set_width(self.content_width);
+                        let line_num = if width.wrapping_rem(self.content_width) == 0 {
+                            width.wrapping_rem(self.content_width)
                         } else {
-                            width.wrapping_rem(CONTENT_AREA_WIDTH) + 1
+                            width.wrapping_rem(self.content_width) + 1
                         };
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * line_num);
+                        size.set_height(line_height * line_num);
                     } else {
                         size.set_width(width);
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio);
+                        size.set_height(line_height);
                     }
                 }
             }
@@ -286,19 +512,35 @@ impl LayoutObject {
         previous_sibling_kind: LayoutObjectKind,
         previous_sibling_point: Option<LayoutPoint>,
         previous_sibling_size: Option<LayoutSize>,
+        previous_sibling_margin_bottom: i64,
     ) {
         let mut point = LayoutPoint::new(0, 0);
 
         match (self.kind(), previous_sibling_kind) {
-            (LayoutObjectKind::Block, _) | (_, LayoutObjectKind::Block) => {
+            (LayoutObjectKind::TableCell, LayoutObjectKind::TableCell) => {
+                // 同じ行のセルは、前のセルの右に並べる
                 if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
-                    point.set_y(pos.y + size.height);
+                    point.set_x(pos.x + size.width);
+                    point.set_y(pos.y);
                 } else {
+                    point.set_x(parent_point.x);
                     point.set_y(parent_point.y);
                 }
+            }
+            (kind, previous_kind) if kind.is_block_level() || previous_kind.is_block_level() => {
+                if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
+                    let margin = collapse_margins(previous_sibling_margin_bottom, self.margin_top());
+                    point.set_y(pos.y + size.height + margin);
+                } else {
+                    point.set_y(parent_point.y + self.margin_top());
+                }
                 point.set_x(parent_point.x);
             }
-            (LayoutObjectKind::Inline, LayoutObjectKind::Inline) => {
+            // インライン要素と、その間にある単語の区切りなどのテキストは横に並べる
+            (
+                LayoutObjectKind::Inline | LayoutObjectKind::Text,
+                LayoutObjectKind::Inline | LayoutObjectKind::Text,
+            ) => {
                 if let(Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
                     point.set_x(pos.x + size.width);
                     point.set_y(pos.y);
@@ -322,8 +564,16 @@ impl LayoutObject {
         }
 
         match self.kind {
-            LayoutObjectKind::Block => {
+            LayoutObjectKind::Block
+            | LayoutObjectKind::Table
+            | LayoutObjectKind::TableRow
+            | LayoutObjectKind::TableCell => {
                 if let NodeKind::Element(_e) = self.node_kind() {
+                    // 背景色が指定されていないブロックは塗りつぶさない。親から継承した背景色は
+                    // 親のブロックですでに塗りつぶされている
+                    if !self.style.is_background_color_specified() {
+                        return vec![];
+                    }
                     return vec![DisplayItem::Rect {
                         style: self.style(),
                         layout_point: self.point(),
@@ -331,30 +581,50 @@ impl LayoutObject {
                     }]
                 }
             }
-            LayoutObjectKind::Inline => {}
+            LayoutObjectKind::Inline => {
+                if let Some(label) = input_label(&self.node_kind()) {
+                    return vec![DisplayItem::Text {
+                        text: label,
+                        style: self.style(),
+                        layout_point: self.point(),
+                    }]
+                }
+            }
             LayoutObjectKind::Text => {
                 if let NodeKind::Text(t) = self.node_kind() {
                     let mut v = vec![];
 
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
+                    let percentage = drawn_font_percentage(self.style.font_size(), self.zoom);
+                    let char_width = scale(CHAR_WIDTH, percentage);
+                    let lines = match self.style.white_space() {
+                        // 整形済みのテキストは空白を詰めずに、改行の位置でだけ行を分ける
+                        WhiteSpace::Pre => t.split('\n').map(String::from).collect(),
+                        WhiteSpace::Normal => {
+                            let plain_text = t.replace("\n", " ")
+                                .split(' ')
+                                .filter(|s| !s.is_empty())
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            split_text(plain_text, char_width, self.content_width)
+                        }
                     };
-                    let plain_text = t.replace("\n", " ")
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let lines = split_text(plain_text, CHAR_WIDTH * ratio);
                     let mut i = 0;
                     for line in lines {
+                        // ブロックはコンテンツエリアの幅いっぱいに広がるので、その中で中央に揃える
+                        let x = match self.style.text_align() {
+                            TextAlign::Left => self.point().x(),
+                            TextAlign::Center => {
+                                let line_width = char_width * line.chars().count() as i64;
+                                self.point().x()
+                                    + center_offset(line_width, self.content_width - self.point().x())
+                            }
+                        };
                         let item = DisplayItem::Text {
                             text: line,
                             style: self.style(),
                             layout_point: LayoutPoint::new(
-                                self.point().x(),
-                                self.point().y() + CHAR_HEIGHT_WITH_PADDING * i,
+                                x,
+                                self.point().y() + scale(CHAR_HEIGHT_WITH_PADDING, percentage) * i,
                             )
                         };
                         v.push(item);
@@ -368,6 +638,73 @@ impl LayoutObject {
 
         vec![]
     }
+
+    /// Returns the index in the text of the node where a caret goes when `position` is clicked,
+    /// or None if this isn't a text. The index counts chars, including the whitespace that isn't
+    /// drawn.
+    pub fn caret_index_at(&mut self, position: (i64, i64)) -> Option<usize> {
+        let text = match self.node_kind() {
+            NodeKind::Text(t) if self.kind == LayoutObjectKind::Text => t,
+            _ => return None,
+        };
+
+        let percentage = drawn_font_percentage(self.style.font_size(), self.zoom);
+        let char_width = scale(CHAR_WIDTH, percentage);
+        let line_height = scale(CHAR_HEIGHT_WITH_PADDING, percentage);
+        let lines = self
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, layout_point, .. } => Some((text, layout_point.x())),
+                _ => None,
+            })
+            .collect::<Vec<(String, i64)>>();
+        if lines.is_empty() {
+            return Some(0);
+        }
+
+        // クリックした行と、その行の中で一番近い文字の境目を探す
+        let row = ((position.1 - self.point().y()) / line_height).clamp(0, lines.len() as i64 - 1)
+            as usize;
+        let (line, x) = &lines[row];
+        let line_chars = line.chars().collect::<Vec<char>>();
+        let column = ((position.0 - x + char_width / 2) / char_width)
+            .clamp(0, line_chars.len() as i64) as usize;
+
+        if self.style.white_space() == WhiteSpace::Pre {
+            // 整形済みのテキストは改行の位置でだけ分けているので、前の行の文字数と改行を足す
+            let before = lines[..row]
+                .iter()
+                .map(|(line, _)| line.chars().count() + 1)
+                .sum::<usize>();
+            return Some(before + column);
+        }
+
+        // 空白は詰めて描画しているので、クリックした位置より前にある空白以外の文字を数え、
+        // 元のテキストで同じ数の文字の後ろを探す
+        let prefix = &line_chars[..column];
+        let visible = lines[..row]
+            .iter()
+            .map(|(line, _)| line.chars().filter(|c| !c.is_whitespace()).count())
+            .sum::<usize>()
+            + prefix.iter().filter(|c| !c.is_whitespace()).count();
+        // 折り返した行の先頭は、折り返した空白の後ろ
+        let after_space = prefix.last().map_or(row > 0, |c| c.is_whitespace());
+
+        let text_chars = text.chars().collect::<Vec<char>>();
+        let mut index = 0;
+        let mut count = 0;
+        while count < visible && index < text_chars.len() {
+            if !text_chars[index].is_whitespace() {
+                count += 1;
+            }
+            index += 1;
+        }
+        if after_space && index < text_chars.len() && text_chars[index].is_whitespace() {
+            index += 1;
+        }
+        Some(index)
+    }
 }
 
 impl LayoutObject {
@@ -375,6 +712,10 @@ impl LayoutObject {
         self.kind
     }
 
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
+    }
+
     pub fn node_kind(&self) -> NodeKind {
         self.node.borrow().kind().clone()
     }
@@ -412,6 +753,78 @@ impl LayoutObject {
     }
 }
 
+impl LayoutObject {
+    /// 表の行を上から順に返す。<tbody> などの行グループの中の行も含める
+    fn table_rows(&self) -> Vec<Rc<RefCell<LayoutObject>>> {
+        let mut rows = Vec::new();
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            match c.borrow().kind() {
+                LayoutObjectKind::TableRow => rows.push(c.clone()),
+                LayoutObjectKind::Block => {
+                    let mut grandchild = c.borrow().first_child();
+                    while let Some(g) = grandchild {
+                        if g.borrow().kind() == LayoutObjectKind::TableRow {
+                            rows.push(g.clone());
+                        }
+                        grandchild = g.borrow().next_sibling();
+                    }
+                }
+                _ => {}
+            }
+            child = c.borrow().next_sibling();
+        }
+        rows
+    }
+
+    /// 各列の幅を、その列で一番幅の広いセルに揃える。罫線と colspan にはまだ対応していない
+    /// https://www.w3.org/TR/CSS2/tables.html#auto-table-layout
+    fn layout_table(&mut self) {
+        let rows = self.table_rows();
+
+        let mut column_widths: Vec<i64> = Vec::new();
+        for row in &rows {
+            let mut cell = row.borrow().first_child();
+            let mut i = 0;
+            while let Some(c) = cell {
+                if i < column_widths.len() {
+                    column_widths[i] = column_widths[i].max(c.borrow().size.width);
+                } else {
+                    column_widths.push(c.borrow().size.width);
+                }
+                i += 1;
+                cell = c.borrow().next_sibling();
+            }
+        }
+        let table_width = column_widths.iter().sum();
+
+        let mut table_height = 0;
+        for row in &rows {
+            let row_height = row.borrow().size.height;
+            let mut cell = row.borrow().first_child();
+            let mut i = 0;
+            while let Some(c) = cell {
+                c.borrow_mut().size = LayoutSize::new(column_widths[i], row_height);
+                i += 1;
+                cell = c.borrow().next_sibling();
+            }
+            row.borrow_mut().size.set_width(table_width);
+            table_height += row_height;
+        }
+
+        // 行グループは表と同じ幅にする
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            if c.borrow().kind() == LayoutObjectKind::Block {
+                c.borrow_mut().size.set_width(table_width);
+            }
+            child = c.borrow().next_sibling();
+        }
+
+        self.size = LayoutSize::new(table_width, table_height);
+    }
+}
+
 impl PartialEq for LayoutObject {
     fn eq(&self, other: &LayoutObject) -> bool {
         self.kind == other.kind
@@ -423,6 +836,19 @@ pub enum LayoutObjectKind {
     Block,
     Inline,
     Text,
+    Table,
+    TableRow,
+    TableCell,
+}
+
+impl LayoutObjectKind {
+    /// 縦に積まれる（前後で改行される）ボックスかどうか
+    pub fn is_block_level(&self) -> bool {
+        matches!(
+            self,
+            LayoutObjectKind::Block | LayoutObjectKind::Table | LayoutObjectKind::TableRow
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
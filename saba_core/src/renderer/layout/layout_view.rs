@@ -4,7 +4,7 @@ use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
-use crate::constants::CONTENT_AREA_WIDTH;
+use crate::constants::{CONTENT_AREA_WIDTH, DEFAULT_ZOOM};
 use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::StyleSheet;
 use crate::renderer::dom::api::get_target_element_node;
@@ -17,12 +17,27 @@ pub struct LayoutView {
 
 impl LayoutView {
     pub fn new(root: Rc<RefCell<Node>>, cssom: &StyleSheet) -> Self {
+        Self::with_zoom(root, cssom, DEFAULT_ZOOM)
+    }
+
+    /// 文字の大きさやマージンを `zoom` パーセントに拡大・縮小してレイアウトする
+    pub fn with_zoom(root: Rc<RefCell<Node>>, cssom: &StyleSheet, zoom: i64) -> Self {
+        Self::with_content_width(root, cssom, zoom, CONTENT_AREA_WIDTH)
+    }
+
+    /// Lays out like `with_zoom` into a content area `content_width` wide.
+    pub fn with_content_width(
+        root: Rc<RefCell<Node>>,
+        cssom: &StyleSheet,
+        zoom: i64,
+        content_width: i64,
+    ) -> Self {
         let body_root = get_target_element_node(Some(root), ElementKind::Body);
 
         let mut tree = Self {
-            root: build_layout_tree(&body_root, &None, cssom),
+            root: build_layout_tree(&body_root, &None, cssom, zoom),
         };
-        tree.update_layout();
+        tree.update_layout(content_width);
         tree
     }
 
@@ -32,8 +47,8 @@ impl LayoutView {
 }
 
 impl LayoutView {
-    fn update_layout(&mut self) {
-        Self::calculate_node_size(&self.root, LayoutSize::new(CONTENT_AREA_WIDTH, 0));
+    fn update_layout(&mut self, content_width: i64) {
+        Self::calculate_node_size(&self.root, LayoutSize::new(content_width, 0), content_width);
 
         Self::calculate_node_position(
             &self.root,
@@ -41,20 +56,26 @@ impl LayoutView {
             LayoutObjectKind::Block,
             None,
             None,
+            0,
         );
     }
 
-    fn calculate_node_size(node: &Option<Rc<RefCell<LayoutObject>>>, parent_size: LayoutSize) {
+    fn calculate_node_size(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        parent_size: LayoutSize,
+        content_width: i64,
+    ) {
         if let Some(n) = node {
+            n.borrow_mut().set_content_width(content_width);
             if n.borrow().kind() == LayoutObjectKind::Block {
                 n.borrow_mut().compute_size(parent_size);
             }
 
             let first_child = n.borrow().first_child();
-            Self::calculate_node_size(&first_child, n.borrow().size());
+            Self::calculate_node_size(&first_child, n.borrow().size(), content_width);
 
             let next_sibling = n.borrow().next_sibling();
-            Self::calculate_node_size(&next_sibling, parent_size);
+            Self::calculate_node_size(&next_sibling, parent_size, content_width);
 
             n.borrow_mut().compute_size(parent_size);
         }
@@ -66,6 +87,7 @@ impl LayoutView {
         previous_sibling_kind: LayoutObjectKind,
         previous_sibling_point: Option<LayoutPoint>,
         previous_sibling_size: Option<LayoutSize>,
+        previous_sibling_margin_bottom: i64,
     ) {
         if let Some(n) = node {
             n.borrow_mut().compute_position(
@@ -73,6 +95,7 @@ impl LayoutView {
                 previous_sibling_kind,
                 previous_sibling_point,
                 previous_sibling_size,
+                previous_sibling_margin_bottom,
             );
 
             let first_child = n.borrow().first_child();
@@ -82,6 +105,7 @@ impl LayoutView {
                 LayoutObjectKind::Block,
                 None,
                 None,
+                0,
             );
 
             let next_sibling = n.borrow().next_sibling();
@@ -91,6 +115,7 @@ impl LayoutView {
                 n.borrow().kind(),
                 Some(n.borrow().point()),
                 Some(n.borrow().size()),
+                n.borrow().margin_bottom(),
             );
         }
     }
@@ -157,18 +182,21 @@ impl LayoutView {
     }
 }
 
+/// `node` とその兄弟から、レイアウトツリーを作る。
+/// 兄弟は同じ親を持つので、`text-align` などの継承するスタイルを受け取れるよう `parent_obj` をそのまま渡す
 fn build_layout_tree(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
     cssom: &StyleSheet,
+    zoom: i64,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     let mut target_node = node.clone();
-    let mut layout_object = create_layout_object(node, parent_obj, cssom);
+    let mut layout_object = create_layout_object(node, parent_obj, cssom, zoom);
 
     while layout_object.is_none() {
         if let Some(n) = target_node {
             target_node = n.borrow().next_sibling().clone();
-            layout_object = create_layout_object(&target_node, parent_obj, cssom);
+            layout_object = create_layout_object(&target_node, parent_obj, cssom, zoom);
         } else {
             return layout_object;
         }
@@ -177,8 +205,8 @@ fn build_layout_tree(
     if let Some(n) = target_node {
         let original_first_child = n.borrow().first_child();
         let original_next_sibling = n.borrow().next_sibling();
-        let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom);
-        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom);
+        let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom, zoom);
+        let mut next_sibling = build_layout_tree(&original_next_sibling, parent_obj, cssom, zoom);
 
         if first_child.is_none() && original_first_child.is_some() {
             let mut original_dome_node = original_first_child
@@ -187,7 +215,7 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                first_child = build_layout_tree(&original_dome_node, &layout_object, cssom);
+                first_child = build_layout_tree(&original_dome_node, &layout_object, cssom, zoom);
 
                 if first_child.is_none() && original_dome_node.is_some() {
                     original_dome_node = original_dome_node
@@ -208,7 +236,7 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                next_sibling = build_layout_tree(&original_dome_node, &None, cssom);
+                next_sibling = build_layout_tree(&original_dome_node, parent_obj, cssom, zoom);
 
                 if next_sibling.is_none() && original_dome_node.is_some() {
                     original_dome_node = original_dome_node
@@ -236,15 +264,21 @@ fn build_layout_tree(
 #[cfg(test)]
 mod tests {
     use alloc::string::{String, ToString};
+    use alloc::vec;
     use alloc::vec::Vec;
     use crate::renderer::css::cssom::CssParser;
     use crate::renderer::css::token::CssTokenizer;
     use crate::renderer::dom::api::get_style_content;
+    use crate::display_item::DisplayItem;
+    use crate::renderer::layout::computed_style::Color;
     use crate::renderer::dom::node::ElementKind::Body;
     use crate::renderer::dom::node::{Element, NodeKind};
     use crate::renderer::html::parser::HtmlParser;
     use crate::renderer::html::token::HtmlTokenizer;
-    use crate::renderer::layout::layout_object::LayoutObjectKind;
+    use alloc::format;
+    use crate::constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH, CONTENT_AREA_WIDTH};
+    use crate::renderer::css::cssom::user_agent_style_sheet;
+    use crate::renderer::layout::layout_object::{center_offset, LayoutObjectKind};
     use crate::renderer::layout::layout_view::LayoutView;
 
     fn create_layout_view(html: String) -> LayoutView {
@@ -356,4 +390,366 @@ mod tests {
         assert!(p.clone().expect("p node should exist").borrow().first_child().is_none());
         assert!(p.clone().expect("p node should exist").borrow().next_sibling().is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_selector_list() {
+        let html = r#"<html>
+        <head>
+        <style>
+        h1, h2, .title { display: none; }
+        </style>
+        </head>
+        <body>
+        <h1>h1</h1>
+        <h2>h2</h2>
+        <p class="title">title</p>
+        <p>text</p>
+        </body>
+        </html>"#.to_string();
+        let layout_view = create_layout_view(html);
+
+        let root = layout_view.root.expect("root should exist");
+        let p = root.borrow().first_child().expect("p node should exist");
+        assert_eq!(
+            NodeKind::Element(Element::new("p", Vec::new())),
+            p.borrow().node_kind()
+        );
+        assert!(p.borrow().next_sibling().is_none());
+        let text = p.borrow().first_child().expect("text should exist");
+        assert_eq!(NodeKind::Text("text".to_string()), text.borrow().node_kind());
+    }
+
+    #[test]
+    fn test_descendant_selector() {
+        let html = r#"<html>
+        <head>
+        <style>
+        div p { display: none; }
+        </style>
+        </head>
+        <body>
+        <div><form><p>nested</p></form></div>
+        <p>outside</p>
+        </body>
+        </html>"#.to_string();
+        let layout_view = create_layout_view(html);
+
+        let root = layout_view.root.expect("root should exist");
+        let div = root.borrow().first_child().expect("div node should exist");
+        let form = div.borrow().first_child().expect("form node should exist");
+        assert!(form.borrow().first_child().is_none());
+
+        let p = div.borrow().next_sibling().expect("p node should exist");
+        assert_eq!(
+            NodeKind::Element(Element::new("p", Vec::new())),
+            p.borrow().node_kind()
+        );
+        let text = p.borrow().first_child().expect("text should exist");
+        assert!(matches!(text.borrow().node_kind(), NodeKind::Text(s) if s == "outside"));
+    }
+
+    #[test]
+    fn test_compound_selector() {
+        let html = r#"<html>
+        <head>
+        <style>
+        p.note { display: none; }
+        </style>
+        </head>
+        <body>
+        <p class="note">hidden</p>
+        <p><a class="note">shown</a></p>
+        </body>
+        </html>"#.to_string();
+        let layout_view = create_layout_view(html);
+
+        let root = layout_view.root.expect("root should exist");
+        let p = root.borrow().first_child().expect("p node should exist");
+        assert!(p.borrow().next_sibling().is_none());
+        let a = p.borrow().first_child().expect("a node should exist");
+        let text = a.borrow().first_child().expect("text should exist");
+        assert!(matches!(text.borrow().node_kind(), NodeKind::Text(s) if s == "shown"));
+    }
+
+    #[test]
+    fn test_collapse_margins() {
+        let html = r#"<html>
+        <head>
+        <style>
+        p { margin-top: 10px; margin-bottom: 20px; }
+        .second { margin: 15px 0; }
+        </style>
+        </head>
+        <body><p>a</p><p class="second">b</p></body>
+        </html>"#.to_string();
+        let layout_view = create_layout_view(html);
+
+        let root = layout_view.root.expect("root should exist");
+        let first = root.borrow().first_child().expect("first p should exist");
+        let second = first.borrow().next_sibling().expect("second p should exist");
+        assert_eq!(10, first.borrow().point().y());
+
+        // 下マージン 20px と上マージン 15px は、足し合わせずに大きい方に相殺される
+        let first_bottom = first.borrow().point().y() + first.borrow().size().height();
+        assert_eq!(first_bottom + 20, second.borrow().point().y());
+
+        // 最初の子の上マージンと最後の子の下マージンは、親の高さに含まれる
+        let second_bottom = second.borrow().point().y() + second.borrow().size().height();
+        assert_eq!(second_bottom + 15, root.borrow().size().height());
+    }
+
+    #[test]
+    fn test_paint_background_color() {
+        let html = r#"<html>
+        <head>
+        <style>
+        .box { background-color: #ff0000; }
+        .clear { background-color: transparent; }
+        </style>
+        </head>
+        <body><div class="box">a</div><div class="clear">b</div></body>
+        </html>"#.to_string();
+        let layout_view = create_layout_view(html);
+        let items = layout_view.paint();
+
+        let root = layout_view.root.expect("root should exist");
+        let div = root.borrow().first_child().expect("div should exist");
+
+        // 背景の四角が、テキストよりも先に描画される
+        assert_eq!(3, items.len());
+        match &items[0] {
+            DisplayItem::Rect { style, layout_point, layout_size } => {
+                assert_eq!(Color::from_name("red").expect("failed to parse color"), style.background_color());
+                assert_eq!(div.borrow().point(), *layout_point);
+                assert_eq!(div.borrow().size(), *layout_size);
+            }
+            item => panic!("unexpected display item {:?}", item),
+        }
+        assert!(matches!(&items[1], DisplayItem::Text { text, .. } if text == "a"));
+        assert!(matches!(&items[2], DisplayItem::Text { text, .. } if text == "b"));
+    }
+
+    #[test]
+    fn test_paint_explicit_white_background() {
+        let html = r#"<html>
+        <head>
+        <style>
+        .box { background-color: red; }
+        .white { background-color: white; }
+        </style>
+        </head>
+        <body><div class="box"><p class="white">a</p><p>b</p></div></body>
+        </html>"#.to_string();
+        let layout_view = create_layout_view(html);
+        let items = layout_view.paint();
+
+        // 明示的に指定された白は塗りつぶし、親から継承した赤は塗りつぶさない
+        let backgrounds: Vec<Color> = items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect { style, .. } => Some(style.background_color()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                Color::from_name("red").expect("failed to parse color"),
+                Color::white(),
+            ],
+            backgrounds
+        );
+    }
+
+    #[test]
+    fn test_center_offset() {
+        assert_eq!(40, center_offset(20, 100));
+        assert_eq!(0, center_offset(100, 100));
+        // コンテナより長い行は左端から描画する
+        assert_eq!(0, center_offset(120, 100));
+    }
+
+    #[test]
+    fn test_text_align_center() {
+        let html = r#"<html>
+        <head><style>.c { text-align: center; }</style></head>
+        <body><center><p>abcd</p><p>ab</p></center><p class="c">abc</p><p>left</p></body>
+        </html>"#.to_string();
+        let layout_view = create_layout_view(html);
+        let items = layout_view.paint();
+
+        let xs = items
+            .iter()
+            .map(|item| match item {
+                DisplayItem::Text { layout_point, .. } => layout_point.x(),
+                item => panic!("unexpected display item {:?}", item),
+            })
+            .collect::<Vec<i64>>();
+        assert_eq!(
+            vec![
+                (CONTENT_AREA_WIDTH - CHAR_WIDTH * 4) / 2,
+                (CONTENT_AREA_WIDTH - CHAR_WIDTH * 2) / 2,
+                (CONTENT_AREA_WIDTH - CHAR_WIDTH * 3) / 2,
+                0,
+            ],
+            xs
+        );
+    }
+
+    fn create_zoomed_layout_view(html: String, zoom: i64) -> LayoutView {
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let dom = window.borrow().document();
+        LayoutView::with_zoom(dom, &user_agent_style_sheet(), zoom)
+    }
+
+    #[test]
+    fn test_zoom_font_size() {
+        let html = "<html><body><h1>ab</h1></body></html>".to_string();
+        for (zoom, expected_width, expected_height) in [
+            (100, CHAR_WIDTH * 3 * 2, CHAR_HEIGHT_WITH_PADDING * 3),
+            // 600% の文字は描画できないので、最も大きい 300% で配置する
+            (200, CHAR_WIDTH * 3 * 2, CHAR_HEIGHT_WITH_PADDING * 3),
+            // 150% の文字は描画できないので、描画される 200% の大きさで配置する
+            (50, CHAR_WIDTH * 2 * 2, CHAR_HEIGHT_WITH_PADDING * 2),
+        ] {
+            let layout_view = create_zoomed_layout_view(html.clone(), zoom);
+            let h1 = layout_view.root.expect("root should exist").borrow().first_child();
+            let text = h1.expect("h1 should exist").borrow().first_child();
+            let size = text.expect("text should exist").borrow().size();
+            assert_eq!(expected_width, size.width());
+            assert_eq!(expected_height, size.height());
+        }
+    }
+
+    #[test]
+    fn test_small_heading_size() {
+        // h5 と h6 の 80% と 60% の文字は描画できないので、100% の文字として配置する
+        let html = "<html><body><h5>abc</h5><h6>abcd</h6></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let h5 = layout_view.root.expect("root should exist").borrow().first_child();
+        let h5 = h5.expect("h5 should exist");
+        let h6 = h5.borrow().next_sibling().expect("h6 should exist");
+        for (heading, len) in [(h5, 3), (h6, 4)] {
+            let text = heading.borrow().first_child().expect("text should exist");
+            assert_eq!(CHAR_WIDTH * len, text.borrow().size().width());
+            assert_eq!(CHAR_HEIGHT_WITH_PADDING, text.borrow().size().height());
+        }
+    }
+
+    #[test]
+    fn test_zoom_wrap_width() {
+        // 7 文字の単語を 10 個並べた 79 文字のテキスト
+        let words = ["abcdefg"; 10].join(" ");
+        let html = format!("<html><body><p>{}</p></body></html>", words);
+        // 50% の文字は描画できないので、100% と同じ位置で折り返す
+        for (zoom, char_width, expected_lines) in
+            [(100, CHAR_WIDTH, 2), (200, CHAR_WIDTH * 2, 3), (50, CHAR_WIDTH, 2)]
+        {
+            let layout_view = create_zoomed_layout_view(html.clone(), zoom);
+            let items = layout_view.paint();
+            assert_eq!(expected_lines, items.len());
+            for item in items {
+                match item {
+                    DisplayItem::Text { text, .. } => {
+                        assert!(char_width * text.len() as i64 <= CONTENT_AREA_WIDTH);
+                    }
+                    item => panic!("unexpected display item {:?}", item),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_table_layout() {
+        // </td> と </tr> を省略し、<tbody> も書かない 2x2 の表
+        let html = "<html><body><table><tr><td>abc<td>d<tr><td>e<td>fghij</table></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root.expect("root should exist");
+        let table = root.borrow().first_child().expect("table should exist");
+        assert_eq!(LayoutObjectKind::Table, table.borrow().kind());
+        let tbody = table.borrow().first_child().expect("tbody should exist");
+
+        let mut cells = Vec::new();
+        let mut row = tbody.borrow().first_child();
+        while let Some(r) = row {
+            assert_eq!(LayoutObjectKind::TableRow, r.borrow().kind());
+            let mut cell = r.borrow().first_child();
+            while let Some(c) = cell {
+                assert_eq!(LayoutObjectKind::TableCell, c.borrow().kind());
+                cells.push((c.borrow().point(), c.borrow().size()));
+                cell = c.borrow().next_sibling();
+            }
+            row = r.borrow().next_sibling();
+        }
+        assert_eq!(4, cells.len());
+
+        // 列の幅は、その列で一番広いセルの幅になる
+        let first_column_width = CHAR_WIDTH * 3;
+        let second_column_width = CHAR_WIDTH * 5;
+        let table_point = table.borrow().point();
+        let (x0, x1) = (table_point.x(), table_point.x() + first_column_width);
+        let (y0, y1) = (table_point.y(), table_point.y() + CHAR_HEIGHT_WITH_PADDING);
+        for (i, (x, y, width)) in [
+            (x0, y0, first_column_width),
+            (x1, y0, second_column_width),
+            (x0, y1, first_column_width),
+            (x1, y1, second_column_width),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let (point, size) = cells[i];
+            assert_eq!((x, y), (point.x(), point.y()), "cell {}", i);
+            assert_eq!(width, size.width(), "cell {}", i);
+            assert_eq!(CHAR_HEIGHT_WITH_PADDING, size.height(), "cell {}", i);
+        }
+
+        let table_size = table.borrow().size();
+        assert_eq!(first_column_width + second_column_width, table_size.width());
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING * 2, table_size.height());
+    }
+
+    #[test]
+    fn test_caret_index_at() {
+        // 描画では空白が 1 つに詰められる
+        let layout_view = create_layout_view("<html><body>ab  cd</body></html>".to_string());
+        let text = layout_view
+            .root
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("text should exist");
+        let expected = [
+            ((0, 0), 0),
+            ((CHAR_WIDTH + 1, 1), 1),
+            ((CHAR_WIDTH * 3, 1), 3),
+            ((CHAR_WIDTH * 4, 1), 5),
+            ((CONTENT_AREA_WIDTH, 1), 6),
+        ];
+        for (position, index) in expected {
+            assert_eq!(Some(index), text.borrow_mut().caret_index_at(position), "{:?}", position);
+        }
+
+        // 折り返した行の先頭をクリックすると、その行の最初の文字の前に置かれる
+        let words = ["abcdefg"; 10].join(" ");
+        let html = format!("<html><body>{}</body></html>", words);
+        let layout_view = create_layout_view(html);
+        let first_line = match &layout_view.paint()[0] {
+            DisplayItem::Text { text, .. } => text.clone(),
+            item => panic!("unexpected display item {:?}", item),
+        };
+        let text = layout_view
+            .root
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("text should exist");
+        assert_eq!(
+            Some(first_line.len() + 1),
+            text.borrow_mut()
+                .caret_index_at((0, CHAR_HEIGHT_WITH_PADDING + 1))
+        );
+    }
+}
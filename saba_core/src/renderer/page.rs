@@ -1,18 +1,33 @@
-use crate::http::HttpResponse;
+use crate::event_log::{EventLog, LogEvent};
+use crate::http::{HttpResponse, NavigationResult};
 use crate::renderer::html::parser::HtmlParser;
 use crate::renderer::html::token::HtmlTokenizer;
 use crate::browser::Browser;
-use crate::renderer::dom::node::{ElementKind, NodeKind, Window};
+use crate::renderer::dom::node::{ElementKind, Node, NodeKind, Window};
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 use crate::display_item::DisplayItem;
-use crate::renderer::css::cssom::{CssParser, StyleSheet};
-use crate::renderer::css::token::CssTokenizer;
-use crate::renderer::dom::api::get_style_content;
+use crate::renderer::css::cssom::{
+    parse_stylesheet, scripting_style_sheet, user_agent_style_sheet, StyleSheet,
+};
+use crate::error::Error;
+use crate::renderer::dom::api::{
+    delete_char_before, get_ancestor_element_node, get_form_fields, get_links, get_meta_refresh,
+    get_title_content, insert_char_at, Link,
+};
+use crate::renderer::dom::node::NodeIterator;
+use crate::url::{build_query, Url};
+use alloc::format;
+use alloc::string::ToString;
+use crate::renderer::layout::layout_object::clamp_zoom;
 use crate::renderer::layout::layout_view::LayoutView;
+use crate::constants::{CONTENT_AREA_WIDTH, DEFAULT_ZOOM};
+use crate::renderer::js::ast::JsParser;
+use crate::renderer::js::runtime::JsRuntime;
+use crate::renderer::js::token::JsLexer;
 
 #[derive(Debug, Clone)]
 pub struct Page {
@@ -21,6 +36,19 @@ pub struct Page {
     style: Option<StyleSheet>,
     layout_view: Option<LayoutView>,
     display_items: Vec<DisplayItem>,
+    load_state: LoadState,
+    url: Option<Url>,
+    zoom: i64,
+    /// テキストを折り返す、コンテンツエリアの幅
+    content_width: i64,
+    event_log: Rc<RefCell<EventLog>>,
+    /// スクリプトが DOM を変更し、レイアウトをやり直す必要がある
+    dirty: bool,
+    /// 直接編集しているテキストノードと、その中の caret の位置（文字単位）
+    focused_text: Option<(Rc<RefCell<Node>>, usize)>,
+    javascript_enabled: bool,
+    /// ページのスクリプトで共有するグローバルスコープ。別のページを読み込むとリセットする
+    runtime: JsRuntime,
 }
 
 impl Default for Page {
@@ -37,6 +65,15 @@ impl Page {
             style: None,
             layout_view: None,
             display_items: Vec::new(),
+            load_state: LoadState::Idle,
+            url: None,
+            zoom: DEFAULT_ZOOM,
+            content_width: CONTENT_AREA_WIDTH,
+            event_log: Rc::new(RefCell::new(EventLog::default())),
+            dirty: false,
+            focused_text: None,
+            javascript_enabled: false,
+            runtime: JsRuntime::new(),
         }
     }
 
@@ -44,6 +81,54 @@ impl Page {
         self.browser = browser;
     }
 
+    pub fn is_javascript_enabled(&self) -> bool {
+        self.javascript_enabled
+    }
+
+    /// Sets whether scripts run in the page. It also decides whether `<noscript>` is rendered,
+    /// from the next load. Scripts are disabled until this is called.
+    pub fn set_javascript_enabled(&mut self, enabled: bool) {
+        self.javascript_enabled = enabled;
+    }
+
+    /// Sets the log that the page records its loads in. The browser shares one log among pages.
+    pub fn set_event_log(&mut self, event_log: Rc<RefCell<EventLog>>) {
+        self.event_log = event_log;
+    }
+
+    /// Marks the start of a navigation to `destination`, before the response arrives.
+    pub fn begin_navigation(&mut self, destination: &str) {
+        self.event_log
+            .borrow_mut()
+            .push(LogEvent::NavigationStarted(destination.to_string()));
+        self.load_state = LoadState::Loading {
+            bytes_received: 0,
+            content_length: None,
+        };
+    }
+
+    /// Loads the response of a navigation as the page of its final URL, fetching subresources
+    /// via `fetch`.
+    pub fn receive_navigation_result(
+        &mut self,
+        result: NavigationResult,
+        fetch: &mut dyn FnMut(&Url) -> Result<HttpResponse, Error>,
+    ) {
+        for (from, to) in result.redirects() {
+            self.event_log.borrow_mut().push(LogEvent::RedirectFollowed {
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+
+        // フォームの action などの相対 URL を解決するために、リダイレクト後のページの URL を覚えておく
+        self.url = Some(result.url());
+        self.receive_response_with_fetcher(result.response(), fetch);
+        self.event_log
+            .borrow_mut()
+            .push(LogEvent::LoadCompleted(result.url().to_string()));
+    }
+
     pub fn clicked(&self, position: (i64, i64)) -> Option<String> {
         let view = match &self.layout_view {
             Some(v) => v,
@@ -51,37 +136,244 @@ impl Page {
         };
 
         if let Some(n) = view.find_node_by_position(position) {
-            if let Some(parent) = n.borrow().parent().upgrade() {
-                if let NodeKind::Element(e) = parent.borrow().node_kind() {
-                    if e.kind() == ElementKind::A {
-                        return e.get_attribute("href");
-                    }
-                }
+            let node = n.borrow().node();
+            if let Some(url) = self.form_submission_url(node) {
+                return Some(url);
             }
         }
 
-        None
+        self.link_at(position)
+    }
+
+    /// Returns the URL of the link at `position` in the content area. This is used both for
+    /// clicks and for showing the hovered link.
+    pub fn link_at(&self, position: (i64, i64)) -> Option<String> {
+        let view = self.layout_view.as_ref()?;
+        let n = view.find_node_by_position(position)?;
+        let parent = n.borrow().parent().upgrade()?;
+        let href = match parent.borrow().node_kind() {
+            NodeKind::Element(e) if e.kind() == ElementKind::A => e.get_attribute("href")?,
+            _ => return None,
+        };
+        match self.resolve_url(&href) {
+            Some(url) => Some(url.to_string()),
+            None => Some(href),
+        }
+    }
+
+    /// Returns the URL to navigate to when `node` is a submit button of a GET form.
+    /// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-submission-algorithm
+    fn form_submission_url(&self, node: Rc<RefCell<Node>>) -> Option<String> {
+        let element = node.borrow().get_element()?;
+        if element.kind() != ElementKind::Input
+            || !element
+                .get_attribute("type")
+                .is_some_and(|t| t.eq_ignore_ascii_case("submit"))
+        {
+            return None;
+        }
+
+        let form = get_ancestor_element_node(node, ElementKind::Form)?;
+        let form_element = form.borrow().get_element()?;
+        let method = form_element.get_attribute("method").unwrap_or("get".to_string());
+        if !method.eq_ignore_ascii_case("get") {
+            return None;
+        }
+
+        let action = form_element.get_attribute("action").unwrap_or_default();
+        let action_url = self.resolve_url(&action)?;
+        let action_url = action_url.to_string();
+        let action_url = match action_url.split_once('?') {
+            Some((url, _query)) => url,
+            None => &action_url,
+        };
+
+        Some(format!("{}?{}", action_url, build_query(&get_form_fields(form))))
+    }
+
+    /// Returns the document of the loaded page, or None before any page is loaded.
+    pub fn document(&self) -> Option<Rc<RefCell<Node>>> {
+        self.frame.as_ref().map(|frame| frame.borrow().document())
+    }
+
+    pub fn url(&self) -> Option<Url> {
+        self.url.clone()
+    }
+
+    pub fn set_url(&mut self, url: Url) {
+        self.url = Some(url);
+    }
+
+    /// Returns the URL that relative URLs in the page are resolved against: the href of
+    /// `<base>` if there is one, otherwise the URL of the page.
+    /// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    pub fn base_url(&self) -> Option<Url> {
+        let base_href = self.frame.as_ref().and_then(|frame| frame.borrow().base_href());
+        match (base_href, &self.url) {
+            // base の href も相対 URL の場合がある
+            (Some(href), Some(url)) => url.join(&href).ok().or(Some(url.clone())),
+            (Some(href), None) => Url::new(href).parse().ok(),
+            (None, url) => url.clone(),
+        }
+    }
+
+    fn resolve_url(&self, url: &str) -> Option<Url> {
+        match self.base_url() {
+            Some(base) => base.join(url).ok(),
+            None => Url::new(url.to_string()).parse().ok(),
+        }
     }
 
     pub fn receive_response(&mut self, response: HttpResponse) {
-        self.create_frame(response.body());
+        self.receive_response_with_fetcher(response, &mut no_fetch);
+    }
+
+    /// Loads `html` as the page without any network access. External resources aren't loaded.
+    pub fn load_from_str(&mut self, html: &str) {
+        self.load_html(html.to_string(), &mut no_fetch);
+    }
+
+    /// Loads `response` as the page, fetching subresources such as external stylesheets via `fetch`.
+    /// A subresource that fails to load is skipped and does not abort rendering.
+    pub fn receive_response_with_fetcher(
+        &mut self,
+        response: HttpResponse,
+        fetch: &mut dyn FnMut(&Url) -> Result<HttpResponse, Error>,
+    ) {
+        self.load_html(response.body(), fetch);
+    }
+
+    fn load_html(
+        &mut self,
+        html: String,
+        fetch: &mut dyn FnMut(&Url) -> Result<HttpResponse, Error>,
+    ) {
+        self.focused_text = None;
+        // 前のページのスクリプトが作った変数を、次のページに残さない
+        self.runtime.reset();
+        self.create_frame(html, fetch);
+        self.run_scripts();
         self.set_layout_view();
         self.paint_tree();
+        // スクリプトによる DOM の変更は、いま作ったレイアウトに反映されている
+        self.dirty = false;
+        self.load_state = LoadState::Complete;
     }
 
-    fn create_frame(&mut self, html: String) {
+    fn create_frame(
+        &mut self,
+        html: String,
+        fetch: &mut dyn FnMut(&Url) -> Result<HttpResponse, Error>,
+    ) {
         let html_tokenizer = HtmlTokenizer::new(html);
-        let frame = HtmlParser::new(html_tokenizer).construct_tree();
+        let mut parser = HtmlParser::new(html_tokenizer).with_scripting(self.javascript_enabled);
+        let frame = parser.construct_tree();
+        self.event_log
+            .borrow_mut()
+            .push(LogEvent::ParseErrors(parser.parse_errors().len()));
         let dom = frame.borrow().document();
-
-        let style = get_style_content(dom);
-        let css_tokenizer = CssTokenizer::new(style);
-        let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
-
+        // スタイルシートの URL を <base> に対して解決できるように、先にフレームを設定する
         self.frame = Some(frame);
+
+        let cssom = self.collect_style_sheets(dom, fetch);
         self.style = Some(cssom);
     }
 
+    /// Runs the inline `<script>` elements in document order if JavaScript is enabled. They run
+    /// after the whole document is parsed, so every element is visible to them. External scripts
+    /// (`<script src>`) aren't loaded.
+    fn run_scripts(&mut self) {
+        if !self.javascript_enabled {
+            return;
+        }
+
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+
+        let sources: Vec<String> = NodeIterator::new(dom)
+            .filter_map(|node| {
+                let element = node.borrow().get_element()?;
+                if element.kind() != ElementKind::Script || element.get_attribute("src").is_some()
+                {
+                    return None;
+                }
+                let text = node.borrow().first_child()?;
+                let source = match text.borrow().kind() {
+                    NodeKind::Text(s) => Some(s),
+                    _ => None,
+                };
+                source
+            })
+            .collect();
+
+        for source in sources {
+            // 構文エラーやタイムアウトはイベントログに残して、次のスクリプトに進む
+            let _ = self.execute_script(&source);
+        }
+    }
+
+    /// <style> と <link rel="stylesheet"> を文書順に読み込み、1つの StyleSheet にまとめる
+    /// https://html.spec.whatwg.org/multipage/semantics.html#link-type-stylesheet
+    fn collect_style_sheets(
+        &self,
+        dom: Rc<RefCell<Node>>,
+        fetch: &mut dyn FnMut(&Url) -> Result<HttpResponse, Error>,
+    ) -> StyleSheet {
+        // 後にあるルールほど優先されるので、ユーザーエージェントスタイルシートを先頭に置く
+        let mut rules = user_agent_style_sheet().rules;
+        if self.javascript_enabled {
+            rules.extend(scripting_style_sheet().rules);
+        }
+
+        for node in NodeIterator::new(dom) {
+            let element = match node.borrow().get_element() {
+                Some(e) => e,
+                None => continue,
+            };
+
+            let css = match element.kind() {
+                ElementKind::Style => match node.borrow().first_child() {
+                    Some(text) => match text.borrow().kind() {
+                        NodeKind::Text(s) => s,
+                        _ => continue,
+                    },
+                    None => continue,
+                },
+                ElementKind::Link => {
+                    let is_stylesheet = element.get_attribute("rel").is_some_and(|rel| {
+                        rel.split_ascii_whitespace()
+                            .any(|r| r.eq_ignore_ascii_case("stylesheet"))
+                    });
+                    if !is_stylesheet {
+                        continue;
+                    }
+                    let href = match element.get_attribute("href") {
+                        Some(href) => href,
+                        None => continue,
+                    };
+                    let url = match self.resolve_url(&href) {
+                        Some(url) => url,
+                        None => continue,
+                    };
+                    // 取得に失敗したスタイルシートは読み飛ばす。ログは fetch 側で出す
+                    match fetch(&url) {
+                        Ok(res) if res.is_success() => res.body(),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            rules.extend(parse_stylesheet(&css).rules);
+        }
+
+        let mut style = StyleSheet::new();
+        style.set_rules(rules);
+        style
+    }
+
     fn set_layout_view(&mut self) {
         let dom = match &self.frame {
             Some(frame) => frame.borrow().document(),
@@ -93,7 +385,7 @@ impl Page {
             None => return,
         };
 
-        let layout_view = LayoutView::new(dom, style);
+        let layout_view = LayoutView::with_content_width(dom, style, self.zoom, self.content_width);
         self.layout_view = Some(layout_view);
     }
 
@@ -105,6 +397,148 @@ impl Page {
 }
 
 impl Page {
+    /// Runs `source` with the document of the page. Inline scripts of a page run through this
+    /// when the page loads. If the script changes the DOM in a way that affects the layout, the
+    /// page is marked dirty until `update_rendering` is called.
+    /// Fails with `Error::ScriptTimeout` if the script runs too long. Does nothing if JavaScript
+    /// is disabled.
+    pub fn execute_script(&mut self, source: &str) -> Result<(), Error> {
+        if !self.javascript_enabled {
+            return Ok(());
+        }
+
+        let document = match self.document() {
+            Some(document) => document,
+            None => return Ok(()),
+        };
+
+        let mut parser = JsParser::new(JsLexer::new(source.to_string()));
+        let program = parser.parse_ast();
+        // 構文エラーがあっても読めたところまでは実行し、エラーはイベントログに残す
+        for error in parser.errors() {
+            self.event_log
+                .borrow_mut()
+                .push(LogEvent::ScriptSyntaxError(error.to_string()));
+        }
+        self.runtime.set_document(document);
+        let result = self.runtime.execute(&program);
+        if let Some(span) = self.runtime.timeout_span() {
+            self.event_log.borrow_mut().push(LogEvent::ScriptTimeout(format!(
+                "line {}, column {}",
+                span.line, span.column
+            )));
+        }
+        self.event_log.borrow_mut().push(LogEvent::ScriptExecuted);
+
+        // 途中で止まっても、それまでの DOM の変更は反映する
+        if self.runtime.is_dom_modified() {
+            self.dirty = true;
+        }
+        result
+    }
+
+    /// Starts editing the text at `position` in the content area, with the caret at the
+    /// character boundary nearest to the click. Returns false if there is no text there.
+    pub fn focus_text_at(&mut self, position: (i64, i64)) -> bool {
+        let object = match &self.layout_view {
+            Some(view) => match view.find_node_by_position(position) {
+                Some(object) => object,
+                None => return false,
+            },
+            None => return false,
+        };
+
+        let caret = match object.borrow_mut().caret_index_at(position) {
+            Some(caret) => caret,
+            None => return false,
+        };
+        self.focused_text = Some((object.borrow().node(), caret));
+        true
+    }
+
+    /// Stops editing the focused text.
+    pub fn blur(&mut self) {
+        self.focused_text = None;
+    }
+
+    pub fn has_focused_text(&self) -> bool {
+        self.focused_text.is_some()
+    }
+
+    /// Inserts `c` at the caret of the focused text and paints the page again.
+    /// Returns false if no text is focused.
+    pub fn insert_char(&mut self, c: char) -> bool {
+        let (node, caret) = match &self.focused_text {
+            Some((node, caret)) => (node.clone(), *caret),
+            None => return false,
+        };
+        let caret = match insert_char_at(&node, caret, c) {
+            Some(caret) => caret,
+            None => return false,
+        };
+        self.focused_text = Some((node, caret));
+        self.set_layout_view();
+        self.paint_tree();
+        true
+    }
+
+    /// Removes the character before the caret of the focused text and paints the page again.
+    /// Returns false if no text is focused.
+    pub fn delete_char(&mut self) -> bool {
+        let (node, caret) = match &self.focused_text {
+            Some((node, caret)) => (node.clone(), *caret),
+            None => return false,
+        };
+        let caret = match delete_char_before(&node, caret) {
+            Some(caret) => caret,
+            None => return false,
+        };
+        self.focused_text = Some((node, caret));
+        self.set_layout_view();
+        self.paint_tree();
+        true
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// dirty なときだけ、レイアウトと描画をやり直す
+    pub fn update_rendering(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.set_layout_view();
+        self.paint_tree();
+        self.dirty = false;
+    }
+
+    pub fn zoom(&self) -> i64 {
+        self.zoom
+    }
+
+    /// ズーム倍率（パーセント）を変えて、レイアウトと描画をやり直す
+    pub fn set_zoom(&mut self, zoom: i64) {
+        self.zoom = clamp_zoom(zoom);
+        self.set_layout_view();
+        self.paint_tree();
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.set_zoom(DEFAULT_ZOOM);
+    }
+
+    /// Lays out the page again to fit a content area `content_width` wide, for windows of a
+    /// size other than the default one.
+    pub fn set_content_width(&mut self, content_width: i64) {
+        if self.content_width == content_width {
+            return;
+        }
+        self.content_width = content_width;
+        self.set_layout_view();
+        self.paint_tree();
+    }
+
     pub fn display_items(&self) -> Vec<DisplayItem> {
         self.display_items.clone()
     }
@@ -112,4 +546,714 @@ impl Page {
     pub fn clear_display_items(&mut self) {
         self.display_items = Vec::new();
     }
-}
\ No newline at end of file
+
+    /// Returns every link in the page, in document order.
+    pub fn links(&self) -> Vec<Link> {
+        match &self.frame {
+            Some(frame) => get_links(frame.borrow().document()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the URL to navigate to right after loading, given by
+    /// `<meta http-equiv="refresh" content="0;url=...">`. Refreshes with a delay are ignored
+    /// for now.
+    pub fn refresh_url(&self) -> Option<Url> {
+        let frame = self.frame.as_ref()?;
+        let (delay, url) = get_meta_refresh(frame.borrow().document())?;
+        if delay != 0 {
+            return None;
+        }
+        self.resolve_url(&url?)
+    }
+
+    pub fn title(&self) -> String {
+        match &self.frame {
+            Some(frame) => get_title_content(frame.borrow().document()),
+            None => String::new(),
+        }
+    }
+
+    pub fn content_height(&self) -> i64 {
+        match &self.layout_view {
+            Some(view) => match view.root() {
+                Some(root) => root.borrow().size().height(),
+                None => 0,
+            },
+            None => 0,
+        }
+    }
+
+    pub fn load_state(&self) -> LoadState {
+        self.load_state
+    }
+
+    pub fn set_load_state(&mut self, load_state: LoadState) {
+        self.load_state = load_state;
+    }
+}
+
+fn no_fetch(url: &Url) -> Result<HttpResponse, Error> {
+    Err(Error::Network(format!("no fetcher to load {}", url)))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoadState {
+    Idle,
+    Loading {
+        bytes_received: usize,
+        content_length: Option<usize>,
+    },
+    Complete,
+    Failed,
+}
+
+impl LoadState {
+    /// Returns how much of the page has been loaded, from 0.0 to 1.0.
+    /// Returns None while loading a response whose length is unknown.
+    pub fn progress(&self) -> Option<f64> {
+        match self {
+            LoadState::Idle => Some(0.0),
+            LoadState::Loading {
+                bytes_received,
+                content_length,
+            } => match content_length {
+                Some(0) => Some(1.0),
+                Some(length) => {
+                    let fraction = *bytes_received as f64 / *length as f64;
+                    if fraction > 1.0 {
+                        Some(1.0)
+                    } else {
+                        Some(fraction)
+                    }
+                }
+                None => None,
+            },
+            LoadState::Complete => Some(1.0),
+            LoadState::Failed => Some(0.0),
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(self, LoadState::Loading { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHAR_WIDTH;
+    use crate::http::testing::FakeTransport;
+    use crate::http::HttpClient;
+    use crate::renderer::dom::api::get_element_by_id;
+    use alloc::vec;
+    use crate::renderer::layout::computed_style::{
+        Color, FontFamily, FontSize, FontStyle, FontWeight, WhiteSpace,
+    };
+    use crate::renderer::layout::layout_object::{drawn_font_percentage, LayoutObject, LayoutObjectKind};
+    use crate::renderer::layout::layout_object::INPUT_TEXT_LENGTH;
+
+    #[test]
+    fn test_progress_known_length() {
+        let state = LoadState::Loading {
+            bytes_received: 0,
+            content_length: Some(200),
+        };
+        assert_eq!(Some(0.0), state.progress());
+
+        let state = LoadState::Loading {
+            bytes_received: 50,
+            content_length: Some(200),
+        };
+        assert_eq!(Some(0.25), state.progress());
+
+        let state = LoadState::Loading {
+            bytes_received: 200,
+            content_length: Some(200),
+        };
+        assert_eq!(Some(1.0), state.progress());
+    }
+
+    #[test]
+    fn test_progress_exceeds_length() {
+        let state = LoadState::Loading {
+            bytes_received: 300,
+            content_length: Some(200),
+        };
+        assert_eq!(Some(1.0), state.progress());
+
+        let state = LoadState::Loading {
+            bytes_received: 0,
+            content_length: Some(0),
+        };
+        assert_eq!(Some(1.0), state.progress());
+    }
+
+    #[test]
+    fn test_progress_unknown_length() {
+        let state = LoadState::Loading {
+            bytes_received: 100,
+            content_length: None,
+        };
+        assert_eq!(None, state.progress());
+        assert!(state.is_loading());
+    }
+
+    #[test]
+    fn test_progress_idle_and_complete() {
+        assert_eq!(Some(0.0), LoadState::Idle.progress());
+        assert_eq!(Some(1.0), LoadState::Complete.progress());
+        assert!(!LoadState::Complete.is_loading());
+    }
+
+    fn html_response(html: &str) -> HttpResponse {
+        let raw = format!("HTTP/1.1 200 OK\nContent-Length: {}\n\n{}", html.len(), html);
+        HttpResponse::new(raw).expect("failed to parse http response")
+    }
+
+    /// 描画されるテキストを、ディスプレイアイテムの順に返す
+    fn rendered_text(page: &Page) -> Vec<String> {
+        page.display_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn load(url: Option<&str>, html: &str) -> Page {
+        let mut page = Page::new();
+        if let Some(url) = url {
+            page.set_url(Url::new(url.to_string()).parse().expect("failed to parse url"));
+        }
+        page.receive_response(html_response(html));
+        page
+    }
+
+    #[test]
+    fn test_links() {
+        let html = "<html><head><title>Sample</title></head><body><h1>Sample page</h1><p>Go to <a href=\"/next\">next</a> or <a href=\"http://example.com/\" title=\"Example\">example</a>.</p></body></html>";
+        let mut page = Page::new();
+        page.receive_response(html_response(html));
+
+        let links = page.links();
+        assert_eq!(
+            vec!["/next", "http://example.com/"],
+            links.iter().map(|link| link.href()).collect::<Vec<String>>()
+        );
+        assert_eq!("Example", links[1].title());
+        assert_eq!(
+            vec!["Sample page", "Go to", "next", "or", "example", "."],
+            rendered_text(&page)
+        );
+    }
+
+    #[test]
+    fn test_submit_form() {
+        let html = "<html><body><form action=\"search\"><input name=\"q\" value=\"a b\"><input type=\"submit\"></form></body></html>";
+        let page = load(Some("http://example.com/dir/index.html"), html);
+
+        // 1つ目の input の右隣にある submit ボタンをクリックする
+        let submit_x = CHAR_WIDTH * (INPUT_TEXT_LENGTH as i64 + 2) + 1;
+        assert_eq!(
+            Some("http://example.com/dir/search?q=a%20b".to_string()),
+            page.clicked((submit_x, 1))
+        );
+        assert_eq!(None, page.clicked((1, 1)));
+    }
+
+    #[test]
+    fn test_submit_post_form() {
+        let html = "<html><body><form method=\"post\" action=\"http://example.com/\"><input type=\"submit\"></form></body></html>";
+        let page = load(None, html);
+        assert_eq!(None, page.clicked((1, 1)));
+    }
+
+    fn load_with_transport(url: &str, html: &str, transport: FakeTransport) -> Page {
+        let mut page = Page::new();
+        page.set_url(Url::new(url.to_string()).parse().expect("failed to parse url"));
+        let mut client = HttpClient::new(transport);
+        page.receive_response_with_fetcher(html_response(html), &mut |url| client.get(url, 80));
+        page
+    }
+
+    fn first_block_background(page: &Page) -> Color {
+        let root = page
+            .layout_view
+            .as_ref()
+            .and_then(|view| view.root())
+            .expect("failed to get a layout root");
+        let p = root.borrow().first_child().expect("failed to get a first child");
+        let color = p.borrow().style().background_color();
+        color
+    }
+
+    #[test]
+    fn test_link_stylesheet() {
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let css = "p { background-color: red; }";
+        let transport = FakeTransport::new(
+            sent.clone(),
+            &format!("HTTP/1.1 200 OK\nContent-Length: {}\n\n{}", css.len(), css),
+        );
+        let html = "<html><head><link rel=\"stylesheet\" href=\"css/style.css\"></head><body><p>a</p></body></html>";
+        let page = load_with_transport("http://example.com/dir/index.html", html, transport);
+
+        let request = String::from_utf8(sent.borrow().clone()).expect("invalid request");
+        assert!(request.starts_with("GET /dir/css/style.css HTTP/1.1\n"));
+        assert_eq!(
+            Color::from_name("red").expect("failed to parse color"),
+            first_block_background(&page)
+        );
+    }
+
+    #[test]
+    fn test_link_stylesheet_fetch_failure() {
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let mut transport = FakeTransport::new(sent, "");
+        transport.connect_errors = vec![Error::ConnectionRefused];
+        let html = "<html><head><link rel=\"stylesheet\" href=\"style.css\"></head><body><p>a</p></body></html>";
+        let page = load_with_transport("http://example.com/", html, transport);
+
+        assert_eq!(LoadState::Complete, page.load_state());
+        assert_eq!(Color::white(), first_block_background(&page));
+        assert!(!page.display_items().is_empty());
+    }
+
+    fn find_layout_object(page: &Page, kind: ElementKind) -> Rc<RefCell<LayoutObject>> {
+        let mut stack = vec![page
+            .layout_view
+            .as_ref()
+            .and_then(|view| view.root())
+            .expect("failed to get a layout root")];
+        while let Some(object) = stack.pop() {
+            if object.borrow().node().borrow().element_kind() == Some(kind) {
+                return object;
+            }
+            if let Some(sibling) = object.borrow().next_sibling() {
+                stack.push(sibling);
+            }
+            if let Some(child) = object.borrow().first_child() {
+                stack.push(child);
+            }
+        }
+        panic!("failed to find a layout object for {}", kind);
+    }
+
+    #[test]
+    fn test_user_agent_style_sheet() {
+        let html = "<html><body><h1>title</h1><p>text<a href=\"/\">link</a></p></body></html>";
+        let page = load(None, html);
+
+        let h1 = find_layout_object(&page, ElementKind::H1).borrow().style();
+        let p = find_layout_object(&page, ElementKind::P).borrow().style();
+        assert!(h1.font_size() > p.font_size());
+
+        let a = find_layout_object(&page, ElementKind::A).borrow().style();
+        assert_eq!(Color::from_name("blue").expect("failed to parse color"), a.color());
+        assert_eq!(Color::black(), p.color());
+    }
+
+    #[test]
+    fn test_author_colors() {
+        // color は文字の色を、background-color は背景の色を変える
+        let mut page = Page::new();
+        page.receive_response(html_response(
+            "<html><head><style>p { color: red; background-color: yellow; }</style></head><body><p>styled</p></body></html>",
+        ));
+        assert_eq!(vec!["styled"], rendered_text(&page));
+        let p = find_layout_object(&page, ElementKind::P).borrow().style();
+        assert_eq!(Color::from_name("red").expect("failed to parse color"), p.color());
+        assert_eq!(
+            Color::from_name("yellow").expect("failed to parse color"),
+            p.background_color()
+        );
+    }
+
+    #[test]
+    fn test_heading_font_sizes() {
+        let html = "<html><body><h1>1</h1><h2>2</h2><h3>3</h3><h4>4</h4><h5>5</h5><h6>6</h6><p>7</p></body></html>";
+        let page = load(None, html);
+
+        let font_sizes: Vec<FontSize> = [
+            ElementKind::H1,
+            ElementKind::H2,
+            ElementKind::H3,
+            ElementKind::H4,
+            ElementKind::H5,
+            ElementKind::H6,
+        ]
+        .iter()
+        .map(|kind| find_layout_object(&page, *kind).borrow().style().font_size())
+        .collect();
+        for pair in font_sizes.windows(2) {
+            assert!(pair[0] > pair[1], "{:?} should be larger than {:?}", pair[0], pair[1]);
+        }
+
+        let h6 = find_layout_object(&page, ElementKind::H6);
+        assert_eq!(LayoutObjectKind::Block, h6.borrow().kind());
+
+        // 描画される大きさは 3 段階しかないが、見出しは太字なので本文とは違って見える
+        let drawn: Vec<(String, i64, FontWeight)> = page
+            .display_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, style, .. } => Some((
+                    text,
+                    drawn_font_percentage(style.font_size(), 100),
+                    style.font_weight(),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("1".to_string(), 300, FontWeight::Bold),
+                ("2".to_string(), 200, FontWeight::Bold),
+                ("3".to_string(), 200, FontWeight::Bold),
+                ("4".to_string(), 100, FontWeight::Bold),
+                ("5".to_string(), 100, FontWeight::Bold),
+                ("6".to_string(), 100, FontWeight::Bold),
+                ("7".to_string(), 100, FontWeight::Normal),
+            ],
+            drawn
+        );
+    }
+
+    #[test]
+    fn test_emphasis_styles() {
+        let html = "<html><body><p>plain<b>bold<i>both</i></b><em>italic</em></p></body></html>";
+        let page = load(None, html);
+
+        let p = find_layout_object(&page, ElementKind::P).borrow().style();
+        assert_eq!(FontWeight::Normal, p.font_weight());
+        assert_eq!(FontStyle::Normal, p.font_style());
+
+        // <b><i> の中のテキストは太字と斜体の両方を引き継ぐ
+        let both = find_layout_object(&page, ElementKind::I)
+            .borrow()
+            .first_child()
+            .expect("text in <i> should exist")
+            .borrow()
+            .style();
+        assert_eq!(FontWeight::Bold, both.font_weight());
+        assert_eq!(FontStyle::Italic, both.font_style());
+
+        let em = find_layout_object(&page, ElementKind::Em).borrow().style();
+        assert_eq!(FontWeight::Normal, em.font_weight());
+        assert_eq!(FontStyle::Italic, em.font_style());
+    }
+
+    #[test]
+    fn test_code_styles() {
+        let html = "<html><body><p>a  b<code>let  x = 1;</code><kbd>Ctrl</kbd></p></body></html>";
+        let page = load(None, html);
+
+        let p = find_layout_object(&page, ElementKind::P).borrow().style();
+        assert_eq!(FontFamily::SansSerif, p.font_family());
+        assert_eq!(WhiteSpace::Normal, p.white_space());
+
+        let text = find_layout_object(&page, ElementKind::Code)
+            .borrow()
+            .first_child()
+            .expect("text in <code> should exist");
+        let style = text.borrow().style();
+        assert_eq!(FontFamily::Monospace, style.font_family());
+        assert_eq!(WhiteSpace::Pre, style.white_space());
+        assert_eq!(
+            Color::from_name("lightgray").expect("failed to parse color"),
+            style.background_color()
+        );
+        assert_eq!(
+            FontFamily::Monospace,
+            find_layout_object(&page, ElementKind::Kbd).borrow().style().font_family()
+        );
+
+        // <code> の中の連続した空白は詰めずに描画し、外の空白は 1 つにまとめる
+        let texts = rendered_text(&page);
+        assert!(texts.contains(&"let  x = 1;".to_string()), "{:?}", texts);
+        assert!(texts.contains(&"a b".to_string()), "{:?}", texts);
+        assert_eq!(
+            CHAR_WIDTH * "let  x = 1;".len() as i64,
+            text.borrow().size().width()
+        );
+    }
+
+    #[test]
+    fn test_author_style_overrides_user_agent_style() {
+        let html = "<html><head><style>h1 { font-size: medium; }</style></head><body><h1>title</h1></body></html>";
+        let page = load(None, html);
+
+        let h1 = find_layout_object(&page, ElementKind::H1).borrow().style();
+        assert_eq!(FontSize::Medium, h1.font_size());
+    }
+
+    #[test]
+    fn test_refresh_url() {
+        let html = "<html><head><meta http-equiv=\"refresh\" content=\"0; url=next.html\"></head><body>moved</body></html>";
+        let page = load(Some("http://example.com/dir/index.html"), html);
+        assert_eq!(
+            Some("http://example.com/dir/next.html".to_string()),
+            page.refresh_url().map(|url| url.to_string())
+        );
+
+        // 遅れて移動するページは、まだ対応していないのでそのまま表示する
+        let html = "<html><head><meta http-equiv=\"refresh\" content=\"5; url=next.html\"></head><body>moved</body></html>";
+        let page = load(Some("http://example.com/dir/index.html"), html);
+        assert_eq!(None, page.refresh_url());
+    }
+
+    #[test]
+    fn test_base_href() {
+        let html = "<html><head><base href=\"http://cdn.example.com/assets/\"></head><body><a href=\"next.html\">next</a></body></html>";
+        let page = load(Some("http://example.com/dir/index.html"), html);
+        assert_eq!(
+            Some("http://cdn.example.com/assets/".to_string()),
+            page.base_url().map(|url| url.to_string())
+        );
+        assert_eq!(
+            Some("http://cdn.example.com/assets/next.html".to_string()),
+            page.clicked((1, 1))
+        );
+
+        let html = "<html><head></head><body><a href=\"next.html\">next</a></body></html>";
+        let page = load(Some("http://example.com/dir/index.html"), html);
+        assert_eq!(
+            Some("http://example.com/dir/next.html".to_string()),
+            page.clicked((1, 1))
+        );
+    }
+
+    #[test]
+    fn test_link_at() {
+        let html = "<html><body><p>text</p><p><a href=\"next.html\">next</a></p></body></html>";
+        let page = load(Some("http://example.com/dir/index.html"), html);
+
+        let a = find_layout_object(&page, ElementKind::A);
+        let point = a.borrow().point();
+        assert_eq!(
+            Some("http://example.com/dir/next.html".to_string()),
+            page.link_at((point.x() + 1, point.y() + 1))
+        );
+        // リンクではないテキストや、何もない場所では None
+        assert_eq!(None, page.link_at((1, 1)));
+        assert_eq!(None, page.link_at((1, point.y() + 1000)));
+    }
+
+    #[test]
+    fn test_set_attribute_from_script() {
+        let html = "<html><head><style>.red { background-color: red; }</style></head><body><p id=\"x\" title=\"a\">text</p></body></html>";
+        let mut page = load(None, html);
+        let p = get_element_by_id(page.document().expect("failed to get a document"), "x")
+            .expect("failed to find p");
+        let attribute = |name: &str| p.borrow().get_element().and_then(|e| e.get_attribute(name));
+
+        // JavaScript を有効にするまでは、スクリプトを実行しない
+        page.execute_script("document.getElementById(\"x\").setAttribute(\"title\", \"b\")")
+            .expect("failed to execute script");
+        assert_eq!(Some("a".to_string()), attribute("title"));
+
+        page.set_javascript_enabled(true);
+        // 描画に影響しない属性では dirty にならない
+        page.execute_script("document.getElementById(\"x\").setAttribute(\"title\", \"b\")")
+            .expect("failed to execute script");
+        assert_eq!(Some("b".to_string()), attribute("title"));
+        assert!(!page.is_dirty());
+
+        page.execute_script("var p = document.getElementById(\"x\"); p.setAttribute(\"class\", \"red\")")
+            .expect("failed to execute script");
+        assert!(page.is_dirty());
+        assert_eq!(Some("red".to_string()), attribute("class"));
+        // レイアウトをやり直すまでは、前のスタイルのまま
+        assert_eq!(Color::white(), first_block_background(&page));
+
+        page.update_rendering();
+        assert!(!page.is_dirty());
+        assert_eq!(
+            Color::from_name("red").expect("failed to parse color"),
+            first_block_background(&page)
+        );
+    }
+
+    #[test]
+    fn test_script_globals_reset_on_load() {
+        let html = "<html><body><p id=\"x\">text</p></body></html>";
+        let mut page = load(None, html);
+        page.set_javascript_enabled(true);
+        let title = |page: &Page| {
+            let p = get_element_by_id(page.document().expect("failed to get a document"), "x")
+                .expect("failed to find p");
+            let title = p.borrow().get_element().and_then(|e| e.get_attribute("title"));
+            title
+        };
+        let set_title = "document.getElementById(\"x\").setAttribute(\"title\", t)";
+
+        // 同じページのスクリプトは変数を共有する
+        page.execute_script("var t = \"a\"").expect("failed to execute script");
+        page.execute_script(set_title).expect("failed to execute script");
+        assert_eq!(Some("a".to_string()), title(&page));
+
+        // 別のページを読み込むと、前のページの変数は残らない
+        page.load_from_str(html);
+        page.execute_script(set_title).expect("failed to execute script");
+        assert_eq!(Some("undefined".to_string()), title(&page));
+    }
+
+    #[test]
+    fn test_script_syntax_error() {
+        let mut page = load(None, "<html><body><p>ab</p></body></html>");
+        page.set_javascript_enabled(true);
+        page.execute_script("var a = ;")
+            .expect("failed to execute script");
+
+        let events = page.event_log.borrow().events();
+        assert_eq!(
+            vec![
+                LogEvent::ScriptSyntaxError(
+                    "unexpected token ';' at line 1, column 9".to_string()
+                ),
+                LogEvent::ScriptExecuted,
+            ],
+            events[events.len() - 2..]
+        );
+    }
+
+    #[test]
+    fn test_script_unsupported_chars() {
+        let mut page = load(None, "<html><body><p>ab</p></body></html>");
+        page.set_javascript_enabled(true);
+
+        // まだサポートしない文字はパニックせず、構文エラーとしてログに残る
+        for source in ["if (a < 2) {}", "!true", "5 % 2"] {
+            page.execute_script(source).expect("failed to execute script");
+            let events = page.event_log.borrow().events();
+            assert!(matches!(events[events.len() - 2], LogEvent::ScriptSyntaxError(_)));
+            assert_eq!(LogEvent::ScriptExecuted, events[events.len() - 1]);
+        }
+        let events = page.event_log.borrow().events();
+        assert_eq!(
+            LogEvent::ScriptSyntaxError("unexpected token '%' at line 1, column 3".to_string()),
+            events[events.len() - 2]
+        );
+    }
+
+    #[test]
+    fn test_run_inline_scripts_on_load() {
+        let html = "<html><head><style>.red { background-color: red; }</style></head><body>\
+                    <p id=\"x\">text</p>\
+                    <script>var p = document.getElementById(\"x\");</script>\
+                    <script src=\"a.js\">p.setAttribute(\"title\", \"src\")</script>\
+                    <script>p.setAttribute(\"class\", \"red\")</script></body></html>";
+        let title_and_class = |page: &Page| {
+            let p = get_element_by_id(page.document().expect("failed to get a document"), "x")
+                .expect("failed to find p");
+            let element = p.borrow().get_element().expect("p should be an element");
+            (element.get_attribute("title"), element.get_attribute("class"))
+        };
+
+        // JavaScript が無効なときは、読み込んでもスクリプトを実行しない
+        let page = load(None, html);
+        assert_eq!((None, None), title_and_class(&page));
+
+        // 後のスクリプトは前のスクリプトの変数を使える。src のあるスクリプトは実行しない
+        let mut page = Page::new();
+        page.set_javascript_enabled(true);
+        page.load_from_str(html);
+        assert_eq!((None, Some("red".to_string())), title_and_class(&page));
+        assert!(!page.is_dirty());
+        assert_eq!(
+            Color::from_name("red").expect("failed to parse color"),
+            first_block_background(&page)
+        );
+    }
+
+    #[test]
+    fn test_script_timeout_position() {
+        let mut page = load(None, "<html><body><p>ab</p></body></html>");
+        page.set_javascript_enabled(true);
+        page.runtime.set_max_operations(100);
+        assert_eq!(
+            Err(Error::ScriptTimeout),
+            page.execute_script("var a = 1;\ndo a = a + 1; while (true)")
+        );
+
+        let events = page.event_log.borrow().events();
+        assert_eq!(
+            vec![
+                LogEvent::ScriptTimeout("line 2, column 1".to_string()),
+                LogEvent::ScriptExecuted,
+            ],
+            events[events.len() - 2..]
+        );
+    }
+
+    #[test]
+    fn test_edit_focused_text() {
+        let mut page = load(None, "<html><body><p>ab</p></body></html>");
+        assert!(!page.insert_char('x'));
+        // 何もない場所ではフォーカスしない
+        assert!(!page.focus_text_at((1, 1000)));
+        assert!(!page.has_focused_text());
+
+        let point = find_layout_object(&page, ElementKind::P).borrow().point();
+        assert!(page.focus_text_at((point.x() + CHAR_WIDTH * 2, point.y() + 1)));
+        assert!(page.insert_char('c'));
+        assert!(page.insert_char('d'));
+        assert!(page.delete_char());
+        assert_eq!(vec!["abc"], rendered_text(&page));
+
+        // キャレットはクリックした位置に一番近い文字の境目に置かれる
+        assert!(page.focus_text_at((point.x() + CHAR_WIDTH + 1, point.y() + 1)));
+        assert!(page.insert_char('x'));
+        assert!(page.focus_text_at((point.x(), point.y() + 1)));
+        assert!(page.insert_char('y'));
+        assert_eq!(vec!["yaxbc"], rendered_text(&page));
+
+        page.blur();
+        assert!(!page.insert_char('e'));
+    }
+
+    #[test]
+    fn test_noscript() {
+        let html = "<html><body><p>a</p><noscript><p>no js</p></noscript></body></html>";
+
+        // 既定では JavaScript が無効なので、<noscript> の中身を描画する
+        let page = load(None, html);
+        assert!(!page.is_javascript_enabled());
+        assert_eq!(vec!["a", "no js"], rendered_text(&page));
+
+        let mut page = Page::new();
+        page.set_javascript_enabled(true);
+        page.receive_response(html_response(html));
+        assert_eq!(vec!["a"], rendered_text(&page));
+    }
+
+    #[test]
+    fn test_content_width() {
+        let mut page = load(None, "<html><body><p>aaaa bbbb</p></body></html>");
+        assert_eq!(vec!["aaaa bbbb"], rendered_text(&page));
+
+        // 狭いウィンドウでは、その幅で折り返す
+        page.set_content_width(CHAR_WIDTH * 6);
+        assert_eq!(vec!["aaaa", "bbbb"], rendered_text(&page));
+    }
+
+    #[test]
+    fn test_whitespace_between_inline_elements() {
+        let page = load(None, "<html><body>\n  <p><b>a</b> <i>b</i></p>\n</body></html>");
+        let points: Vec<(String, i64)> = page
+            .display_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, layout_point, .. } if !text.is_empty() => {
+                    Some((text, layout_point.x()))
+                }
+                _ => None,
+            })
+            .collect();
+        // 単語の区切りの空白は 1 文字分の幅を空ける
+        assert_eq!("a", points[0].0);
+        assert_eq!(("b".to_string(), points[0].1 + CHAR_WIDTH * 2), points[1]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::Error;
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::Debug;
+
+/// A key-value store for data that should outlive a browser session, such as history.
+/// Each platform provides its own implementation backed by its filesystem.
+pub trait Storage: Debug {
+    /// Returns None if nothing has been saved for `key`.
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, data: &str) -> Result<(), Error>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStorage {
+    entries: BTreeMap<String, String>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.entries.get(key).cloned()
+    }
+
+    fn save(&mut self, key: &str, data: &str) -> Result<(), Error> {
+        self.entries.insert(key.to_string(), data.to_string());
+        Ok(())
+    }
+}
+
+/// A `Storage` that keeps the data of each key in a file named after the key under `dir`.
+/// A file that is missing or isn't valid UTF-8 loads as nothing saved.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStorage {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileStorage {
+    pub fn new(dir: std::path::PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Returns a storage in `$HOME/.saba`, or in `.saba` under the current directory if `HOME`
+    /// isn't set.
+    pub fn in_home_dir() -> Self {
+        let home = std::env::var_os("HOME").map(std::path::PathBuf::from).unwrap_or_default();
+        Self::new(home.join(".saba"))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.dir.join(key)).ok()
+    }
+
+    fn save(&mut self, key: &str, data: &str) -> Result<(), Error> {
+        // 初めて保存するときはディレクトリがないので作る
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(self.dir.join(key), data))
+            .map_err(|e| Error::Other(format!("failed to save {}: {}", key, e)))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+    use crate::browser::Browser;
+    use crate::history::HISTORY_STORAGE_KEY;
+
+    fn temp_storage(name: &str) -> FileStorage {
+        let dir = std::env::temp_dir().join(format!("saba_storage_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        FileStorage::new(dir)
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let mut storage = temp_storage("round_trip");
+        assert_eq!(None, storage.load("history"));
+
+        storage.save("history", "a\nb\n").expect("failed to save");
+        assert_eq!(Some("a\nb\n".to_string()), storage.load("history"));
+        // 別のセッションのストレージからも読める
+        assert_eq!(Some("a\nb\n".to_string()), FileStorage::new(storage.dir.clone()).load("history"));
+
+        let _ = std::fs::remove_dir_all(&storage.dir);
+    }
+
+    #[test]
+    fn test_history_across_sessions() {
+        let storage = temp_storage("history");
+        let browser = Browser::new();
+        browser.borrow_mut().set_storage(Rc::new(RefCell::new(storage.clone())));
+        browser
+            .borrow_mut()
+            .push_history("http://example.com/".to_string())
+            .expect("failed to push history");
+
+        let restored = Browser::new();
+        restored.borrow_mut().set_storage(Rc::new(RefCell::new(storage.clone())));
+        assert_eq!(browser.borrow().history(), restored.borrow().history());
+
+        // 壊れたファイルからは空の履歴を読み込む
+        std::fs::write(storage.dir.join(HISTORY_STORAGE_KEY), [0xff, 0xfe]).expect("failed to write");
+        let restored = Browser::new();
+        restored.borrow_mut().set_storage(Rc::new(RefCell::new(storage.clone())));
+        assert!(restored.borrow().history().entries().is_empty());
+
+        let _ = std::fs::remove_dir_all(&storage.dir);
+    }
+}
@@ -0,0 +1,16 @@
+//! Helpers shared by the tests of several modules.
+
+use alloc::rc::Rc;
+use alloc::string::ToString;
+use core::cell::RefCell;
+use crate::renderer::dom::node::Node;
+use crate::renderer::html::parser::HtmlParser;
+use crate::renderer::html::token::HtmlTokenizer;
+
+/// Parses `html` and returns the document node.
+pub(crate) fn parse_document(html: &str) -> Rc<RefCell<Node>> {
+    HtmlParser::new(HtmlTokenizer::new(html.to_string()))
+        .construct_tree()
+        .borrow()
+        .document()
+}
@@ -9,12 +9,21 @@ use alloc::string::String;
 use core::cell::RefCell;
 use crate::alloc::string::ToString;
 use noli::*;
-use net_wasabi::http::HttpClient;
+use net_wasabi::dns::NoliResolver;
+use net_wasabi::http::TcpTransport;
 use saba_core::browser::Browser;
+use saba_core::dns::DnsCache;
 use saba_core::error::Error;
-use saba_core::http::HttpResponse;
+use saba_core::http::CancelFlag;
+use saba_core::http::HttpClient;
+use saba_core::http::NavigationResult;
+use saba_core::http::RetryPolicy;
+#[cfg(target_os = "linux")]
+use saba_core::storage::FileStorage;
 use saba_core::url::Url;
 use ui_wasabi::app::WasabiUI;
+use ui_wasabi::clock;
+use ui_wasabi::config::UiConfig;
 
 static TEST_HTTP_RESPONSE: &str = r#"HTTP/1.1 200 OK
 Data: xx xx xx
@@ -34,7 +43,34 @@ Data: xx xx xx
 </html>
 "#;
 
-fn handle_url(url: String) -> Result<HttpResponse, Error> {
+/// 名前解決の結果を使い続ける時間 (tick)。時計がないので、ミリ秒ではなく UI のイベントループを回った回数で数える
+const DNS_CACHE_TTL_TICKS: u64 = 100_000;
+
+/// 接続できなかったときや応答がなかったときに、読み込みを再試行する回数と最初の待ち時間
+const NAVIGATION_RETRIES: u32 = 2;
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// noli には一定時間待つ API がないので、おおよその回数だけ空回りして待つ。
+/// clock::ticks は UI のイベントループで進むが、読み込みの間はイベントループが止まっているので使えない
+const SPINS_PER_MS: u64 = 100_000;
+
+/// 再試行の前に待つ。停止キーが押されたら、待ち時間の途中でもすぐに戻る
+fn sleep(delay_ms: u64, cancel_flag: &CancelFlag) {
+    for _ in 0..delay_ms {
+        if cancel_flag.is_cancelled() {
+            return;
+        }
+        for _ in 0..SPINS_PER_MS {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+fn handle_url(
+    dns_cache: &Rc<RefCell<DnsCache<NoliResolver>>>,
+    browser: &Browser,
+    url: String,
+) -> Result<NavigationResult, Error> {
     let parsed_url = match Url::new(url).parse() {
         Ok(url) => url,
         Err(e) => {
@@ -45,63 +81,36 @@ fn handle_url(url: String) -> Result<HttpResponse, Error> {
         }
     };
 
-    let client = HttpClient::new();
-    let response = match client.get(
-        parsed_url.host(),
-        parsed_url.port().parse::<u16>().expect(&format!(
-            "port number should be u16 but got {}",
-            parsed_url.port()
-        )),
-        parsed_url.path(),
-    ) {
-        Ok(res) => {
-            if res.status_code() == 302 {
-                let location = match res.header_value("Location") {
-                    Ok(value) => value,
-                    Err(_) => return Ok(res),
-                };
-                let redirect_parsed_url = match Url::new(location).parse() {
-                    Ok(url) => url,
-                    Err(e) => {
-                        return Err(Error::UnexpectedInput(format!(
-                            "input html is not supported: {:?}",
-                            e
-                        )))
-                    }
-                };
-                let redirect_res = match client.get(
-                    redirect_parsed_url.host(),
-                    redirect_parsed_url.port().parse::<u16>().expect(&format!(
-                        "port number should be u16 but got {}",
-                        redirect_parsed_url.port()
-                    )),
-                    redirect_parsed_url.path(),
-                ) {
-                    Ok(res) => res,
-                    Err(e) => return Err(Error::Network(format!("{:?}", e))),
-                };
-                redirect_res
-            } else {
-                res
-            }
-        }
-        Err(e) => {
-            return Err(Error::Network(format!(
-                "failed to get http response: {:?}",
-                e
-            )))
-        }
-    };
-
-    Ok(response)
+    let transport = TcpTransport::new(dns_cache.clone(), clock::ticks);
+    let mut client = HttpClient::new(transport)
+        // UTF-8 として不正なバイトがあっても、ページの残りは表示する
+        .with_lenient_decoding(true)
+        // Cookie とキャッシュはすべてのページで共有する
+        .with_cookie_jar(browser.cookie_jar())
+        .with_cache(browser.response_cache())
+        // UI で停止キーが押されると読み込みを中止する
+        .with_cancel_flag(browser.cancel_flag())
+        .with_retry(RetryPolicy::new(NAVIGATION_RETRIES, RETRY_BASE_DELAY_MS), sleep);
+    // リダイレクトを辿った後の URL も含めて UI に返す
+    client.navigate(&parsed_url)
 }
 
 fn main() -> u64 {
     let browser = Browser::new();
+    // ホスト向けのビルドでは、履歴とブックマークを $HOME/.saba に保存して次の起動で読み込む。
+    // noli にはまだファイルに書き込む API がないので、Wasabi ではセッションの間だけ残る
+    #[cfg(target_os = "linux")]
+    browser
+        .borrow_mut()
+        .set_storage(Rc::new(RefCell::new(FileStorage::in_home_dir())));
+
+    let ui = Rc::new(RefCell::new(WasabiUI::new(browser, UiConfig::default())));
 
-    let ui = Rc::new(RefCell::new(WasabiUI::new(browser)));
+    // 同じホストへの読み込みで名前解決を繰り返さないように、キャッシュはすべての読み込みで共有する
+    let dns_cache = Rc::new(RefCell::new(DnsCache::new(NoliResolver, DNS_CACHE_TTL_TICKS)));
+    let handle_url = |browser: &Browser, url: String| handle_url(&dns_cache, browser, url);
 
-    match ui.borrow_mut().start(handle_url) {
+    match ui.borrow_mut().start(&handle_url) {
         Ok(_) => {}
         Err(e) => {
             println!("browser fails to start {:?}", e);
@@ -0,0 +1,118 @@
+use alloc::string::String;
+
+/// Wasabi の read_key は矢印キーのコードを返さないので、Emacs と同じ Ctrl+P と Ctrl+N で候補を選ぶ
+static KEY_UP: char = '\u{10}';
+static KEY_DOWN: char = '\u{0e}';
+/// Ctrl+V
+static KEY_PASTE: char = '\u{16}';
+
+/// The maximum number of history entries shown below the address bar.
+pub static MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyInput {
+    Enter,
+    Delete,
+    Up,
+    Down,
+    Paste,
+    Escape,
+    Char(char),
+    Ignored,
+}
+
+impl KeyInput {
+    /// Classifies a key read while editing the address bar. Only printable ASCII characters are
+    /// typed into the bar; control characters other than Enter, Backspace/Delete, Escape and
+    /// Ctrl+V are ignored.
+    pub fn from_char(c: char) -> Self {
+        if c == KEY_UP {
+            return KeyInput::Up;
+        }
+        if c == KEY_DOWN {
+            return KeyInput::Down;
+        }
+        if c == KEY_PASTE {
+            return KeyInput::Paste;
+        }
+
+        match c {
+            '\n' | '\r' => KeyInput::Enter,
+            '\u{7f}' | '\u{08}' => KeyInput::Delete,
+            '\u{1b}' => KeyInput::Escape,
+            ' '..='~' => KeyInput::Char(c),
+            _ => KeyInput::Ignored,
+        }
+    }
+}
+
+/// Returns the characters of `text` that can be typed into the address bar, dropping the rest.
+pub fn printable_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| matches!(KeyInput::from_char(*c), KeyInput::Char(_)))
+        .collect()
+}
+
+/// Returns the suggestion selected after pressing Up or Down. Moving up from the first
+/// suggestion returns to the typed input.
+pub fn move_selection(selected: Option<usize>, key: KeyInput, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+
+    match (key, selected) {
+        (KeyInput::Down, None) => Some(0),
+        (KeyInput::Down, Some(i)) => Some((i + 1).min(len - 1)),
+        (KeyInput::Up, Some(0)) | (KeyInput::Up, None) => None,
+        (KeyInput::Up, Some(i)) => Some(i - 1),
+        _ => selected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_chars() {
+        assert_eq!(KeyInput::Enter, KeyInput::from_char(0x0A as char));
+        assert_eq!(KeyInput::Delete, KeyInput::from_char(0x7F as char));
+        assert_eq!(KeyInput::Delete, KeyInput::from_char(0x08 as char));
+        assert_eq!(KeyInput::Ignored, KeyInput::from_char(0x00 as char));
+        assert_eq!(KeyInput::Ignored, KeyInput::from_char(0x09 as char));
+        assert_eq!(KeyInput::Escape, KeyInput::from_char(0x1B as char));
+        assert_eq!(KeyInput::Ignored, KeyInput::from_char(0x06 as char));
+        assert_eq!(KeyInput::Ignored, KeyInput::from_char('あ'));
+    }
+
+    #[test]
+    fn test_arrow_keys() {
+        assert_eq!(KeyInput::Up, KeyInput::from_char(KEY_UP));
+        assert_eq!(KeyInput::Down, KeyInput::from_char(KEY_DOWN));
+    }
+
+    #[test]
+    fn test_move_selection() {
+        assert_eq!(Some(0), move_selection(None, KeyInput::Down, 3));
+        assert_eq!(Some(2), move_selection(Some(1), KeyInput::Down, 3));
+        assert_eq!(Some(2), move_selection(Some(2), KeyInput::Down, 3));
+        assert_eq!(Some(0), move_selection(Some(1), KeyInput::Up, 3));
+        assert_eq!(None, move_selection(Some(0), KeyInput::Up, 3));
+        assert_eq!(None, move_selection(None, KeyInput::Up, 3));
+        assert_eq!(None, move_selection(None, KeyInput::Down, 0));
+    }
+
+    #[test]
+    fn test_printable_chars() {
+        for c in "azAZ09:/.?&=-_~%#@!".chars() {
+            assert_eq!(KeyInput::Char(c), KeyInput::from_char(c));
+        }
+    }
+
+    #[test]
+    fn test_paste() {
+        assert_eq!(KeyInput::Paste, KeyInput::from_char(0x16 as char));
+        assert_eq!("http://a.com/", printable_text("http://a.com/\n"));
+        assert_eq!("ab", printable_text("a\tb\u{7f}あ"));
+    }
+}
\ No newline at end of file
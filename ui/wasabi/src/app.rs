@@ -1,7 +1,20 @@
+use crate::address_bar::{move_selection, printable_text, KeyInput, MAX_SUGGESTIONS};
+use crate::clipboard::{Clipboard, EmptyClipboard};
+use crate::clock;
+use crate::close_button::{is_close_button_hit, CloseButton};
+use crate::config::UiConfig;
 use crate::cursor::Cursor;
+use crate::drawer::Drawer;
+use crate::favicon::{favicon_color, FAVICON_SIZE};
+use crate::scroll::{scroll_by_key, ScrollKey};
+use crate::text::{char_height, text_width};
+use crate::zoom::{zoom_by_key, ZoomKey};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::cell::RefCell;
 use noli::error::Result as OsResult;
 use noli::prelude::SystemApi;
@@ -10,56 +23,158 @@ use noli::rect::Rect;
 use noli::sys::api::MouseEvent;
 use noli::sys::wasabi::Api;
 use noli::window::{StringSize, Window};
-use saba_core::browser::Browser;
-use saba_core::constants::{
-    ADDRESSBAR_HEIGHT, BLACK, CONTENT_AREA_HEIGHT, CONTENT_AREA_WIDTH, DARKGRAY, GREY, LIGHTGRAY,
-    TITLE_BAR_HEIGHT, TOOLBAR_HEIGHT, WHITE, WINDOW_HEIGHT, WINDOW_INIT_X_POS, WINDOW_INIT_Y_POS,
-    WINDOW_PADDING, WINDOW_WIDTH,
-};
+use saba_core::browser::{Browser, ABOUT_BLANK};
+use saba_core::constants::CHAR_HEIGHT_WITH_PADDING;
 use saba_core::display_item::DisplayItem;
 use saba_core::error::Error;
-use saba_core::http::HttpResponse;
-use saba_core::renderer::layout::computed_style::{FontSize, TextDecoration};
+use saba_core::http::NavigationResult;
+use saba_core::renderer::page::LoadState;
+use saba_core::renderer::layout::computed_style::{
+    Color, FontSize, FontStyle, FontWeight, TextDecoration,
+};
+use saba_core::renderer::layout::layout_object::drawn_font_percentage;
+
+/// 移動し続けるページでループしないように、続けて従う meta refresh の数を制限する
+const MAX_META_REFRESHES: usize = 5;
+
+/// コンテンツエリアにフォーカスがあるときに、ホームに移動するキー
+const HOME_KEY: char = 'h';
+/// 読み込みの途中で押すと、読み込みを中止するキー (Esc)
+const STOP_KEY: char = '\u{1b}';
+/// 不具合を調べるために、イベントログを出力するキー
+const EVENT_LOG_KEY: char = 'l';
+/// ページにタイトルがないときの、ウィンドウのタイトル
+const APP_NAME: &str = "saba";
 
 #[derive(Debug)]
 pub struct WasabiUI {
     browser: Rc<RefCell<Browser>>,
+    config: UiConfig,
     input_url: String,
-    input_mode: InputMode,
-    window: Window,
+    focus: Focus,
+    clipboard: Box<dyn Clipboard>,
+    window: Box<dyn Drawer>,
+    /// タイトルバーに表示している文字列
+    window_title: String,
+    window_x: i64,
+    window_y: i64,
     cursor: Cursor,
+    close_button: CloseButton,
+    drag_origin: Option<(i64, i64)>,
+    closed: bool,
+    scroll_y: i64,
+    suggestions: Vec<String>,
+    selected_suggestion: Option<usize>,
+    meta_refreshes: usize,
+    /// マウスカーソルの下にあるリンクの URL
+    hovered_link: Option<String>,
+    /// ステータスバーに表示している文字列
+    status_text: String,
+    /// 直前の移動で起きた、読み込みを止めるほどではない問題
+    navigation_warning: Option<String>,
+    /// 読み込みの途中に押された停止キー以外のキー。読み込みが終わった後に押された順に処理する
+    pending_keys: Rc<RefCell<VecDeque<char>>>,
 }
 
 impl WasabiUI {
-    pub fn new(browser: Rc<RefCell<Browser>>) -> Self {
+    pub fn new(browser: Rc<RefCell<Browser>>, config: UiConfig) -> Self {
+        let window = Window::new(
+            APP_NAME.to_string(),
+            config.background_color,
+            config.window_x,
+            config.window_y,
+            config.window_width,
+            config.window_height,
+        )
+        .unwrap();
+        Self::with_drawer(browser, config, Box::new(window))
+    }
+
+    /// Creates a UI that draws through `window` instead of a real window.
+    pub fn with_drawer(
+        browser: Rc<RefCell<Browser>>,
+        config: UiConfig,
+        window: Box<dyn Drawer>,
+    ) -> Self {
+        // イベントログの時刻は、イベントループを回った回数で記録する
+        browser
+            .borrow()
+            .event_log()
+            .borrow_mut()
+            .set_clock(clock::ticks);
+        // ページはウィンドウの大きさに合わせて折り返す
+        browser
+            .borrow()
+            .current_page()
+            .borrow_mut()
+            .set_content_width(config.content_area_width());
+
         Self {
             browser,
+            config,
             input_url: String::new(),
-            input_mode: InputMode::Normal,
-            window: Window::new(
-                "saba".to_string(),
-                WHITE,
-                WINDOW_INIT_X_POS,
-                WINDOW_INIT_Y_POS,
-                WINDOW_WIDTH,
-                WINDOW_HEIGHT,
-            )
-            .unwrap(),
+            focus: Focus::Content,
+            clipboard: Box::new(EmptyClipboard),
+            window,
+            window_title: APP_NAME.to_string(),
+            window_x: config.window_x,
+            window_y: config.window_y,
             cursor: Cursor::new(),
+            close_button: CloseButton::new(),
+            drag_origin: None,
+            closed: false,
+            scroll_y: 0,
+            suggestions: Vec::new(),
+            selected_suggestion: None,
+            meta_refreshes: 0,
+            hovered_link: None,
+            status_text: String::new(),
+            navigation_warning: None,
+            pending_keys: Rc::new(RefCell::new(VecDeque::new())),
         }
     }
+
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.clipboard = clipboard;
+    }
 }
 
 impl WasabiUI {
     pub fn start(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
     ) -> Result<(), Error> {
-        self.setup()?;
+        self.open(handle_url)?;
         self.run_app(handle_url)?;
         Ok(())
     }
 
+    /// Draws the window and loads the home URL of the browser as the start page.
+    fn open(
+        &mut self,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
+    ) -> Result<(), Error> {
+        self.setup()?;
+        // 読み込みはキー入力を待たずに進むので、読み込みの途中で停止キーが押されたかを調べてもらう
+        let pending_keys = self.pending_keys.clone();
+        self.browser
+            .borrow()
+            .cancel_flag()
+            .set_poll(move || poll_stop_key(Api::read_key(), &pending_keys));
+        self.go_home(handle_url)
+    }
+
+    /// Navigates to the home URL of the browser. This is also how the start page is loaded.
+    fn go_home(
+        &mut self,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
+    ) -> Result<(), Error> {
+        let home_url = self.browser.borrow().home_url();
+        self.input_url = home_url.clone();
+        self.update_address_bar()?;
+        self.start_navigation(handle_url, home_url)
+    }
+
     fn setup(&mut self) -> Result<(), Error> {
         if let Err(error) = self.setup_toolbar() {
             return Err(Error::InvalidUI(format!(
@@ -68,58 +183,83 @@ impl WasabiUI {
             )));
         }
 
+        self.update_favicon()?;
+        self.update_status_bar()?;
+
+        self.close_button
+            .set_window_position(self.window_x, self.window_y, &self.config);
+        self.close_button.flush();
+
         self.window.flush();
         Ok(())
     }
 
     fn setup_toolbar(&mut self) -> OsResult<()> {
         // ツールバーの背景の四角を描画
-        self.window
-            .fill_rect(LIGHTGRAY, 0, 0, WINDOW_WIDTH, TOOLBAR_HEIGHT)?;
+        self.window.fill_rect(
+            self.config.toolbar_color,
+            0,
+            0,
+            self.config.window_width,
+            self.config.toolbar_height,
+        )?;
 
         // ツールバーとコンテンツエリアの境目の線を描画
-        self.window
-            .draw_line(GREY, 0, TOOLBAR_HEIGHT, WINDOW_WIDTH - 1, TOOLBAR_HEIGHT)?;
         self.window.draw_line(
-            DARKGRAY,
+            self.config.border_color,
             0,
-            TOOLBAR_HEIGHT + 1,
-            WINDOW_WIDTH - 1,
-            TOOLBAR_HEIGHT + 1,
+            self.config.toolbar_height,
+            self.config.window_width - 1,
+            self.config.toolbar_height,
+        )?;
+        self.window.draw_line(
+            self.config.shadow_color,
+            0,
+            self.config.toolbar_height + 1,
+            self.config.window_width - 1,
+            self.config.toolbar_height + 1,
         )?;
 
         // アドレスバーの横に "Address:" という文字列を描画
         self.window
-            .draw_string(BLACK, 5, 5, "Address:", StringSize::Medium, false)?;
+            .draw_string(self.config.text_color, 5, 5, "Address:", StringSize::Medium, false)?;
 
         // アドレスバーの四角を描画
-        self.window
-            .fill_rect(WHITE, 70, 2, WINDOW_WIDTH - 74, 2 + ADDRESSBAR_HEIGHT)?;
+        self.window.fill_rect(
+            self.config.background_color,
+            70,
+            2,
+            self.config.window_width - 74,
+            2 + self.config.addressbar_height,
+        )?;
 
         // アドレスバーの影の線を描画
-        self.window.draw_line(GREY, 70, 2, WINDOW_WIDTH - 4, 2)?;
+        self.window.draw_line(self.config.border_color, 70, 2, self.config.window_width - 4, 2)?;
         self.window
-            .draw_line(GREY, 70, 2, 70, 2 + ADDRESSBAR_HEIGHT)?;
-        self.window.draw_line(BLACK, 71, 3, WINDOW_WIDTH - 5, 3)?;
+            .draw_line(self.config.border_color, 70, 2, 70, 2 + self.config.addressbar_height)?;
+        self.window.draw_line(self.config.text_color, 71, 3, self.config.window_width - 5, 3)?;
         self.window
-            .draw_line(GREY, 71, 3, 71, 1 + ADDRESSBAR_HEIGHT)?;
+            .draw_line(self.config.border_color, 71, 3, 71, 1 + self.config.addressbar_height)?;
 
         Ok(())
     }
 
     fn run_app(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
     ) -> Result<(), Error> {
-        loop {
+        while !self.closed {
+            clock::tick();
             self.handle_key_input(handle_url)?;
             self.handle_mouse_input(handle_url)?;
         }
+
+        Ok(())
     }
 
     fn handle_mouse_input(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
     ) -> Result<(), Error> {
         if let Some(MouseEvent { button, position }) = Api::get_mouse_cursor_info() {
             self.window.flush_area(self.cursor.rect());
@@ -127,36 +267,54 @@ impl WasabiUI {
             self.window.flush_area(self.cursor.rect());
             self.cursor.flush();
 
+            self.update_hovered_link((position.x - self.window_x, position.y - self.window_y))?;
+
+            if !button.l() {
+                if let Some(origin) = self.drag_origin.take() {
+                    self.move_window(position.x - origin.0, position.y - origin.1)?;
+                }
+            }
+
             if button.l() || button.c() || button.r() {
-                let relative_pos = (
-                    position.x - WINDOW_INIT_X_POS,
-                    position.y - WINDOW_INIT_Y_POS,
-                );
+                let relative_pos = (position.x - self.window_x, position.y - self.window_y);
 
                 if relative_pos.0 < 0
-                    || WINDOW_WIDTH < relative_pos.0
+                    || self.config.window_width < relative_pos.0
                     || relative_pos.1 < 0
-                    || WINDOW_HEIGHT < relative_pos.1
+                    || self.config.window_height < relative_pos.1
                 {
                     println!("button clicked OUTSIDE window: {button:?} {position:?}");
                     return Ok(());
                 }
 
-                if TITLE_BAR_HEIGHT <= relative_pos.1
-                    && relative_pos.1 < TOOLBAR_HEIGHT + TITLE_BAR_HEIGHT
-                {
+                if relative_pos.1 < self.config.title_bar_height {
+                    if is_close_button_hit(relative_pos, &self.config) {
+                        self.closed = true;
+                        return Ok(());
+                    }
+
+                    // ボタンが離されるまでの移動量だけウィンドウを動かす
+                    if button.l() && self.drag_origin.is_none() {
+                        self.drag_origin = Some((position.x, position.y));
+                    }
+                    return Ok(());
+                }
+
+                self.move_focus(relative_pos);
+
+                if self.focus == Focus::AddressBar {
+                    self.browser.borrow().current_page().borrow_mut().blur();
                     self.clear_address_bar()?;
                     self.input_url = String::new();
-                    self.input_mode = InputMode::Editing;
+                    self.update_suggestions()?;
                     println!("button clicked in toolbar: {button:?} {position:?}");
                     return Ok(());
                 }
 
-                self.input_mode = InputMode::Normal;
-
                 let position_in_content_area = (
                     relative_pos.0,
-                    relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
+                    relative_pos.1 - self.config.title_bar_height - self.config.toolbar_height
+                        + self.scroll_y,
                 );
                 let page = self.browser.borrow().current_page();
                 let next_destination = page.borrow_mut().clicked(position_in_content_area);
@@ -164,6 +322,9 @@ impl WasabiUI {
                     self.input_url = url.clone();
                     self.update_address_bar()?;
                     self.start_navigation(handle_url, url)?;
+                } else if !page.borrow_mut().focus_text_at(position_in_content_area) {
+                    // テキスト以外の場所をクリックしたら編集をやめる
+                    page.borrow_mut().blur();
                 }
             }
         }
@@ -171,28 +332,134 @@ impl WasabiUI {
         Ok(())
     }
 
+    /// Shows the URL of the link under the pointer at `relative_pos` in the status bar, and
+    /// clears it when the pointer leaves the link.
+    fn update_hovered_link(&mut self, relative_pos: (i64, i64)) -> Result<(), Error> {
+        let content_top = self.config.title_bar_height + self.config.toolbar_height;
+        let link = if relative_pos.0 < 0
+            || self.config.window_width < relative_pos.0
+            || relative_pos.1 < content_top
+            || self.config.title_bar_height + self.config.status_bar_y() <= relative_pos.1
+        {
+            None
+        } else {
+            // クリックと同じ位置の計算でリンクを探す
+            let position_in_content_area =
+                (relative_pos.0, relative_pos.1 - content_top + self.scroll_y);
+            self.browser
+                .borrow()
+                .current_page()
+                .borrow()
+                .link_at(position_in_content_area)
+        };
+
+        if link == self.hovered_link {
+            return Ok(());
+        }
+        self.hovered_link = link;
+        self.update_status_bar()
+    }
+
+    /// Updates the text of the status bar from the hovered link and the load state, and paints
+    /// it if it has changed.
+    fn update_status_bar(&mut self) -> Result<(), Error> {
+        let load_state = self.browser.borrow().current_page().borrow().load_state();
+        let text = status_text(&self.hovered_link, &self.navigation_warning, load_state);
+        if text == self.status_text {
+            return Ok(());
+        }
+        self.status_text = text;
+        self.paint_status_bar()
+    }
+
+    fn paint_status_bar(&mut self) -> Result<(), Error> {
+        let y = self.config.status_bar_y();
+        let width = self.config.window_width;
+        let height = self.config.status_bar_height;
+
+        if self
+            .window
+            .fill_rect(self.config.toolbar_color, 0, y, width, height)
+            .is_err()
+            || self
+                .window
+                .draw_line(self.config.border_color, 0, y, width - 1, y)
+                .is_err()
+        {
+            return Err(Error::InvalidUI("failed to draw a status bar".to_string()));
+        }
+
+        if self
+            .window
+            .draw_string(
+                self.config.text_color,
+                self.config.window_padding,
+                y + 2,
+                &self.status_text,
+                StringSize::Medium,
+                false,
+            )
+            .is_err()
+        {
+            return Err(Error::InvalidUI("failed to draw a status text".to_string()));
+        }
+
+        self.window.flush_area(
+            Rect::new(
+                self.window_x,
+                self.window_y + self.config.title_bar_height + y,
+                width,
+                height,
+            )
+            .expect("failed to create a rect for the status bar"),
+        );
+
+        Ok(())
+    }
+
+    /// Moves the focus to the region at `relative_pos`. Clicks on the title bar keep the focus.
+    fn move_focus(&mut self, relative_pos: (i64, i64)) {
+        if relative_pos.1 < self.config.title_bar_height {
+            return;
+        }
+
+        if relative_pos.1 < self.config.title_bar_height + self.config.toolbar_height {
+            self.focus = Focus::AddressBar;
+        } else {
+            self.focus = Focus::Content;
+        }
+    }
+
     fn handle_key_input(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
     ) -> Result<(), Error> {
-        match self.input_mode {
-            InputMode::Normal => {
-                // 入力を無視する
-                let _ = Api::read_key();
-            }
-            InputMode::Editing => {
-                if let Some(c) = Api::read_key() {
-                    if c == 0x0A as char {
-                        self.start_navigation(handle_url, self.input_url.clone())?;
-                        self.input_url = String::new();
-                        self.input_mode = InputMode::Normal;
-                    } else if c == 0x7F as char || c == 0x08 as char {
-                        self.input_url.pop();
-                        self.update_address_bar();
-                    } else {
-                        self.input_url.push(c);
-                        self.update_address_bar();
+        match self.focus {
+            Focus::Content => {
+                // スクロール、ズーム、ホームへの移動、ログの出力以外の入力を無視する
+                if let Some(c) = self.read_key() {
+                    // スクロールのキーは文字を入力しないので、テキストの編集中でも使える
+                    if let Some(key) = ScrollKey::from_char(c) {
+                        return self.scroll(key);
                     }
+
+                    let page = self.browser.borrow().current_page();
+                    if page.borrow().has_focused_text() {
+                        return self.handle_text_editing_key(KeyInput::from_char(c));
+                    }
+
+                    if let Some(key) = ZoomKey::from_char(c) {
+                        self.zoom(key)?;
+                    } else if c == HOME_KEY {
+                        self.go_home(handle_url)?;
+                    } else if c == EVENT_LOG_KEY {
+                        println!("{}", self.browser.borrow().event_log().borrow().dump());
+                    }
+                }
+            }
+            Focus::AddressBar => {
+                if let Some(c) = self.read_key() {
+                    self.handle_address_bar_key(handle_url, KeyInput::from_char(c))?;
                 }
             }
         }
@@ -200,10 +467,127 @@ impl WasabiUI {
         Ok(())
     }
 
+    /// 読み込みの途中に押されたキーが残っていればそれを先に返し、なければ新しく押されたキーを読む
+    fn read_key(&self) -> Option<char> {
+        let pending = self.pending_keys.borrow_mut().pop_front();
+        pending.or_else(Api::read_key)
+    }
+
+    /// Routes a key to the text being edited in the page, like the address bar does for the URL.
+    fn handle_text_editing_key(&mut self, key: KeyInput) -> Result<(), Error> {
+        let page = self.browser.borrow().current_page();
+        let edited = match key {
+            KeyInput::Char(c) => page.borrow_mut().insert_char(c),
+            KeyInput::Delete => page.borrow_mut().delete_char(),
+            KeyInput::Escape => {
+                page.borrow_mut().blur();
+                false
+            }
+            _ => false,
+        };
+
+        if edited {
+            self.clear_content_area()?;
+            self.update_ui()?;
+        }
+        Ok(())
+    }
+
+    fn handle_address_bar_key(
+        &mut self,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
+        key: KeyInput,
+    ) -> Result<(), Error> {
+        match key {
+            KeyInput::Enter => {
+                let destination = match self.selected_suggestion {
+                    Some(i) => self.suggestions[i].clone(),
+                    None => self.input_url.clone(),
+                };
+                if !self.suggestions.is_empty() {
+                    self.suggestions = Vec::new();
+                    self.selected_suggestion = None;
+                    self.setup()?;
+                    self.update_address_bar()?;
+                }
+                self.start_navigation(handle_url, destination)?;
+                self.input_url = String::new();
+                self.focus = Focus::Content;
+            }
+            KeyInput::Delete => {
+                self.input_url.pop();
+                self.update_address_bar()?;
+                self.update_suggestions()?;
+            }
+            KeyInput::Up | KeyInput::Down => {
+                self.selected_suggestion = move_selection(
+                    self.selected_suggestion,
+                    key,
+                    self.suggestions.len(),
+                );
+                self.draw_suggestions()?;
+            }
+            KeyInput::Char(c) => {
+                self.input_url.push(c);
+                self.update_address_bar()?;
+                self.update_suggestions()?;
+            }
+            KeyInput::Paste => {
+                self.paste()?;
+            }
+            KeyInput::Escape => {
+                self.cancel_editing()?;
+            }
+            KeyInput::Ignored => {}
+        }
+
+        Ok(())
+    }
+
+    /// Restores the address bar to the URL of the loaded page, or clears it if no page is loaded,
+    /// and stops editing.
+    fn cancel_editing(&mut self) -> Result<(), Error> {
+        let url = self.browser.borrow().current_page().borrow().url();
+        self.input_url = match url {
+            Some(url) => url.to_string(),
+            None => String::new(),
+        };
+        self.focus = Focus::Content;
+
+        // ドロップダウンを閉じて、隠れていた部分を描画し直す
+        if !self.suggestions.is_empty() {
+            self.suggestions = Vec::new();
+            self.selected_suggestion = None;
+            self.setup()?;
+            self.clear_content_area()?;
+            self.update_ui()?;
+        }
+
+        self.update_address_bar()
+    }
+
+    /// Appends the printable part of the clipboard text to the address bar at once.
+    fn paste(&mut self) -> Result<(), Error> {
+        let text = match self.clipboard.read_text() {
+            Some(text) => text,
+            None => return Ok(()),
+        };
+
+        self.input_url.push_str(&printable_text(&text));
+        self.update_address_bar()?;
+        self.update_suggestions()
+    }
+
     fn update_address_bar(&mut self) -> Result<(), Error> {
         if self
             .window
-            .fill_rect(WHITE, 72, 4, WINDOW_WIDTH - 76, ADDRESSBAR_HEIGHT - 2)
+            .fill_rect(
+                self.config.background_color,
+                72,
+                4,
+                self.config.window_width - 76,
+                self.config.addressbar_height - 2,
+            )
             .is_err()
         {
             return Err(Error::InvalidUI(
@@ -213,7 +597,7 @@ impl WasabiUI {
 
         if self
             .window
-            .draw_string(BLACK, 74, 6, &self.input_url, StringSize::Medium, false)
+            .draw_string(self.config.text_color, 74, 6, &self.input_url, StringSize::Medium, false)
             .is_err()
         {
             return Err(Error::InvalidUI(
@@ -223,21 +607,27 @@ impl WasabiUI {
 
         self.window.flush_area(
             Rect::new(
-                WINDOW_INIT_X_POS,
-                WINDOW_INIT_Y_POS + TITLE_BAR_HEIGHT,
-                WINDOW_WIDTH,
-                TOOLBAR_HEIGHT,
+                self.window_x,
+                self.window_y + self.config.title_bar_height,
+                self.config.window_width,
+                self.config.toolbar_height,
             )
             .expect("failed to create a rect for the address bar"),
         );
 
-        Ok(())
+        self.update_favicon()
     }
 
     fn clear_address_bar(&mut self) -> Result<(), Error> {
         if self
             .window
-            .fill_rect(WHITE, 72, 4, WINDOW_WIDTH - 76, ADDRESSBAR_HEIGHT - 2)
+            .fill_rect(
+                self.config.background_color,
+                72,
+                4,
+                self.config.window_width - 76,
+                self.config.addressbar_height - 2,
+            )
             .is_err()
         {
             return Err(Error::InvalidUI(
@@ -247,26 +637,200 @@ impl WasabiUI {
 
         self.window.flush_area(
             Rect::new(
-                WINDOW_INIT_X_POS,
-                WINDOW_INIT_Y_POS + TITLE_BAR_HEIGHT,
-                WINDOW_WIDTH,
-                TOOLBAR_HEIGHT,
+                self.window_x,
+                self.window_y + self.config.title_bar_height,
+                self.config.window_width,
+                self.config.toolbar_height,
             )
             .expect("failed to create a rect for the address bar"),
         );
 
+        self.update_favicon()
+    }
+
+    fn update_suggestions(&mut self) -> Result<(), Error> {
+        let had_suggestions = !self.suggestions.is_empty();
+        self.suggestions = self
+            .browser
+            .borrow()
+            .history()
+            .suggestions(&self.input_url, MAX_SUGGESTIONS);
+        self.selected_suggestion = None;
+
+        // ドロップダウンが縮んだり消えたりした部分のツールバーとコンテンツを描画し直す
+        if had_suggestions {
+            self.setup()?;
+            self.update_address_bar()?;
+            self.clear_content_area()?;
+            self.update_ui()?;
+        }
+
+        self.draw_suggestions()
+    }
+
+    fn draw_suggestions(&mut self) -> Result<(), Error> {
+        if self.suggestions.is_empty() {
+            return Ok(());
+        }
+
+        for (i, suggestion) in self.suggestions.iter().enumerate() {
+            let y = 2 + self.config.addressbar_height + CHAR_HEIGHT_WITH_PADDING * i as i64;
+            let background = if self.selected_suggestion == Some(i) {
+                self.config.toolbar_color
+            } else {
+                self.config.background_color
+            };
+
+            if self
+                .window
+                .fill_rect(
+                    background,
+                    70,
+                    y,
+                    self.config.window_width - 74,
+                    CHAR_HEIGHT_WITH_PADDING,
+                )
+                .is_err()
+                || self
+                    .window
+                    .draw_line(self.config.border_color, 70, y, 70, y + CHAR_HEIGHT_WITH_PADDING)
+                    .is_err()
+                || self
+                    .window
+                    .draw_line(
+                        self.config.border_color,
+                        self.config.window_width - 5,
+                        y,
+                        self.config.window_width - 5,
+                        y + CHAR_HEIGHT_WITH_PADDING,
+                    )
+                    .is_err()
+                || self
+                    .window
+                    .draw_string(
+                        self.config.text_color,
+                        74,
+                        y + 2,
+                        suggestion,
+                        StringSize::Medium,
+                        false,
+                    )
+                    .is_err()
+            {
+                return Err(Error::InvalidUI(
+                    "failed to draw address bar suggestions".to_string(),
+                ));
+            }
+        }
+
+        let bottom = 2
+            + self.config.addressbar_height
+            + CHAR_HEIGHT_WITH_PADDING * self.suggestions.len() as i64;
+        if self
+            .window
+            .draw_line(self.config.border_color, 70, bottom, self.config.window_width - 5, bottom)
+            .is_err()
+        {
+            return Err(Error::InvalidUI(
+                "failed to draw address bar suggestions".to_string(),
+            ));
+        }
+
+        self.window.flush();
+
         Ok(())
     }
 
+    fn move_window(&mut self, dx: i64, dy: i64) -> Result<(), Error> {
+        if dx == 0 && dy == 0 {
+            return Ok(());
+        }
+
+        self.window_x += dx;
+        self.window_y += dy;
+        self.reopen_window()
+    }
+
+    /// Shows the title of the current page in the title bar.
+    fn update_window_title(&mut self) -> Result<(), Error> {
+        let title = window_title(&self.browser.borrow().current_page().borrow().title());
+        if title == self.window_title {
+            return Ok(());
+        }
+
+        self.window_title = title;
+        self.reopen_window()
+    }
+
+    /// Opens the window again at the current position with the current title, and draws
+    /// everything in it.
+    fn reopen_window(&mut self) -> Result<(), Error> {
+        if let Err(e) = self.window.reopen(
+            &self.window_title,
+            self.config.background_color,
+            self.window_x,
+            self.window_y,
+            self.config.window_width,
+            self.config.window_height,
+        ) {
+            return Err(Error::InvalidUI(format!(
+                "failed to open a window with error: {:#?}",
+                e
+            )));
+        }
+
+        self.setup()?;
+        // 新しいウィンドウには、文字列が変わっていなくてもステータスバーを描く
+        self.paint_status_bar()?;
+        self.update_address_bar()?;
+        self.update_progress_bar()?;
+        self.update_ui()
+    }
+
+    fn scroll(&mut self, key: ScrollKey) -> Result<(), Error> {
+        let content_height = self
+            .browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .content_height();
+        let scroll_y = scroll_by_key(
+            key,
+            self.scroll_y,
+            content_height,
+            self.config.content_area_height(),
+        );
+        if scroll_y == self.scroll_y {
+            return Ok(());
+        }
+
+        self.scroll_y = scroll_y;
+        self.clear_content_area()?;
+        self.update_ui()
+    }
+
+    fn zoom(&mut self, key: ZoomKey) -> Result<(), Error> {
+        let page = self.browser.borrow().current_page();
+        let zoom = zoom_by_key(key, page.borrow().zoom());
+        if zoom == page.borrow().zoom() {
+            return Ok(());
+        }
+
+        page.borrow_mut().set_zoom(zoom);
+        self.scroll_y = 0;
+        self.clear_content_area()?;
+        self.update_ui()
+    }
+
     fn clear_content_area(&mut self) -> Result<(), Error> {
         if self
             .window
             .fill_rect(
-                WHITE,
+                self.config.background_color,
                 0,
-                TOOLBAR_HEIGHT + 2,
-                CONTENT_AREA_WIDTH,
-                CONTENT_AREA_HEIGHT - 2,
+                self.config.toolbar_height + 2,
+                self.config.content_area_width(),
+                self.config.content_area_height() - 2,
             )
             .is_err()
         {
@@ -282,33 +846,192 @@ impl WasabiUI {
 
     fn start_navigation(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: &dyn Fn(&Browser, String) -> Result<NavigationResult, Error>,
         destination: String,
     ) -> Result<(), Error> {
         self.clear_content_area()?;
+        self.scroll_y = 0;
+        self.navigation_warning = None;
 
-        match handle_url(destination) {
-            Ok(response) => {
-                let page = self.browser.borrow().current_page();
-                page.borrow_mut().receive_response(response);
+        let page = self.browser.borrow().current_page();
+        page.borrow_mut().begin_navigation(&destination);
+        // 前の読み込みを中止していても、新しい読み込みは始められるようにする
+        self.browser.borrow().cancel_flag().reset();
+        self.update_progress_bar()?;
+        self.update_favicon()?;
+        self.update_status_bar()?;
+
+        // about:blank はネットワークにアクセスせず、空のページを表示する
+        if destination == ABOUT_BLANK {
+            page.borrow_mut().load_from_str("");
+            self.update_window_title()?;
+            self.update_progress_bar()?;
+            self.update_favicon()?;
+            self.update_status_bar()?;
+            return self.update_ui();
+        }
+
+        let browser = self.browser.clone();
+        let result = handle_url(&browser.borrow(), destination.clone());
+        match result {
+            Ok(result) => {
+                let url = result.url();
+                for warning in result.warnings() {
+                    println!("warning while loading {}: {}", destination, warning);
+                }
+                self.navigation_warning = result.warnings().first().cloned();
+                // 外部スタイルシートの取得に失敗しても、ページの描画は続ける
+                page.borrow_mut().receive_navigation_result(result, &mut |url| {
+                    let result = handle_url(&browser.borrow(), url.to_string())
+                        .map(|result| result.response());
+                    if let Err(e) = &result {
+                        println!("failed to fetch {}: {:?}", url, e);
+                    }
+                    result
+                });
+                // アドレスバーにはリダイレクト後の URL を表示する
+                self.input_url = url.to_string();
+                self.update_address_bar()?;
+                if let Err(e) = self.browser.borrow_mut().push_history(url.to_string()) {
+                    println!("failed to save history: {:?}", e);
+                }
+            }
+            Err(Error::Aborted) => {
+                // 中止された場合は、読み込む前のページとアドレスを表示し直す
+                page.borrow_mut().set_load_state(LoadState::Idle);
+                self.update_progress_bar()?;
+                self.update_favicon()?;
+                self.update_status_bar()?;
+                self.input_url = match page.borrow().url() {
+                    Some(url) => url.to_string(),
+                    None => String::new(),
+                };
+                self.update_address_bar()?;
+                return self.update_ui();
             }
             Err(e) => {
-                return Err(e);
+                // 不正な URL などで読み込めなくても、ブラウザは終了せずにエラーページを表示する
+                println!("failed to load {}: {:?}", destination, e);
+                page.borrow_mut().load_from_str(&error_page(&destination, &e));
+                page.borrow_mut().set_load_state(LoadState::Failed);
+                self.update_window_title()?;
+                self.update_progress_bar()?;
+                self.update_favicon()?;
+                self.update_status_bar()?;
+                return self.update_ui();
             }
         }
 
+        self.update_window_title()?;
+        self.update_progress_bar()?;
+        self.update_favicon()?;
+        self.update_status_bar()?;
         self.update_ui()?;
 
+        // <meta http-equiv="refresh" content="0;url=..."> のページは、すぐに次の URL に移動する
+        let refresh_url = page.borrow().refresh_url();
+        if let Some(url) = refresh_url {
+            if self.meta_refreshes < MAX_META_REFRESHES {
+                self.meta_refreshes += 1;
+                let result = self.start_navigation(handle_url, url.to_string());
+                self.meta_refreshes -= 1;
+                return result;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_favicon(&mut self) -> Result<(), Error> {
+        let load_state = self.browser.borrow().current_page().borrow().load_state();
+
+        // アドレスバーの右端に、読み込み状態を表す四角を描画
+        let x = self.config.window_width - 8 - FAVICON_SIZE;
+        let y = 2 + (self.config.addressbar_height - FAVICON_SIZE) / 2;
+        if self
+            .window
+            .fill_rect(self.config.border_color, x - 1, y - 1, FAVICON_SIZE + 2, FAVICON_SIZE + 2)
+            .is_err()
+            || self
+                .window
+                .fill_rect(favicon_color(load_state), x, y, FAVICON_SIZE, FAVICON_SIZE)
+                .is_err()
+        {
+            return Err(Error::InvalidUI("failed to draw a favicon".to_string()));
+        }
+
+        self.window.flush_area(
+            Rect::new(
+                self.window_x,
+                self.window_y + self.config.title_bar_height,
+                self.config.window_width,
+                self.config.toolbar_height,
+            )
+            .expect("failed to create a rect for the favicon"),
+        );
+
+        Ok(())
+    }
+
+    fn update_progress_bar(&mut self) -> Result<(), Error> {
+        let load_state = self.browser.borrow().current_page().borrow().load_state();
+
+        // 前回の描画を消す
+        if self
+            .window
+            .fill_rect(
+                self.config.background_color,
+                0,
+                self.config.toolbar_height + 2,
+                self.config.window_width,
+                self.config.progress_bar_height,
+            )
+            .is_err()
+        {
+            return Err(Error::InvalidUI(
+                "failed to clear a progress bar".to_string(),
+            ));
+        }
+
+        if load_state.is_loading() {
+            // 長さがわからない場合は、バーの一部だけを描画する
+            let width = match load_state.progress() {
+                Some(progress) => (self.config.window_width as f64 * progress) as i64,
+                None => self.config.window_width / 4,
+            };
+            if self
+                .window
+                .fill_rect(
+                    self.config.shadow_color,
+                    0,
+                    self.config.toolbar_height + 2,
+                    width,
+                    self.config.progress_bar_height,
+                )
+                .is_err()
+            {
+                return Err(Error::InvalidUI(
+                    "failed to draw a progress bar".to_string(),
+                ));
+            }
+        }
+
+        self.window.flush_area(
+            Rect::new(
+                self.window_x,
+                self.window_y + self.config.title_bar_height + self.config.toolbar_height + 2,
+                self.config.window_width,
+                self.config.progress_bar_height,
+            )
+            .expect("failed to create a rect for the progress bar"),
+        );
+
         Ok(())
     }
 
     fn update_ui(&mut self) -> Result<(), Error> {
-        let display_items = self
-            .browser
-            .borrow()
-            .current_page()
-            .borrow()
-            .display_items();
+        let display_items = self.browser.borrow().display_list();
+        let zoom = self.browser.borrow().current_page().borrow().zoom();
 
         for item in display_items {
             println!("{:#?}", item);
@@ -318,19 +1041,70 @@ impl WasabiUI {
                     style,
                     layout_point,
                 } => {
-                    if self
-                        .window
-                        .draw_string(
-                            style.color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            &text,
-                            convert_font_size(style.font_size()),
-                            style.text_decoration() == TextDecoration::Underline,
-                        )
-                        .is_err()
+                    // 表示領域に収まらないテキストは、ステータスバーに重ならないように描画しない
+                    let size = convert_font_size(style.font_size(), zoom);
+                    let y = layout_point.y() - self.scroll_y;
+                    if y < 0 || self.config.content_area_height() < y + char_height(size) {
+                        continue;
+                    }
+
+                    let x = layout_point.x() + self.config.window_padding;
+                    let y = y + self.config.window_padding + self.config.toolbar_height;
+                    // Wasabi のフォントには太字と斜体がないので、太字は 1px ずらして重ね書きし、
+                    // 斜体は色の濃さを変えて表す
+                    let color = match style.font_style() {
+                        FontStyle::Italic => italic_color(style.color().code_u32()),
+                        FontStyle::Normal => style.color().code_u32(),
+                    };
+                    let offsets: &[i64] = match style.font_weight() {
+                        FontWeight::Bold => &[0, 1],
+                        FontWeight::Normal => &[0],
+                    };
+                    // <code> などの背景色は文字列の後ろに塗る。Wasabi のフォントは元々等幅なので、
+                    // 等幅フォントのための切り替えはしない
+                    if style.background_color() != Color::white()
+                        && self
+                            .window
+                            .fill_rect(
+                                style.background_color().code_u32(),
+                                x,
+                                y,
+                                text_width(&text, size),
+                                char_height(size),
+                            )
+                            .is_err()
                     {
-                        return Err(Error::InvalidUI("failed to draw a string".to_string()));
+                        return Err(Error::InvalidUI(
+                            "failed to draw a text background".to_string(),
+                        ));
+                    }
+                    for offset in offsets {
+                        if self
+                            .window
+                            .draw_string(color, x + offset, y, &text, size, false)
+                            .is_err()
+                        {
+                            return Err(Error::InvalidUI("failed to draw a string".to_string()));
+                        }
+                    }
+
+                    // 下線は文字列の幅に合わせて、文字のすぐ下に引く
+                    if style.text_decoration() == TextDecoration::Underline {
+                        let width = text_width(&text, size);
+                        let underline_y = y + char_height(size);
+                        if self
+                            .window
+                            .draw_line(
+                                style.color().code_u32(),
+                                x,
+                                underline_y,
+                                x + width,
+                                underline_y,
+                            )
+                            .is_err()
+                        {
+                            return Err(Error::InvalidUI("failed to draw an underline".to_string()));
+                        }
                     }
                 }
                 DisplayItem::Rect {
@@ -338,14 +1112,22 @@ impl WasabiUI {
                     layout_point,
                     layout_size,
                 } => {
+                    // 表示領域に収まる部分だけを描画する
+                    let top = (layout_point.y() - self.scroll_y).max(0);
+                    let bottom = (layout_point.y() - self.scroll_y + layout_size.height())
+                        .min(self.config.content_area_height());
+                    if bottom <= top {
+                        continue;
+                    }
+
                     if self
                         .window
                         .fill_rect(
                             style.background_color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                            layout_point.x() + self.config.window_padding,
+                            top + self.config.window_padding + self.config.toolbar_height,
                             layout_size.width(),
-                            layout_size.height(),
+                            bottom - top,
                         )
                         .is_err()
                     {
@@ -361,16 +1143,815 @@ impl WasabiUI {
     }
 }
 
+/// The region that receives key input.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum InputMode {
-    Normal,
-    Editing,
+enum Focus {
+    /// アドレスバーで URL を編集している
+    AddressBar,
+    Content,
+}
+
+/// レイアウトで使った倍率と同じ大きさのフォントを選ぶ
+fn convert_font_size(size: FontSize, zoom: i64) -> StringSize {
+    match drawn_font_percentage(size, zoom) {
+        100 => StringSize::Medium,
+        200 => StringSize::Large,
+        _ => StringSize::XLarge,
+    }
+}
+
+/// 読み込みの途中に押された `key` が停止キーかを返す。
+/// それ以外のキーは捨てずに、読み込みが終わった後に処理するため `pending_keys` に取っておく
+fn poll_stop_key(key: Option<char>, pending_keys: &RefCell<VecDeque<char>>) -> bool {
+    match key {
+        Some(STOP_KEY) => true,
+        Some(c) => {
+            pending_keys.borrow_mut().push_back(c);
+            false
+        }
+        None => false,
+    }
 }
 
-fn convert_font_size(size: FontSize) -> StringSize {
-    match size {
-        FontSize::Medium => StringSize::Medium,
-        FontSize::XLarge => StringSize::Large,
-        FontSize::XXLarge => StringSize::XLarge,
+/// タイトルバーに表示する文字列。ページのタイトルの後にブラウザの名前を付ける
+fn window_title(page_title: &str) -> String {
+    if page_title.is_empty() {
+        return APP_NAME.to_string();
+    }
+    format!("{} - {}", page_title, APP_NAME)
+}
+
+/// 読み込みに失敗したときに表示するページ
+fn error_page(url: &str, error: &Error) -> String {
+    let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!(
+        "<html><head><title>Error</title></head><body><h1>Failed to load the page</h1><p>{}</p><p>{}</p></body></html>",
+        escape(url),
+        escape(&format!("{:?}", error))
+    )
+}
+
+/// ステータスバーに表示する文字列。リンクの上にカーソルがあればその URL を、なければ読み込み状態を表示する。
+/// 読み込みが終わったときに警告があれば、"Done" の代わりに警告を表示する
+fn status_text(
+    hovered_link: &Option<String>,
+    warning: &Option<String>,
+    load_state: LoadState,
+) -> String {
+    if let Some(url) = hovered_link {
+        return url.clone();
+    }
+    if let (Some(warning), LoadState::Complete) = (warning, load_state) {
+        return warning.clone();
+    }
+
+    match load_state {
+        LoadState::Idle => "Ready".to_string(),
+        LoadState::Loading {
+            bytes_received,
+            content_length: Some(content_length),
+        } => format!("Loading... {}/{} bytes", bytes_received, content_length),
+        LoadState::Loading { .. } => "Loading...".to_string(),
+        LoadState::Complete => "Done".to_string(),
+        LoadState::Failed => "Failed to load the page".to_string(),
+    }
+}
+
+/// 斜体の代わりに使う色。各色成分を灰色 (0x80) との中間にする
+fn italic_color(color: u32) -> u32 {
+    let mut result = 0;
+    for shift in [16, 8, 0] {
+        let c = (color >> shift) & 0xff;
+        result |= ((c + 0x80) / 2) << shift;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::TextClipboard;
+    use crate::drawer::{DrawCall, RecordingDrawer};
+    use alloc::vec;
+    use saba_core::constants::{CHAR_HEIGHT, CHAR_WIDTH};
+    use saba_core::event_log::LogEvent;
+    use saba_core::http::HttpResponse;
+    use saba_core::url::Url;
+
+    fn create_ui(config: UiConfig) -> (WasabiUI, Rc<RefCell<Vec<DrawCall>>>) {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let ui = WasabiUI::with_drawer(
+            Browser::new(),
+            config,
+            Box::new(RecordingDrawer::new(calls.clone())),
+        );
+        (ui, calls)
+    }
+
+    #[test]
+    fn test_setup_toolbar() {
+        let config = UiConfig::default();
+        let (mut ui, calls) = create_ui(config);
+        ui.setup_toolbar().expect("failed to set up a toolbar");
+
+        assert_eq!(
+            vec![
+                DrawCall::FillRect {
+                    color: config.toolbar_color,
+                    x: 0,
+                    y: 0,
+                    width: config.window_width,
+                    height: config.toolbar_height,
+                },
+                DrawCall::DrawLine {
+                    color: config.border_color,
+                    x0: 0,
+                    y0: config.toolbar_height,
+                    x1: config.window_width - 1,
+                    y1: config.toolbar_height,
+                },
+                DrawCall::DrawLine {
+                    color: config.shadow_color,
+                    x0: 0,
+                    y0: config.toolbar_height + 1,
+                    x1: config.window_width - 1,
+                    y1: config.toolbar_height + 1,
+                },
+                DrawCall::DrawString {
+                    color: config.text_color,
+                    x: 5,
+                    y: 5,
+                    text: "Address:".to_string(),
+                    size: StringSize::Medium,
+                    underline: false,
+                },
+                DrawCall::FillRect {
+                    color: config.background_color,
+                    x: 70,
+                    y: 2,
+                    width: config.window_width - 74,
+                    height: 2 + config.addressbar_height,
+                },
+                DrawCall::DrawLine {
+                    color: config.border_color,
+                    x0: 70,
+                    y0: 2,
+                    x1: config.window_width - 4,
+                    y1: 2,
+                },
+                DrawCall::DrawLine {
+                    color: config.border_color,
+                    x0: 70,
+                    y0: 2,
+                    x1: 70,
+                    y1: 2 + config.addressbar_height,
+                },
+                DrawCall::DrawLine {
+                    color: config.text_color,
+                    x0: 71,
+                    y0: 3,
+                    x1: config.window_width - 5,
+                    y1: 3,
+                },
+                DrawCall::DrawLine {
+                    color: config.border_color,
+                    x0: 71,
+                    y0: 3,
+                    x1: 71,
+                    y1: 1 + config.addressbar_height,
+                },
+            ],
+            *calls.borrow()
+        );
+    }
+
+    #[test]
+    fn test_custom_window_size() {
+        let config = UiConfig {
+            window_width: 200,
+            window_height: 150,
+            toolbar_height: 20,
+            background_color: 0x123456,
+            ..UiConfig::default()
+        };
+        let (mut ui, calls) = create_ui(config);
+
+        ui.setup_toolbar().expect("failed to set up a toolbar");
+        assert_eq!(
+            Some(&DrawCall::FillRect {
+                color: config.toolbar_color,
+                x: 0,
+                y: 0,
+                width: 200,
+                height: 20,
+            }),
+            calls.borrow().first()
+        );
+
+        calls.borrow_mut().clear();
+        ui.clear_content_area().expect("failed to clear a content area");
+        assert_eq!(
+            Some(&DrawCall::FillRect {
+                color: 0x123456,
+                x: 0,
+                y: 22,
+                width: 200 - config.window_padding * 2,
+                height: 150
+                    - config.title_bar_height
+                    - 20
+                    - config.status_bar_height
+                    - config.window_padding * 2
+                    - 2,
+            }),
+            calls.borrow().first()
+        );
+
+        // ページもウィンドウの幅で折り返す
+        ui.browser.borrow_mut().load_from_str(
+            "<html><body><p>aaaa bbbb cccc dddd eeee ffff gggg hhhh iiii jjjj</p></body></html>",
+        );
+        let lines: Vec<String> = ui
+            .browser
+            .borrow()
+            .display_list()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert!(lines.len() > 1);
+        for line in lines {
+            assert!(CHAR_WIDTH * line.len() as i64 <= config.content_area_width());
+        }
+    }
+
+    #[test]
+    fn test_focus_on_click() {
+        let config = UiConfig::default();
+        let (mut ui, _calls) = create_ui(config);
+        assert_eq!(Focus::Content, ui.focus);
+
+        // ツールバーをクリックするとアドレスバーにフォーカスが移る
+        ui.move_focus((100, config.title_bar_height + 1));
+        assert_eq!(Focus::AddressBar, ui.focus);
+
+        // タイトルバーのクリックではフォーカスは変わらない
+        ui.move_focus((100, 1));
+        assert_eq!(Focus::AddressBar, ui.focus);
+
+        // コンテンツエリアをクリックするとコンテンツにフォーカスが移る
+        ui.move_focus((100, config.title_bar_height + config.toolbar_height));
+        assert_eq!(Focus::Content, ui.focus);
+
+        ui.move_focus((100, 1));
+        assert_eq!(Focus::Content, ui.focus);
+    }
+
+    /// `url` から `html` を読み込んだ結果を作る
+    fn navigation_result(url: &str, html: &str) -> Result<NavigationResult, Error> {
+        let url = Url::new(url.to_string()).parse().map_err(Error::UnexpectedInput)?;
+        let response = HttpResponse::new(format!(
+            "HTTP/1.1 200 OK\nContent-Length: {}\n\n{}",
+            html.len(),
+            html
+        ))?;
+        Ok(NavigationResult::new(url, response, Vec::new()))
+    }
+
+    fn no_network(_browser: &Browser, _url: String) -> Result<NavigationResult, Error> {
+        Err(Error::Network("no network in tests".to_string()))
+    }
+
+    #[test]
+    fn test_paste() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.move_focus((100, ui.config.title_bar_height + 1));
+        ui.set_clipboard(Box::new(TextClipboard::new("http://a.com\n".to_string())));
+
+        ui.handle_address_bar_key(&no_network, KeyInput::Paste)
+            .expect("failed to paste");
+        assert_eq!("http://a.com", ui.input_url);
+    }
+
+    #[test]
+    fn test_escape_restores_url() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        let url = Url::new("http://example.com/index.html".to_string())
+            .parse()
+            .expect("failed to parse url");
+        ui.browser.borrow().current_page().borrow_mut().set_url(url);
+
+        ui.move_focus((100, ui.config.title_bar_height + 1));
+        for c in "http://ex".chars() {
+            ui.handle_address_bar_key(&no_network, KeyInput::Char(c))
+                .expect("failed to type");
+        }
+        assert_eq!("http://ex", ui.input_url);
+
+        ui.handle_address_bar_key(&no_network, KeyInput::Escape)
+            .expect("failed to cancel editing");
+        assert_eq!("http://example.com/index.html", ui.input_url);
+        assert_eq!(Focus::Content, ui.focus);
+    }
+
+    #[test]
+    fn test_edit_page_text() {
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.browser
+            .borrow_mut()
+            .load_from_str("<html><body>ab</body></html>");
+        let page = ui.browser.borrow().current_page();
+        // 2 文字目の後ろをクリックすると、キャレットはテキストの末尾に置かれる
+        assert!(page.borrow_mut().focus_text_at((CHAR_WIDTH * 2, 1)));
+
+        ui.handle_text_editing_key(KeyInput::Char('c'))
+            .expect("failed to type");
+        assert!(calls.borrow().contains(&DrawCall::DrawString {
+            color: 0x000000,
+            x: ui.config.window_padding,
+            y: ui.config.window_padding + ui.config.toolbar_height,
+            text: "abc".to_string(),
+            size: StringSize::Medium,
+            underline: false,
+        }));
+
+        ui.handle_text_editing_key(KeyInput::Escape)
+            .expect("failed to stop editing");
+        assert!(!page.borrow().has_focused_text());
+    }
+
+    #[test]
+    fn test_clip_text_at_bottom() {
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.browser.borrow_mut().load_from_str("<html><body>ab</body></html>");
+        let draws_text = |calls: &Rc<RefCell<Vec<DrawCall>>>| {
+            calls
+                .borrow()
+                .iter()
+                .any(|call| matches!(call, DrawCall::DrawString { text, .. } if text == "ab"))
+        };
+
+        // 文字の下端がステータスバーに重なる位置では描画しない
+        ui.scroll_y = -(ui.config.content_area_height() - CHAR_HEIGHT + 1);
+        ui.update_ui().expect("failed to update ui");
+        assert!(!draws_text(&calls));
+
+        // コンテンツエリアにちょうど収まる位置なら描画する
+        ui.scroll_y = -(ui.config.content_area_height() - CHAR_HEIGHT);
+        ui.update_ui().expect("failed to update ui");
+        assert!(draws_text(&calls));
+    }
+
+    #[test]
+    fn test_escape_clears_without_page() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.move_focus((100, ui.config.title_bar_height + 1));
+        ui.handle_address_bar_key(&no_network, KeyInput::Char('a'))
+            .expect("failed to type");
+
+        ui.handle_address_bar_key(&no_network, KeyInput::Escape)
+            .expect("failed to cancel editing");
+        assert_eq!("", ui.input_url);
+        assert_eq!(Focus::Content, ui.focus);
+    }
+
+    #[test]
+    fn test_link_underline() {
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.browser
+            .borrow_mut()
+            .load_from_str("<html><body><a href=\"/next\">next</a></body></html>");
+        ui.update_ui().expect("failed to update ui");
+
+        let blue = 0x0000ff;
+        let x = ui.config.window_padding;
+        let y = ui.config.window_padding + ui.config.toolbar_height;
+        let calls = calls.borrow();
+        assert!(calls.contains(&DrawCall::DrawString {
+            color: blue,
+            x,
+            y,
+            text: "next".to_string(),
+            size: StringSize::Medium,
+            underline: false,
+        }));
+        assert!(calls.contains(&DrawCall::DrawLine {
+            color: blue,
+            x0: x,
+            y0: y + CHAR_HEIGHT,
+            x1: x + CHAR_WIDTH * 4,
+            y1: y + CHAR_HEIGHT,
+        }));
+    }
+
+    #[test]
+    fn test_bold_and_italic() {
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.browser
+            .borrow_mut()
+            .load_from_str("<html><body><b><i>both</i></b></body></html>");
+        ui.update_ui().expect("failed to update ui");
+
+        let x = ui.config.window_padding;
+        let y = ui.config.window_padding + ui.config.toolbar_height;
+        let calls = calls.borrow();
+        for x in [x, x + 1] {
+            assert!(calls.contains(&DrawCall::DrawString {
+                color: 0x404040,
+                x,
+                y,
+                text: "both".to_string(),
+                size: StringSize::Medium,
+                underline: false,
+            }));
+        }
+    }
+
+    #[test]
+    fn test_code_background() {
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.browser
+            .borrow_mut()
+            .load_from_str("<html><body><code>a  b</code></body></html>");
+        ui.update_ui().expect("failed to update ui");
+
+        let x = ui.config.window_padding;
+        let y = ui.config.window_padding + ui.config.toolbar_height;
+        let calls = calls.borrow();
+        assert!(calls.contains(&DrawCall::FillRect {
+            color: 0xd3d3d3,
+            x,
+            y,
+            width: text_width("a  b", StringSize::Medium),
+            height: char_height(StringSize::Medium),
+        }));
+        assert!(calls.contains(&DrawCall::DrawString {
+            color: 0x000000,
+            x,
+            y,
+            text: "a  b".to_string(),
+            size: StringSize::Medium,
+            underline: false,
+        }));
+    }
+
+    #[test]
+    fn test_italic_color() {
+        assert_eq!(0x404040, italic_color(0x000000));
+        assert_eq!(0x4040bf, italic_color(0x0000ff));
+        assert_eq!(0xbfbfbf, italic_color(0xffffff));
+    }
+
+    #[test]
+    fn test_convert_font_size_with_zoom() {
+        assert_eq!(StringSize::Medium, convert_font_size(FontSize::Medium, 100));
+        assert_eq!(StringSize::Large, convert_font_size(FontSize::Medium, 200));
+        assert_eq!(StringSize::Medium, convert_font_size(FontSize::XLarge, 50));
+        assert_eq!(StringSize::XLarge, convert_font_size(FontSize::XLarge, 150));
+    }
+
+    fn aborted(_browser: &Browser, _url: String) -> Result<NavigationResult, Error> {
+        Err(Error::Aborted)
+    }
+
+    #[test]
+    fn test_aborted_navigation() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.input_url = "http://example.com/".to_string();
+
+        ui.start_navigation(&aborted, "http://example.com/".to_string())
+            .expect("an aborted navigation should not be an error");
+        let page = ui.browser.borrow().current_page();
+        assert_eq!(LoadState::Idle, page.borrow().load_state());
+        assert_eq!("", ui.input_url);
+    }
+
+    /// 停止キーが押されていれば中止し、そうでなければページを読み込む
+    fn cancellable(browser: &Browser, url: String) -> Result<NavigationResult, Error> {
+        if browser.cancel_flag().is_cancelled() {
+            return Err(Error::Aborted);
+        }
+        navigation_result(&url, "<html><body>loaded</body></html>")
+    }
+
+    #[test]
+    fn test_stop_loading() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        let flag = ui.browser.borrow().cancel_flag();
+
+        // 前の読み込みを中止していても、次の読み込みは中止されない
+        flag.cancel();
+        ui.start_navigation(&cancellable, "http://example.com/".to_string())
+            .expect("failed to navigate");
+        let page = ui.browser.borrow().current_page();
+        assert_eq!(LoadState::Complete, page.borrow().load_state());
+
+        // 読み込みの途中で停止キーが押されると、読み込む前のページに戻る
+        flag.set_poll(|| true);
+        ui.start_navigation(&cancellable, "http://example.com/next".to_string())
+            .expect("a stopped navigation should not be an error");
+        assert_eq!(LoadState::Idle, page.borrow().load_state());
+        assert_eq!("http://example.com/", ui.input_url);
+    }
+
+    #[test]
+    fn test_keys_pressed_while_loading() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+
+        // 停止キー以外のキーは、読み込みが終わった後に押された順に処理する
+        assert!(!poll_stop_key(Some('a'), &ui.pending_keys));
+        assert!(!poll_stop_key(None, &ui.pending_keys));
+        assert!(!poll_stop_key(Some('b'), &ui.pending_keys));
+        assert!(poll_stop_key(Some(STOP_KEY), &ui.pending_keys));
+
+        ui.focus = Focus::AddressBar;
+        ui.handle_key_input(&cancellable).expect("failed to handle a key");
+        ui.handle_key_input(&cancellable).expect("failed to handle a key");
+        ui.handle_key_input(&cancellable).expect("failed to handle a key");
+        assert_eq!("ab", ui.input_url);
+        assert!(ui.pending_keys.borrow().is_empty());
+    }
+
+    fn refreshing(_browser: &Browser, url: String) -> Result<NavigationResult, Error> {
+        let html = match url.as_str() {
+            "http://example.com/" => {
+                "<html><head><meta http-equiv=\"refresh\" content=\"0;url=/next\"></head></html>"
+            }
+            _ => "<html><body>next</body></html>",
+        };
+        navigation_result(&url, html)
+    }
+
+    #[test]
+    fn test_meta_refresh() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.start_navigation(&refreshing, "http://example.com/".to_string())
+            .expect("failed to navigate");
+        let page = ui.browser.borrow().current_page();
+        assert_eq!(
+            Some("http://example.com/next".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+        assert_eq!(0, ui.meta_refreshes);
+    }
+
+    #[test]
+    fn test_hovered_link() {
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.browser
+            .borrow_mut()
+            .load_from_str("<html><body><a href=\"http://example.com/next\">next</a></body></html>");
+        ui.update_ui().expect("failed to update ui");
+
+        let content_top = ui.config.title_bar_height + ui.config.toolbar_height;
+        ui.update_hovered_link((1, content_top + 1))
+            .expect("failed to update the hovered link");
+        assert_eq!(Some("http://example.com/next".to_string()), ui.hovered_link);
+        assert!(calls.borrow().contains(&DrawCall::DrawString {
+            color: ui.config.text_color,
+            x: ui.config.window_padding,
+            y: ui.config.status_bar_y() + 2,
+            text: "http://example.com/next".to_string(),
+            size: StringSize::Medium,
+            underline: false,
+        }));
+
+        // ツールバーの上やリンクのない場所では消える
+        ui.update_hovered_link((1, 1)).expect("failed to update the hovered link");
+        assert_eq!(None, ui.hovered_link);
+        ui.update_hovered_link((1, content_top + 1)).expect("failed to update the hovered link");
+        ui.update_hovered_link((1, content_top + 100))
+            .expect("failed to update the hovered link");
+        assert_eq!(None, ui.hovered_link);
+    }
+
+    #[test]
+    fn test_status_text() {
+        assert_eq!("Ready", status_text(&None, &None, LoadState::Idle));
+        assert_eq!(
+            "Loading...",
+            status_text(
+                &None,
+                &None,
+                LoadState::Loading {
+                    bytes_received: 10,
+                    content_length: None
+                }
+            )
+        );
+        assert_eq!(
+            "Loading... 10/40 bytes",
+            status_text(
+                &None,
+                &None,
+                LoadState::Loading {
+                    bytes_received: 10,
+                    content_length: Some(40)
+                }
+            )
+        );
+        assert_eq!("Done", status_text(&None, &None, LoadState::Complete));
+        assert_eq!("Failed to load the page", status_text(&None, &None, LoadState::Failed));
+        assert_eq!(
+            "http://example.com/",
+            status_text(&Some("http://example.com/".to_string()), &None, LoadState::Complete)
+        );
+        assert_eq!(
+            "Redirect without a Location header",
+            status_text(
+                &None,
+                &Some("Redirect without a Location header".to_string()),
+                LoadState::Complete
+            )
+        );
+    }
+
+    #[test]
+    fn test_status_bar() {
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.update_status_bar().expect("failed to update the status bar");
+        assert_eq!("Ready", ui.status_text);
+        assert!(calls.borrow().contains(&DrawCall::DrawString {
+            color: ui.config.text_color,
+            x: ui.config.window_padding,
+            y: ui.config.status_bar_y() + 2,
+            text: "Ready".to_string(),
+            size: StringSize::Medium,
+            underline: false,
+        }));
+
+        // 変わっていなければ描き直さない
+        calls.borrow_mut().clear();
+        ui.update_status_bar().expect("failed to update the status bar");
+        assert!(calls.borrow().is_empty());
+
+        let page = ui.browser.borrow().current_page();
+        page.borrow_mut().set_load_state(LoadState::Failed);
+        ui.update_status_bar().expect("failed to update the status bar");
+        assert!(calls.borrow().contains(&DrawCall::DrawString {
+            color: ui.config.text_color,
+            x: ui.config.window_padding,
+            y: ui.config.status_bar_y() + 2,
+            text: "Failed to load the page".to_string(),
+            size: StringSize::Medium,
+            underline: false,
+        }));
+    }
+
+    fn home(_browser: &Browser, url: String) -> Result<NavigationResult, Error> {
+        assert_eq!("http://example.com/home", url);
+        let html = "<html><body>home</body></html>";
+        navigation_result(&url, html)
+    }
+
+    fn unreachable_network(_browser: &Browser, url: String) -> Result<NavigationResult, Error> {
+        panic!("{} should not be fetched", url);
+    }
+
+    #[test]
+    fn test_start_page() {
+        // 既定では about:blank をネットワークにアクセスせずに表示する
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.open(&unreachable_network).expect("failed to load the start page");
+        let page = ui.browser.borrow().current_page();
+        assert_eq!(LoadState::Complete, page.borrow().load_state());
+        assert_eq!(ABOUT_BLANK, ui.input_url);
+
+        let browser = Browser::new();
+        browser.borrow_mut().set_home_url("http://example.com/home".to_string());
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut ui = WasabiUI::with_drawer(
+            browser,
+            UiConfig::default(),
+            Box::new(RecordingDrawer::new(calls.clone())),
+        );
+        ui.open(&home).expect("failed to load the start page");
+        let page = ui.browser.borrow().current_page();
+        assert_eq!(
+            Some("http://example.com/home".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+        assert_eq!("http://example.com/home", ui.input_url);
+    }
+
+    #[test]
+    fn test_home_action() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.browser
+            .borrow_mut()
+            .set_home_url("http://example.com/home".to_string());
+        ui.browser.borrow_mut().load_from_str("<html><body>other</body></html>");
+
+        ui.go_home(&home).expect("failed to go home");
+        let page = ui.browser.borrow().current_page();
+        assert_eq!(
+            Some("http://example.com/home".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+        assert_eq!(
+            vec!["http://example.com/home".to_string()],
+            ui.browser.borrow().history().entries()
+        );
+    }
+
+    fn bad_port(_browser: &Browser, url: String) -> Result<NavigationResult, Error> {
+        let url = Url::new(url).parse().map_err(Error::UnexpectedInput)?;
+        url.port_number()?;
+        unreachable!("the port of the test URL is invalid");
+    }
+
+    #[test]
+    fn test_error_page() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.start_navigation(&bad_port, "http://example.com:abc/".to_string())
+            .expect("a failed navigation should show an error page");
+
+        let page = ui.browser.borrow().current_page();
+        assert_eq!(LoadState::Failed, page.borrow().load_state());
+        assert_eq!("Error", page.borrow().title());
+        assert!(ui.browser.borrow().history().is_empty());
+    }
+
+    fn titled(_browser: &Browser, url: String) -> Result<NavigationResult, Error> {
+        navigation_result(&url, "<html><head><title>Sample</title></head><body>text</body></html>")
+    }
+
+    #[test]
+    fn test_window_title() {
+        assert_eq!("saba", window_title(""));
+        assert_eq!("Sample - saba", window_title("Sample"));
+
+        let (mut ui, calls) = create_ui(UiConfig::default());
+        ui.start_navigation(&titled, "http://example.com/".to_string())
+            .expect("failed to navigate");
+        assert_eq!("Sample - saba", ui.window_title);
+        let reopened = DrawCall::Reopen {
+            title: "Sample - saba".to_string(),
+            x: ui.window_x,
+            y: ui.window_y,
+        };
+        assert!(calls.borrow().contains(&reopened));
+
+        // タイトルが変わらなければ、ウィンドウを開き直さない
+        ui.start_navigation(&titled, "http://example.com/".to_string())
+            .expect("failed to navigate");
+        assert_eq!(1, calls.borrow().iter().filter(|call| **call == reopened).count());
+    }
+
+    fn redirecting(_browser: &Browser, _url: String) -> Result<NavigationResult, Error> {
+        let url = Url::new("http://example.com/moved".to_string())
+            .parse()
+            .map_err(Error::UnexpectedInput)?;
+        let html = "<html><body>moved</body></html>";
+        let response = HttpResponse::new(format!(
+            "HTTP/1.1 200 OK\nContent-Length: {}\n\n{}",
+            html.len(),
+            html
+        ))?;
+        Ok(NavigationResult::new(
+            url,
+            response,
+            vec!["Stopped after 5 redirects".to_string()],
+        ))
+    }
+
+    #[test]
+    fn test_redirected_navigation() {
+        let (mut ui, _calls) = create_ui(UiConfig::default());
+        ui.start_navigation(&redirecting, "http://example.com/".to_string())
+            .expect("failed to navigate");
+
+        assert_eq!("http://example.com/moved", ui.input_url);
+        assert_eq!(
+            vec!["http://example.com/moved".to_string()],
+            ui.browser.borrow().history().entries()
+        );
+        assert_eq!("Stopped after 5 redirects", ui.status_text);
+
+        let events = ui.browser.borrow().event_log().borrow().events();
+        assert_eq!(
+            Some(&LogEvent::NavigationStarted("http://example.com/".to_string())),
+            events.first()
+        );
+        assert_eq!(
+            Some(&LogEvent::LoadCompleted("http://example.com/moved".to_string())),
+            events.last()
+        );
+    }
+
+    #[test]
+    fn test_event_log_clock() {
+        let (ui, _calls) = create_ui(UiConfig::default());
+        clock::tick();
+        let before = clock::ticks();
+        let event_log = ui.browser.borrow().event_log();
+        event_log
+            .borrow_mut()
+            .push(LogEvent::NavigationStarted("http://example.com/".to_string()));
+
+        let entries = event_log.borrow().entries();
+        assert!(entries[0].timestamp() >= before);
+        assert!(before > 0);
     }
 }
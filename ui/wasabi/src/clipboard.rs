@@ -0,0 +1,35 @@
+use alloc::string::String;
+use core::fmt::Debug;
+
+/// The source of the text pasted into the address bar.
+pub trait Clipboard: Debug {
+    fn read_text(&mut self) -> Option<String>;
+}
+
+/// noli にはクリップボードを読む API がないため、常に空のクリップボードとして振る舞う
+#[derive(Debug)]
+pub struct EmptyClipboard;
+
+impl Clipboard for EmptyClipboard {
+    fn read_text(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// A clipboard that always holds `text`. Used by tests and host builds.
+#[derive(Debug)]
+pub struct TextClipboard {
+    text: String,
+}
+
+impl TextClipboard {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+}
+
+impl Clipboard for TextClipboard {
+    fn read_text(&mut self) -> Option<String> {
+        Some(self.text.clone())
+    }
+}
@@ -0,0 +1,27 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// noli には時刻を取得する API がないので、イベントループを回った回数を時計の代わりに使う
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Advances the clock. The UI calls this once per iteration of its event loop.
+pub fn tick() {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Returns the number of event loop iterations since startup.
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick() {
+        let before = ticks();
+        tick();
+        tick();
+        assert!(ticks() >= before + 2);
+    }
+}
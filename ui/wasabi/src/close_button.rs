@@ -0,0 +1,100 @@
+use noli::bitmap::bitmap_draw_rect;
+use noli::rect::Rect;
+use noli::sheet::Sheet;
+use crate::config::UiConfig;
+use saba_core::constants::{DARKGRAY, LIGHTGRAY};
+
+pub static CLOSE_BUTTON_SIZE: i64 = 12;
+static CLOSE_BUTTON_MARGIN: i64 = 6;
+
+/// The close box drawn at the right end of the title bar.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CloseButton {
+    sheet: Sheet,
+}
+
+impl CloseButton {
+    pub fn new() -> Self {
+        let mut sheet = Sheet::new(Rect::new(0, 0, CLOSE_BUTTON_SIZE, CLOSE_BUTTON_SIZE).unwrap());
+        let bitmap = sheet.bitmap();
+        bitmap_draw_rect(bitmap, DARKGRAY, 0, 0, CLOSE_BUTTON_SIZE, CLOSE_BUTTON_SIZE)
+            .expect("failed to draw a close button");
+        bitmap_draw_rect(bitmap, LIGHTGRAY, 1, 1, CLOSE_BUTTON_SIZE - 2, CLOSE_BUTTON_SIZE - 2)
+            .expect("failed to draw a close button");
+        // "×" の印を描画
+        for i in 3..CLOSE_BUTTON_SIZE - 3 {
+            bitmap_draw_rect(bitmap, DARKGRAY, i, i, 1, 1).expect("failed to draw a close button");
+            bitmap_draw_rect(bitmap, DARKGRAY, CLOSE_BUTTON_SIZE - 1 - i, i, 1, 1)
+                .expect("failed to draw a close button");
+        }
+        Self { sheet }
+    }
+
+    /// Moves the close box so that it follows the window placed at (`window_x`, `window_y`).
+    pub fn set_window_position(&mut self, window_x: i64, window_y: i64, config: &UiConfig) {
+        let (x, y) = close_button_position(config);
+        self.sheet.set_position(window_x + x, window_y + y);
+    }
+
+    pub fn flush(&mut self) {
+        self.sheet.flush();
+    }
+}
+
+/// Returns the top-left corner of the close box relative to the window.
+fn close_button_position(config: &UiConfig) -> (i64, i64) {
+    (
+        config.window_width - CLOSE_BUTTON_SIZE - CLOSE_BUTTON_MARGIN,
+        (config.title_bar_height - CLOSE_BUTTON_SIZE) / 2,
+    )
+}
+
+/// Returns true if `relative_pos`, a position relative to the window, is on the close box.
+pub fn is_close_button_hit(relative_pos: (i64, i64), config: &UiConfig) -> bool {
+    let (x, y) = close_button_position(config);
+    x <= relative_pos.0
+        && relative_pos.0 < x + CLOSE_BUTTON_SIZE
+        && y <= relative_pos.1
+        && relative_pos.1 < y + CLOSE_BUTTON_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit() {
+        let config = UiConfig::default();
+        let (x, y) = close_button_position(&config);
+        assert!(is_close_button_hit((x, y), &config));
+        assert!(is_close_button_hit((x + CLOSE_BUTTON_SIZE / 2, y + CLOSE_BUTTON_SIZE / 2), &config));
+        assert!(is_close_button_hit((x + CLOSE_BUTTON_SIZE - 1, y + CLOSE_BUTTON_SIZE - 1), &config));
+    }
+
+    #[test]
+    fn test_miss() {
+        let config = UiConfig::default();
+        let (x, y) = close_button_position(&config);
+        assert!(!is_close_button_hit((x - 1, y), &config));
+        assert!(!is_close_button_hit((x, y - 1), &config));
+        assert!(!is_close_button_hit((x + CLOSE_BUTTON_SIZE, y), &config));
+        assert!(!is_close_button_hit((x, y + CLOSE_BUTTON_SIZE), &config));
+        // タイトルバーの左端やツールバーはクローズボタンではない
+        assert!(!is_close_button_hit((0, 0), &config));
+        assert!(!is_close_button_hit((x, config.title_bar_height + 1), &config));
+    }
+
+    #[test]
+    fn test_custom_title_bar() {
+        let config = UiConfig {
+            window_width: 300,
+            title_bar_height: 40,
+            ..UiConfig::default()
+        };
+        // タイトルバーの高さの中央に置く
+        assert_eq!(
+            (300 - CLOSE_BUTTON_SIZE - CLOSE_BUTTON_MARGIN, (40 - CLOSE_BUTTON_SIZE) / 2),
+            close_button_position(&config)
+        );
+    }
+}
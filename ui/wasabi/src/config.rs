@@ -0,0 +1,80 @@
+use saba_core::constants::{
+    ADDRESSBAR_HEIGHT, BLACK, DARKGRAY, GREY, LIGHTGRAY, PROGRESS_BAR_HEIGHT, STATUS_BAR_HEIGHT,
+    TITLE_BAR_HEIGHT, TOOLBAR_HEIGHT, WHITE, WINDOW_HEIGHT, WINDOW_INIT_X_POS, WINDOW_INIT_Y_POS,
+    WINDOW_PADDING, WINDOW_WIDTH,
+};
+
+/// The size, position and colors of the browser window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiConfig {
+    pub window_x: i64,
+    pub window_y: i64,
+    pub window_width: i64,
+    pub window_height: i64,
+    pub window_padding: i64,
+    pub title_bar_height: i64,
+    pub toolbar_height: i64,
+    pub addressbar_height: i64,
+    pub progress_bar_height: i64,
+    pub status_bar_height: i64,
+    pub background_color: u32,
+    pub toolbar_color: u32,
+    pub text_color: u32,
+    pub border_color: u32,
+    pub shadow_color: u32,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            window_x: WINDOW_INIT_X_POS,
+            window_y: WINDOW_INIT_Y_POS,
+            window_width: WINDOW_WIDTH,
+            window_height: WINDOW_HEIGHT,
+            window_padding: WINDOW_PADDING,
+            title_bar_height: TITLE_BAR_HEIGHT,
+            toolbar_height: TOOLBAR_HEIGHT,
+            addressbar_height: ADDRESSBAR_HEIGHT,
+            progress_bar_height: PROGRESS_BAR_HEIGHT,
+            status_bar_height: STATUS_BAR_HEIGHT,
+            background_color: WHITE,
+            toolbar_color: LIGHTGRAY,
+            text_color: BLACK,
+            border_color: GREY,
+            shadow_color: DARKGRAY,
+        }
+    }
+}
+
+impl UiConfig {
+    pub fn content_area_width(&self) -> i64 {
+        self.window_width - self.window_padding * 2
+    }
+
+    pub fn content_area_height(&self) -> i64 {
+        self.window_height
+            - self.title_bar_height
+            - self.toolbar_height
+            - self.status_bar_height
+            - self.window_padding * 2
+    }
+
+    /// The y coordinate of the status bar at the bottom of the window, relative to the area
+    /// below the title bar.
+    pub fn status_bar_y(&self) -> i64 {
+        self.window_height - self.title_bar_height - self.status_bar_height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use saba_core::constants::{CONTENT_AREA_HEIGHT, CONTENT_AREA_WIDTH};
+
+    #[test]
+    fn test_default_content_area() {
+        let config = UiConfig::default();
+        assert_eq!(CONTENT_AREA_WIDTH, config.content_area_width());
+        assert_eq!(CONTENT_AREA_HEIGHT, config.content_area_height());
+    }
+}
@@ -0,0 +1,199 @@
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use noli::error::Result as OsResult;
+use noli::rect::Rect;
+use noli::window::{StringSize, Window};
+
+/// The drawing operations the UI uses, so that tests can replace the real window.
+pub trait Drawer: Debug {
+    fn fill_rect(&mut self, color: u32, x: i64, y: i64, width: i64, height: i64) -> OsResult<()>;
+
+    fn draw_line(&mut self, color: u32, x0: i64, y0: i64, x1: i64, y1: i64) -> OsResult<()>;
+
+    fn draw_string(
+        &mut self,
+        color: u32,
+        x: i64,
+        y: i64,
+        s: &str,
+        size: StringSize,
+        underline: bool,
+    ) -> OsResult<()>;
+
+    fn flush_area(&mut self, rect: Rect);
+
+    fn flush(&mut self);
+
+    /// Replaces the window with a new one that has `title` in its title bar, because a Wasabi
+    /// window can't be moved or renamed. Everything has to be drawn again afterwards.
+    fn reopen(
+        &mut self,
+        title: &str,
+        background_color: u32,
+        x: i64,
+        y: i64,
+        width: i64,
+        height: i64,
+    ) -> OsResult<()>;
+}
+
+impl Drawer for Window {
+    fn fill_rect(&mut self, color: u32, x: i64, y: i64, width: i64, height: i64) -> OsResult<()> {
+        Window::fill_rect(self, color, x, y, width, height)
+    }
+
+    fn draw_line(&mut self, color: u32, x0: i64, y0: i64, x1: i64, y1: i64) -> OsResult<()> {
+        Window::draw_line(self, color, x0, y0, x1, y1)
+    }
+
+    fn draw_string(
+        &mut self,
+        color: u32,
+        x: i64,
+        y: i64,
+        s: &str,
+        size: StringSize,
+        underline: bool,
+    ) -> OsResult<()> {
+        Window::draw_string(self, color, x, y, s, size, underline)
+    }
+
+    fn flush_area(&mut self, rect: Rect) {
+        Window::flush_area(self, rect);
+    }
+
+    fn flush(&mut self) {
+        Window::flush(self);
+    }
+
+    fn reopen(
+        &mut self,
+        title: &str,
+        background_color: u32,
+        x: i64,
+        y: i64,
+        width: i64,
+        height: i64,
+    ) -> OsResult<()> {
+        *self = Window::new(title.to_string(), background_color, x, y, width, height)?;
+        Ok(())
+    }
+}
+
+/// A call made to a `Drawer`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCall {
+    FillRect {
+        color: u32,
+        x: i64,
+        y: i64,
+        width: i64,
+        height: i64,
+    },
+    DrawLine {
+        color: u32,
+        x0: i64,
+        y0: i64,
+        x1: i64,
+        y1: i64,
+    },
+    DrawString {
+        color: u32,
+        x: i64,
+        y: i64,
+        text: String,
+        size: StringSize,
+        underline: bool,
+    },
+    FlushArea(Rect),
+    Flush,
+    Reopen {
+        title: String,
+        x: i64,
+        y: i64,
+    },
+}
+
+/// A `Drawer` that only records the calls made to it.
+#[derive(Debug)]
+pub struct RecordingDrawer {
+    calls: Rc<RefCell<Vec<DrawCall>>>,
+}
+
+impl RecordingDrawer {
+    pub fn new(calls: Rc<RefCell<Vec<DrawCall>>>) -> Self {
+        Self { calls }
+    }
+}
+
+impl Drawer for RecordingDrawer {
+    fn fill_rect(&mut self, color: u32, x: i64, y: i64, width: i64, height: i64) -> OsResult<()> {
+        self.calls.borrow_mut().push(DrawCall::FillRect {
+            color,
+            x,
+            y,
+            width,
+            height,
+        });
+        Ok(())
+    }
+
+    fn draw_line(&mut self, color: u32, x0: i64, y0: i64, x1: i64, y1: i64) -> OsResult<()> {
+        self.calls.borrow_mut().push(DrawCall::DrawLine {
+            color,
+            x0,
+            y0,
+            x1,
+            y1,
+        });
+        Ok(())
+    }
+
+    fn draw_string(
+        &mut self,
+        color: u32,
+        x: i64,
+        y: i64,
+        s: &str,
+        size: StringSize,
+        underline: bool,
+    ) -> OsResult<()> {
+        self.calls.borrow_mut().push(DrawCall::DrawString {
+            color,
+            x,
+            y,
+            text: s.to_string(),
+            size,
+            underline,
+        });
+        Ok(())
+    }
+
+    fn flush_area(&mut self, rect: Rect) {
+        self.calls.borrow_mut().push(DrawCall::FlushArea(rect));
+    }
+
+    fn flush(&mut self) {
+        self.calls.borrow_mut().push(DrawCall::Flush);
+    }
+
+    fn reopen(
+        &mut self,
+        title: &str,
+        _background_color: u32,
+        x: i64,
+        y: i64,
+        _width: i64,
+        _height: i64,
+    ) -> OsResult<()> {
+        self.calls.borrow_mut().push(DrawCall::Reopen {
+            title: title.to_string(),
+            x,
+            y,
+        });
+        Ok(())
+    }
+}
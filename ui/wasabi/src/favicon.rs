@@ -0,0 +1,32 @@
+use saba_core::constants::{GREY, RED, WHITE};
+use saba_core::renderer::page::LoadState;
+
+pub static FAVICON_SIZE: i64 = 12;
+
+/// Returns the color of the placeholder favicon shown while a page is in `state`.
+pub fn favicon_color(state: LoadState) -> u32 {
+    match state {
+        LoadState::Idle | LoadState::Complete => WHITE,
+        LoadState::Loading { .. } => GREY,
+        LoadState::Failed => RED,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_favicon_color() {
+        assert_eq!(WHITE, favicon_color(LoadState::Idle));
+        assert_eq!(
+            GREY,
+            favicon_color(LoadState::Loading {
+                bytes_received: 0,
+                content_length: None,
+            })
+        );
+        assert_eq!(WHITE, favicon_color(LoadState::Complete));
+        assert_eq!(RED, favicon_color(LoadState::Failed));
+    }
+}
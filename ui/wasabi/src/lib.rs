@@ -2,5 +2,15 @@
 
 extern crate alloc;
 
+mod address_bar;
 pub mod app;
-mod cursor;
\ No newline at end of file
+pub mod clipboard;
+pub mod clock;
+mod close_button;
+pub mod config;
+mod cursor;
+pub mod drawer;
+mod favicon;
+mod scroll;
+pub mod text;
+mod zoom;
@@ -0,0 +1,102 @@
+/// Wasabi の read_key は押されたキーを文字として返すだけで、PageUp などのキーにはコードがない。
+/// そのため、less や Emacs と同じ Ctrl との組み合わせの制御文字を使う（アドレスバーの Ctrl+V と同じ）
+/// Ctrl+A
+static KEY_HOME: char = '\u{01}';
+/// Ctrl+E
+static KEY_END: char = '\u{05}';
+/// Ctrl+B
+static KEY_PAGE_UP: char = '\u{02}';
+/// Ctrl+F
+static KEY_PAGE_DOWN: char = '\u{06}';
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScrollKey {
+    PageUp,
+    PageDown,
+    Home,
+    End,
+}
+
+impl ScrollKey {
+    pub fn from_char(c: char) -> Option<Self> {
+        if c == KEY_PAGE_UP {
+            Some(ScrollKey::PageUp)
+        } else if c == KEY_PAGE_DOWN {
+            Some(ScrollKey::PageDown)
+        } else if c == KEY_HOME {
+            Some(ScrollKey::Home)
+        } else if c == KEY_END {
+            Some(ScrollKey::End)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the scroll offset after `key` is pressed, clamped so that the viewport stays within
+/// the content.
+pub fn scroll_by_key(
+    key: ScrollKey,
+    scroll_y: i64,
+    content_height: i64,
+    viewport_height: i64,
+) -> i64 {
+    let max_scroll_y = if content_height > viewport_height {
+        content_height - viewport_height
+    } else {
+        0
+    };
+
+    let next = match key {
+        ScrollKey::PageUp => scroll_y - viewport_height,
+        ScrollKey::PageDown => scroll_y + viewport_height,
+        ScrollKey::Home => 0,
+        ScrollKey::End => max_scroll_y,
+    };
+
+    next.clamp(0, max_scroll_y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_char() {
+        assert_eq!(Some(ScrollKey::PageUp), ScrollKey::from_char(KEY_PAGE_UP));
+        assert_eq!(Some(ScrollKey::PageDown), ScrollKey::from_char(KEY_PAGE_DOWN));
+        assert_eq!(Some(ScrollKey::Home), ScrollKey::from_char(KEY_HOME));
+        assert_eq!(Some(ScrollKey::End), ScrollKey::from_char(KEY_END));
+        assert_eq!(None, ScrollKey::from_char('a'));
+    }
+
+    #[test]
+    fn test_page_down() {
+        assert_eq!(300, scroll_by_key(ScrollKey::PageDown, 0, 1000, 300));
+        assert_eq!(600, scroll_by_key(ScrollKey::PageDown, 300, 1000, 300));
+        // 末尾より先には進まない
+        assert_eq!(700, scroll_by_key(ScrollKey::PageDown, 600, 1000, 300));
+        assert_eq!(700, scroll_by_key(ScrollKey::PageDown, 700, 1000, 300));
+    }
+
+    #[test]
+    fn test_page_up() {
+        assert_eq!(400, scroll_by_key(ScrollKey::PageUp, 700, 1000, 300));
+        assert_eq!(0, scroll_by_key(ScrollKey::PageUp, 100, 1000, 300));
+        assert_eq!(0, scroll_by_key(ScrollKey::PageUp, 0, 1000, 300));
+    }
+
+    #[test]
+    fn test_home_and_end() {
+        assert_eq!(0, scroll_by_key(ScrollKey::Home, 500, 1000, 300));
+        assert_eq!(700, scroll_by_key(ScrollKey::End, 0, 1000, 300));
+    }
+
+    #[test]
+    fn test_short_content() {
+        // コンテンツがビューポートより短い場合はスクロールしない
+        assert_eq!(0, scroll_by_key(ScrollKey::PageDown, 0, 100, 300));
+        assert_eq!(0, scroll_by_key(ScrollKey::End, 0, 100, 300));
+        assert_eq!(0, scroll_by_key(ScrollKey::PageUp, 0, 100, 300));
+    }
+}
@@ -0,0 +1,49 @@
+use noli::window::StringSize;
+use saba_core::constants::{CHAR_HEIGHT, CHAR_WIDTH};
+
+/// Returns the width in pixels of `s` drawn with the Wasabi font at `size`.
+/// Every glyph has the same width, scaled by the font size.
+pub fn text_width(s: &str, size: StringSize) -> i64 {
+    s.chars().map(|_| char_width(size)).sum()
+}
+
+pub fn char_width(size: StringSize) -> i64 {
+    match size {
+        StringSize::Medium => CHAR_WIDTH,
+        StringSize::Large => CHAR_WIDTH * 2,
+        StringSize::XLarge => CHAR_WIDTH * 3,
+    }
+}
+
+pub fn char_height(size: StringSize) -> i64 {
+    match size {
+        StringSize::Medium => CHAR_HEIGHT,
+        StringSize::Large => CHAR_HEIGHT * 2,
+        StringSize::XLarge => CHAR_HEIGHT * 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(0, text_width("", StringSize::Medium));
+        assert_eq!(0, text_width("", StringSize::Large));
+    }
+
+    #[test]
+    fn test_medium() {
+        assert_eq!(8, text_width("a", StringSize::Medium));
+        assert_eq!(40, text_width("hello", StringSize::Medium));
+        assert_eq!(144, text_width("http://example.com", StringSize::Medium));
+    }
+
+    #[test]
+    fn test_large() {
+        assert_eq!(16, text_width("a", StringSize::Large));
+        assert_eq!(80, text_width("hello", StringSize::Large));
+        assert_eq!(176, text_width("Hello World", StringSize::Large));
+    }
+}
@@ -0,0 +1,63 @@
+use saba_core::constants::{DEFAULT_ZOOM, ZOOM_STEP};
+use saba_core::renderer::layout::layout_object::clamp_zoom;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ZoomKey {
+    In,
+    Out,
+    Reset,
+}
+
+impl ZoomKey {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '+' | '=' => Some(ZoomKey::In),
+            '-' => Some(ZoomKey::Out),
+            '0' => Some(ZoomKey::Reset),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the zoom level in percent after `key` is pressed, clamped to the supported range.
+pub fn zoom_by_key(key: ZoomKey, zoom: i64) -> i64 {
+    let next = match key {
+        ZoomKey::In => zoom + ZOOM_STEP,
+        ZoomKey::Out => zoom - ZOOM_STEP,
+        ZoomKey::Reset => DEFAULT_ZOOM,
+    };
+
+    clamp_zoom(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use saba_core::constants::{MAX_ZOOM, MIN_ZOOM};
+
+    #[test]
+    fn test_from_char() {
+        assert_eq!(Some(ZoomKey::In), ZoomKey::from_char('+'));
+        assert_eq!(Some(ZoomKey::In), ZoomKey::from_char('='));
+        assert_eq!(Some(ZoomKey::Out), ZoomKey::from_char('-'));
+        assert_eq!(Some(ZoomKey::Reset), ZoomKey::from_char('0'));
+        assert_eq!(None, ZoomKey::from_char('a'));
+    }
+
+    #[test]
+    fn test_zoom_in_and_out() {
+        assert_eq!(110, zoom_by_key(ZoomKey::In, 100));
+        assert_eq!(90, zoom_by_key(ZoomKey::Out, 100));
+    }
+
+    #[test]
+    fn test_clamp() {
+        assert_eq!(MAX_ZOOM, zoom_by_key(ZoomKey::In, MAX_ZOOM));
+        assert_eq!(MIN_ZOOM, zoom_by_key(ZoomKey::Out, MIN_ZOOM));
+    }
+
+    #[test]
+    fn test_reset() {
+        assert_eq!(100, zoom_by_key(ZoomKey::Reset, 250));
+    }
+}